@@ -0,0 +1,276 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, GenericArgument, LitChar, LitStr, PathArguments,
+          Type};
+
+use {help, kebab_case};
+
+/// The kind of argument of a field, from its type.
+enum Shape<'a> {
+    Switch,
+    Optional(&'a Type),
+    Multiple(&'a Type),
+    Required(&'a Type),
+}
+
+/// The settings of a field from `#[arg(...)]`.
+#[derive(Default)]
+struct Settings {
+    given: usize,
+    flatten: bool,
+    positional: bool,
+    short: Option<LitChar>,
+    long: Option<LitStr>,
+    count: bool,
+    zero_or_more: bool,
+    required: bool,
+    choices: bool,
+    param: Option<LitStr>,
+    default: Option<LitStr>,
+    env: Option<LitStr>,
+}
+
+/// Reads the settings of `#[arg(...)]` attributes.
+fn settings(attrs: &[Attribute]) -> syn::Result<Settings> {
+    let mut settings = Settings::default();
+    for attr in attrs {
+        if !attr.path().is_ident("arg") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            settings.given += 1;
+            let path = &meta.path;
+            if path.is_ident("flatten") {
+                settings.flatten = true;
+            } else if path.is_ident("positional") {
+                settings.positional = true;
+            } else if path.is_ident("count") {
+                settings.count = true;
+            } else if path.is_ident("zero_or_more") {
+                settings.zero_or_more = true;
+            } else if path.is_ident("required") {
+                settings.required = true;
+            } else if path.is_ident("choices") {
+                settings.choices = true;
+            } else if path.is_ident("short") {
+                settings.short = Some(meta.value()?.parse()?);
+            } else if path.is_ident("long") {
+                settings.long = Some(meta.value()?.parse()?);
+            } else if path.is_ident("param") {
+                settings.param = Some(meta.value()?.parse()?);
+            } else if path.is_ident("default") {
+                settings.default = Some(meta.value()?.parse()?);
+            } else if path.is_ident("env") {
+                settings.env = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown argument setting"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(settings)
+}
+
+/// Returns `T` if the type is `<wrapper><T>` (like `Option<T>`).
+fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match *ty {
+        Type::Path(ref ty) if ty.qself.is_none() => &ty.path,
+        _ => return None,
+    };
+    let last = path.segments.last()?;
+    if last.ident != wrapper {
+        return None;
+    }
+    match last.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                GenericArgument::Type(ref inner) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the kind of argument for a field of the type.
+fn shape(ty: &Type) -> Shape<'_> {
+    if let Some(inner) = inner_type(ty, "Option") {
+        return Shape::Optional(inner);
+    }
+    if let Some(inner) = inner_type(ty, "Vec") {
+        return Shape::Multiple(inner);
+    }
+    match *ty {
+        Type::Path(ref path) if path.path.is_ident("bool") => Shape::Switch,
+        _ => Shape::Required(ty),
+    }
+}
+
+/// Unwraps the value of a required argument, which is missing if the
+/// arguments do not come from a parse.
+fn required(name: &str, value: TokenStream) -> TokenStream {
+    quote! {
+        match #value {
+            ::std::option::Option::Some(value) => value,
+            ::std::option::Option::None => {
+                return ::std::result::Result::Err(format!("the argument '{}' is missing", #name));
+            }
+        }
+    }
+}
+
+/// Implements `FromArgs` for the struct.
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Named(ref fields) => &fields.named,
+                _ => return Err(syn::Error::new_spanned(input, "the fields must be named")),
+            }
+        }
+        _ => return Err(syn::Error::new_spanned(input, "FromArgs can only be derived for structs")),
+    };
+
+    let mut defs = Vec::new();
+    let mut idents = Vec::new();
+    let mut values = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named fields have names");
+        let settings = settings(&field.attrs)?;
+        let name = match settings.long {
+            Some(ref long) => long.value(),
+            None => kebab_case(&ident.to_string()),
+        };
+        let fail = |message: &str| Err(syn::Error::new_spanned(field, message));
+        if settings.flatten {
+            let ty = &field.ty;
+            if settings.given > 1 {
+                return fail("flattened fields have no other settings");
+            }
+            defs.push(quote!(defs.extend(<#ty as ::argonaut::FromArgs>::args());));
+            idents.push(ident);
+            values.push(quote!(<#ty as ::argonaut::FromArgs>::from_args(args)?));
+            continue;
+        }
+        let shape = shape(&field.ty);
+        let multiple = matches!(shape, Shape::Multiple(_));
+        if (settings.zero_or_more || settings.required) && !multiple {
+            return fail("only Vec fields can be 'zero_or_more' or 'required'");
+        }
+
+        let (mut def, value) = if settings.positional {
+            if settings.short.is_some() || settings.count {
+                return fail("positional arguments have no flags");
+            }
+            if settings.default.is_some() || settings.env.is_some() {
+                return fail("positional arguments have no default or environment variable");
+            }
+            match shape {
+                Shape::Required(ty) => {
+                    (quote!(::argonaut::Arg::positional(#name).set_type::<#ty>()),
+                     required(&name, quote!(args.single_as::<#ty>(#name)?)))
+                }
+                Shape::Multiple(ty) if settings.required => {
+                    (quote!(::argonaut::Arg::required_trail(#name).set_type::<#ty>()),
+                     required(&name, quote!(args.multiple_as::<#ty>(#name)?)))
+                }
+                Shape::Multiple(ty) => {
+                    (quote!(::argonaut::Arg::optional_trail(#name).set_type::<#ty>()),
+                     quote!(args.multiple_as::<#ty>(#name)?.unwrap_or_default()))
+                }
+                _ => return fail("positional arguments are a single value or a Vec of values"),
+            }
+        } else {
+            let opt = match settings.short {
+                Some(ref short) => quote!(::argonaut::Arg::named_and_short(#name, #short)),
+                None => quote!(::argonaut::Arg::named(#name)),
+            };
+            match shape {
+                Shape::Required(ty) if settings.count => {
+                    let count = quote! {
+                        <#ty as ::std::convert::TryFrom<usize>>::try_from(args.count(#name))
+                            .map_err(|_| format!("the argument '{}' was given too many times",
+                                                 #name))?
+                    };
+                    (quote!(#opt.count()), count)
+                }
+                _ if settings.count => return fail("counted switches must be integers"),
+                Shape::Switch => (quote!(#opt.switch()), quote!(args.is_present(#name))),
+                Shape::Optional(ty) => {
+                    (quote!(#opt.single().set_type::<#ty>()),
+                     quote!(args.single_as::<#ty>(#name)?))
+                }
+                Shape::Required(ty) => {
+                    let def = if settings.default.is_some() {
+                        quote!(#opt.single().set_type::<#ty>())
+                    } else {
+                        quote!(#opt.single().set_type::<#ty>().set_required())
+                    };
+                    (def, required(&name, quote!(args.single_as::<#ty>(#name)?)))
+                }
+                Shape::Multiple(ty) => {
+                    let def = if settings.zero_or_more {
+                        quote!(#opt.zero_or_more().set_type::<#ty>())
+                    } else {
+                        quote!(#opt.one_or_more().set_type::<#ty>())
+                    };
+                    if settings.required {
+                        (quote!(#def.set_required()),
+                         required(&name, quote!(args.multiple_as::<#ty>(#name)?)))
+                    } else {
+                        (def, quote!(args.multiple_as::<#ty>(#name)?.unwrap_or_default()))
+                    }
+                }
+            }
+        };
+        let switch = settings.count || matches!(shape, Shape::Switch);
+        if settings.choices {
+            let ty = match shape {
+                Shape::Optional(ty) | Shape::Multiple(ty) | Shape::Required(ty) if !switch => ty,
+                _ => return fail("switches cannot have choices"),
+            };
+            def = quote!(#def.set_choices_of::<#ty>());
+        }
+        if settings.param.is_some() && switch {
+            return fail("switches have no parameter");
+        }
+        if settings.default.is_some() && switch {
+            return fail("switches have no default");
+        }
+        if let Some(ref param) = settings.param {
+            def = quote!(#def.set_param(#param));
+        }
+        if let Some(ref default) = settings.default {
+            def = quote!(#def.set_default(#default));
+        }
+        if let Some(ref env) = settings.env {
+            def = quote!(#def.set_env(#env));
+        }
+        if let Some(help) = help(&field.attrs) {
+            def = quote!(#def.set_help(#help));
+        }
+
+        defs.push(quote!(defs.push(::std::convert::Into::into(#def));));
+        idents.push(ident);
+        values.push(value);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::argonaut::FromArgs for #name #ty_generics #where_clause {
+            fn args() -> ::std::vec::Vec<::argonaut::Arg<'static>> {
+                let mut defs = ::std::vec::Vec::new();
+                #(#defs)*
+                defs
+            }
+
+            fn from_args(args: &::argonaut::ParsedArgs)
+                         -> ::std::result::Result<Self, ::std::string::String> {
+                ::std::result::Result::Ok(#name {
+                    #(#idents: #values,)*
+                })
+            }
+        }
+    })
+}
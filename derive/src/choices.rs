@@ -0,0 +1,57 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr};
+
+use kebab_case;
+
+/// Implements `Choices` for the enum.
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => return Err(syn::Error::new_spanned(input, "Choices can only be derived for enums")),
+    };
+    let mut values = Vec::new();
+    let mut idents = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, "choices cannot have fields"));
+        }
+        let mut value = kebab_case(&variant.ident.to_string());
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("choice") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    value = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected 'rename'"))
+                }
+            })?;
+        }
+        if values.contains(&value) {
+            let message = format!("'{}' is already a choice", value);
+            return Err(syn::Error::new_spanned(variant, message));
+        }
+        values.push(value);
+        idents.push(&variant.ident);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::argonaut::Choices for #name #ty_generics #where_clause {
+            fn variants() -> &'static [&'static str] {
+                &[#(#values),*]
+            }
+
+            fn from_str(value: &str) -> ::std::option::Option<Self> {
+                match value {
+                    #(#values => ::std::option::Option::Some(#name::#idents),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,103 @@
+//! Derive macros for argonaut, enabled by its `derive` feature.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+
+mod args;
+mod choices;
+mod subcommand;
+
+use proc_macro::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{Attribute, DeriveInput, Expr, ExprLit, Lit, Meta};
+
+/// Implements `argonaut::Choices` for an enum of unit variants.
+///
+/// The value of a variant is its name in kebab-case, unless it is given with
+/// `#[choice(rename = "...")]`.
+#[proc_macro_derive(Choices, attributes(choice))]
+pub fn derive_choices(input: TokenStream) -> TokenStream {
+    expand(input, choices::derive)
+}
+
+/// Implements `argonaut::FromArgs` for a struct with named fields.
+///
+/// The doc comments of the fields are the help texts, and the arguments are
+/// set up with `#[arg(...)]` as described by `FromArgs`.
+#[proc_macro_derive(FromArgs, attributes(arg))]
+pub fn derive_from_args(input: TokenStream) -> TokenStream {
+    expand(input, args::derive)
+}
+
+/// Implements `argonaut::Subcommand` for an enum whose variants hold a
+/// `FromArgs` struct or nothing.
+///
+/// The doc comments of the variants are the help texts, and the names are the
+/// ones of the variants in kebab-case unless given with
+/// `#[command(rename = "...")]`.
+#[proc_macro_derive(Subcommand, attributes(command))]
+pub fn derive_subcommand(input: TokenStream) -> TokenStream {
+    expand(input, subcommand::derive)
+}
+
+/// Runs the derive on the parsed input, or turns its errors into
+/// `compile_error!` calls.
+///
+/// The errors of syn call `::core::compile_error!`, which a 2015 crate without
+/// `extern crate core` cannot find, so the macro is named without a path.
+fn expand(input: TokenStream,
+          derive: fn(&DeriveInput) -> syn::Result<proc_macro2::TokenStream>)
+          -> TokenStream {
+    match syn::parse(input).and_then(|input| derive(&input)) {
+        Ok(tokens) => tokens.into(),
+        Err(errors) => {
+            let errors = errors.into_iter().map(|err| {
+                let message = err.to_string();
+                quote_spanned!(err.span()=> compile_error!(#message);)
+            });
+            quote!(#(#errors)*).into()
+        }
+    }
+}
+
+/// Returns the doc comment as one line of help text.
+fn help(attrs: &[Attribute]) -> Option<String> {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        if !attr.path().is_ident("doc") {
+            continue;
+        }
+        if let Meta::NameValue(ref doc) = attr.meta {
+            if let Expr::Lit(ExprLit { lit: Lit::Str(ref text), .. }) = doc.value {
+                let text = text.value();
+                let line = text.trim();
+                if !line.is_empty() {
+                    lines.push(line.to_owned());
+                }
+            }
+        }
+    }
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" "))
+    }
+}
+
+/// Converts a name like `DryRun` or `dry_run` to `dry-run`.
+fn kebab_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, ch) in name.chars().enumerate() {
+        if ch == '_' {
+            out.push('-');
+            continue;
+        }
+        if ch.is_uppercase() && i > 0 && !out.ends_with('-') {
+            out.push('-');
+        }
+        out.extend(ch.to_lowercase());
+    }
+    out
+}
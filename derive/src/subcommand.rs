@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr};
+
+use {help, kebab_case};
+
+/// Implements `Subcommand` for the enum.
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => return Err(syn::Error::new_spanned(input, "Subcommand can only be derived for enums")),
+    };
+    let name = &input.ident;
+
+    let mut commands = Vec::new();
+    let mut args = Vec::new();
+    let mut values = Vec::new();
+    for variant in &data.variants {
+        let mut command = kebab_case(&variant.ident.to_string());
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("command") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    command = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected 'rename'"))
+                }
+            })?;
+        }
+        if commands.iter().any(|(other, _)| *other == command) {
+            let message = format!("'{}' is already a command", command);
+            return Err(syn::Error::new_spanned(variant, message));
+        }
+
+        let ident = &variant.ident;
+        match variant.fields {
+            Fields::Unit => {
+                args.push(quote!(#command => ::std::vec::Vec::new()));
+                values.push(quote!(#command => #name::#ident));
+            }
+            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                args.push(quote!(#command => <#ty as ::argonaut::FromArgs>::args()));
+                values.push(quote! {
+                    #command => #name::#ident(<#ty as ::argonaut::FromArgs>::from_args(args)?)
+                });
+            }
+            _ => {
+                let message = "subcommands hold one struct of arguments, or nothing";
+                return Err(syn::Error::new_spanned(variant, message));
+            }
+        }
+        let help = match help(&variant.attrs) {
+            Some(text) => quote!(::std::option::Option::Some(#text)),
+            None => quote!(::std::option::Option::None),
+        };
+        commands.push((command, help));
+    }
+
+    let command_list = commands.iter().map(|(command, help)| quote!((#command, #help)));
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::argonaut::Subcommand for #name #ty_generics #where_clause {
+            fn commands() -> &'static [(&'static str, ::std::option::Option<&'static str>)] {
+                &[#(#command_list),*]
+            }
+
+            fn args(command: &str)
+                    -> ::std::option::Option<::std::vec::Vec<::argonaut::Arg<'static>>> {
+                ::std::option::Option::Some(match command {
+                    #(#args,)*
+                    _ => return ::std::option::Option::None,
+                })
+            }
+
+            fn from_args(command: &str, args: &::argonaut::ParsedArgs)
+                         -> ::std::result::Result<Self, ::std::string::String> {
+                ::std::result::Result::Ok(match command {
+                    #(#values,)*
+                    _ => {
+                        return ::std::result::Result::Err(format!("unknown command '{}'",
+                                                                  command));
+                    }
+                })
+            }
+        }
+    })
+}
@@ -1,59 +1,29 @@
+#[macro_use]
 extern crate argonaut;
 
-use argonaut::{Parser, Arg, generate_help};
-use std::env;
+use argonaut::{generate_help, report_error};
 
 fn main() {
     use argonaut::StructuredArgument::*;
     println!("Argonaut!");
 
-    // Prepare the argument slice (skip the program path)
-    let arg_vec: Vec<_> = env::args().skip(1).collect();
-    let mut args: Vec<&str> = Vec::new();
-    for arg in arg_vec.iter() {
-        args.push(arg);
-    }
-
-    // println!("Args: {:?}", args);
-
-    let mut parser = Parser::new();
-
-    // Create the arguments
-    let a_foo = Arg::positional("foo").set_help("A single argument.");
-    let a_foobar = Arg::required_trail("foobar").set_help("One or more trailing arguments.");
-    let a_help = Arg::named_and_short("help", 'h')
-                     .interrupt()
-                     .set_help("Prints a help message for this tool and exits.");
-    let a_version = Arg::named("version")
-                        .interrupt()
-                        .set_help("Prints the version of this tool and exits.");
-    let a_verbose = Arg::named_and_short("verbose", 'v')
-                        .switch()
-                        .set_help("A switch (present or non-present)");
-    let a_exclude = Arg::named_and_short("exclude", 'x')
-                        .single()
-                        .set_param("item")
-                        .set_help("A single argument.");
-    let a_extra = Arg::named_and_short("extra", 'e')
-                      .zero_or_more()
-                      .set_help("Zero or more arguments.");
-    let a_add = Arg::named_and_short("add", 'a')
-                    .one_or_more()
-                    .set_param("number")
-                    .set_help("One or more arguments.");
-    let a_passed = Arg::named("")
-                       .passalong()
-                       .set_param("args")
-                       .set_help("Collect the remaining arguments after this one.");
+    // Define the arguments, one per line
+    let mut parser = argonaut! {
+        positional foo: "A single argument.";
+        trail+ foobar: "One or more trailing arguments.";
+        interrupt -h, --help: "Prints a help message for this tool and exits.";
+        interrupt --version: "Prints the version of this tool and exits.";
+        switch -v, --verbose: "A switch (present or non-present)";
+        option -x, --exclude item: "A single argument.";
+        option* -e, --extra: "Zero or more arguments.";
+        option+ -a, --add number: "One or more arguments.";
+        passalong args: "Collect the remaining arguments after this one.";
+    }.unwrap();
 
-    // Add the arguments, and assert that none of the named ones overlap
-    // Add one
-    parser.define_single(a_foo).unwrap();
-    // Add many
-    parser.define(&[a_foobar, a_help, a_version, a_verbose, a_exclude, a_extra, a_add, a_passed])
-          .unwrap();
+    let usage = "Usage: cargo run --example main -- [--help | OPTIONS ]";
+    parser.set_usage(usage);
 
-    let mut foo = "";
+    let mut foo_value = "";
     let mut foobar = Vec::new();
     let mut extra = None;
     let mut add = None;
@@ -61,37 +31,34 @@ fn main() {
     let mut exclude = None;
     let mut passed = None;
 
-    let usage = "Usage: cargo run --example main -- [--help | OPTIONS ]";
-
-    for item in parser.parse(&args) {
+    // Parse the arguments given to the program (the program path is skipped)
+    for item in parser.parse_env() {
         match item {
             Err(err) => {
-                println!("Parse error: {:?}", err);
-                println!("{}", usage);
-                return;
+                return report_error(&err, &parser);
             }
             Ok(Positional { name: "foo", value }) => {
-                foo = value;
+                foo_value = value;
             }
             Ok(Trail { values }) => {
                 foobar = values;
             }
-            Ok(Interrupt { name: "help" }) => {
+            Ok(Interrupt { name: "help", .. }) => {
                 return println!("{}\n\n{}", usage, generate_help(&parser));
             }
-            Ok(Interrupt { name: "version" }) => {
+            Ok(Interrupt { name: "version", .. }) => {
                 return println!("{}", env!("CARGO_PKG_VERSION"));
             }
-            Ok(Switch { name: "verbose" }) => {
+            Ok(Switch { name: "verbose", .. }) => {
                 verbose = true;
             }
-            Ok(Single { name: "exclude", parameter }) => {
+            Ok(Single { name: "exclude", parameter, .. }) => {
                 exclude = Some(parameter);
             }
-            Ok(Multiple { name: "add", parameters }) => {
+            Ok(Multiple { name: "add", parameters, .. }) => {
                 add = Some(parameters);
             }
-            Ok(Multiple { name: "extra", parameters }) => {
+            Ok(Multiple { name: "extra", parameters, .. }) => {
                 extra = Some(parameters);
             }
             Ok(PassAlong { name: "", args }) => {
@@ -102,7 +69,7 @@ fn main() {
     }
     // Use the parsed values
     println!("Parsed succesfully!");
-    println!("Foo:          {}", foo);
+    println!("Foo:          {}", foo_value);
     println!("Foobar:       {:?}", foobar);
     println!("Verbose:      {}", verbose);
     println!("Exclude:      {:?}", exclude);
@@ -0,0 +1,64 @@
+/*
+ * The C interface of argonaut (the `capi` feature). See the documentation of
+ * the `capi` module for an example.
+ */
+
+#ifndef ARGONAUT_H
+#define ARGONAUT_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A parser created from a JSON spec. */
+typedef struct ArgonautParser ArgonautParser;
+
+/* The results of a parse: the collected arguments, or the error. */
+typedef struct ArgonautArgs ArgonautArgs;
+
+/* Creates a parser from a JSON spec. Returns NULL if the spec is invalid,
+ * after setting `*error` (if `error` is not NULL) to the reason. */
+ArgonautParser *argonaut_parser_new(const char *spec, char **error);
+
+/* Frees a parser. Does nothing if it is NULL. */
+void argonaut_parser_free(ArgonautParser *parser);
+
+/* Returns the usage line and help message of the parser, to be freed with
+ * `argonaut_string_free`. */
+char *argonaut_parser_help(const ArgonautParser *parser);
+
+/* Parses the `argc` arguments in `argv` (without the program name). */
+ArgonautArgs *argonaut_parse(const ArgonautParser *parser, int argc, const char *const *argv);
+
+/* Frees the results of a parse, and the strings returned for them. */
+void argonaut_args_free(ArgonautArgs *args);
+
+/* Returns the error message of the parse, or NULL if it succeeded. */
+const char *argonaut_args_error(const ArgonautArgs *args);
+
+/* Returns 1 if the argument was given, and 0 otherwise. */
+int argonaut_args_is_present(const ArgonautArgs *args, const char *name);
+
+/* Returns how many times the argument was given. */
+size_t argonaut_args_count(const ArgonautArgs *args, const char *name);
+
+/* Returns the value of an argument with a single value, or NULL. */
+const char *argonaut_args_single(const ArgonautArgs *args, const char *name);
+
+/* Returns the number of values of an argument with multiple values. */
+size_t argonaut_args_multiple_len(const ArgonautArgs *args, const char *name);
+
+/* Returns the value at the index of an argument with multiple values, or
+ * NULL. */
+const char *argonaut_args_multiple_at(const ArgonautArgs *args, const char *name, size_t index);
+
+/* Frees a string returned as `char *`. Does nothing if it is NULL. */
+void argonaut_string_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
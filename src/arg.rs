@@ -1,4 +1,8 @@
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
 use common::OptName;
+use parsed::ParsedArgs;
+use typed::{self, FromValue};
 
 /// The different kinds of arguments that can be given to the parser.
 #[derive(Debug, Clone, Copy)]
@@ -12,50 +16,184 @@ pub enum ArgType<'a> {
     Switch(OptName<'a>),
     Interrupt(OptName<'a>),
     PassAlong(OptName<'a>),
+    Digits(OptName<'a>),
+}
+
+/// The values accepted by an argument.
+#[derive(Debug, Clone, Copy)]
+pub struct ChoiceList<'a> {
+    choices: Choices<'a>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Choices<'a> {
+    Borrowed(&'a [&'a str]),
+    Owned(&'a [String]),
+}
+
+impl<'a> ChoiceList<'a> {
+    /// Returns the number of choices.
+    pub fn len(self) -> usize {
+        match self.choices {
+            Choices::Borrowed(choices) => choices.len(),
+            Choices::Owned(choices) => choices.len(),
+        }
+    }
+
+    /// Returns whether there are no choices.
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the choice at the given index.
+    pub fn get(self, index: usize) -> Option<&'a str> {
+        match self.choices {
+            Choices::Borrowed(choices) => choices.get(index).cloned(),
+            Choices::Owned(choices) => choices.get(index).map(|choice| choice.as_str()),
+        }
+    }
+
+    /// Returns whether the value is one of the choices.
+    pub fn contains(self, value: &str) -> bool {
+        self.iter().any(|choice| choice == value)
+    }
+
+    /// Returns an iterator over the choices.
+    pub fn iter(self) -> impl Iterator<Item = &'a str> {
+        (0..self.len()).filter_map(move |i| self.get(i))
+    }
+}
+
+/// The kind of value an argument takes, for shells to complete it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueHint {
+    /// The path of a file (or of any file system entry).
+    FilePath,
+    /// The path of a directory.
+    DirPath,
+    /// The name of a host.
+    Hostname,
+    /// The name of a user.
+    Username,
+    /// The name of a command.
+    CommandName,
 }
 
+/// A check on the values of an argument, returning why a value is invalid.
+pub type Validator = fn(&str) -> Result<(), String>;
+
+/// Computes the default of an argument from the values collected so far, or
+/// returns `None` for no default.
+pub type DerivedDefault = fn(&ParsedArgs) -> Option<String>;
+
 /// An argument description for the parser.
 #[derive(Debug, Clone, Copy)]
 pub struct Arg<'a> {
     param: Option<&'a str>,
     help: Option<&'a str>,
+    deprecated: Option<&'a str>,
+    choices: Option<ChoiceList<'a>>,
+    validator: Option<Validator>,
+    derived_default: Option<DerivedDefault>,
+    range: Option<(i64, i64)>,
+    expand_path: bool,
+    value_hint: Option<ValueHint>,
+    counted: bool,
+    stop_at_flags: bool,
+    lazy: bool,
+    delimiter: Option<char>,
+    allow_duplicates: bool,
+    number_flag: bool,
+    auto_short: bool,
+    aliases: &'a [&'a str],
+    hidden_aliases: &'a [&'a str],
+    required: bool,
+    required_if: Option<&'a str>,
+    required_if_value: Option<(&'a str, &'a str)>,
+    required_unless: Option<&'a str>,
+    display_order: Option<u32>,
+    precedence: u32,
+    overrides: Option<&'a str>,
+    default: Option<&'a str>,
+    env: Option<&'a str>,
+    #[cfg(feature = "regex")]
+    regex: Option<&'a str>,
     argtype: ArgType<'a>,
 }
 
 impl<'a> Arg<'a> {
     fn new(argtype: ArgType<'a>) -> Arg<'a> {
         Arg {
-            argtype: argtype,
+            argtype,
             param: None,
             help: None,
+            deprecated: None,
+            choices: None,
+            validator: None,
+            derived_default: None,
+            range: None,
+            expand_path: false,
+            value_hint: None,
+            counted: false,
+            stop_at_flags: false,
+            lazy: false,
+            delimiter: None,
+            allow_duplicates: false,
+            number_flag: false,
+            auto_short: false,
+            aliases: &[],
+            hidden_aliases: &[],
+            required: false,
+            required_if: None,
+            required_if_value: None,
+            required_unless: None,
+            display_order: None,
+            precedence: 0,
+            overrides: None,
+            default: None,
+            env: None,
+            #[cfg(feature = "regex")]
+            regex: None,
         }
     }
 
     /// Creates a positional argument with the given parameter name.
-    pub fn positional(name: &'a str) -> Arg<'a> {
-        Arg::new(ArgType::Single(name))
+    pub fn positional(name: &'a str) -> PositionalArg<'a> {
+        PositionalArg(Arg::new(ArgType::Single(name)))
     }
 
     /// Creates an argument that requires zero or more trailing parameters.
-    pub fn optional_trail(name: &'a str) -> Arg<'a> {
-        Arg::new(ArgType::ZeroPlus(name))
+    pub fn optional_trail(name: &'a str) -> TrailArg<'a> {
+        TrailArg(Arg::new(ArgType::ZeroPlus(name)))
     }
 
     /// Creates an argument that requires one or more trailing parameters.
-    pub fn required_trail(name: &'a str) -> Arg<'a> {
-        Arg::new(ArgType::OnePlus(name))
+    pub fn required_trail(name: &'a str) -> TrailArg<'a> {
+        TrailArg(Arg::new(ArgType::OnePlus(name)))
     }
 
     /// Creates a new optional argument with a short name (e.g. `h` for `-h`).
     pub fn named_and_short(name: &'a str, short: char) -> OptArg<'a> {
-        OptArg { name: OptName::NormalAndShort(name, short) }
+        OptArg { name: OptName::NormalAndShort(name, short), auto_short: false }
     }
 
     /// Creates a new optional argument with the given flag name.
     ///
     /// The `name` is without the `--` prefix.
     pub fn named(name: &'a str) -> OptArg<'a> {
-        OptArg { name: OptName::Normal(name) }
+        OptArg { name: OptName::Normal(name), auto_short: false }
+    }
+
+    /// Creates a new optional argument whose short flag is picked from its
+    /// name when it is defined, for quickly putting a tool together.
+    ///
+    /// The short flag is the first letter of the name that is not yet taken
+    /// (`e` for `exclude`, or `x` if `-e` is), or else the first of these
+    /// letters in the other case (`E`). Defining it fails if all of them are
+    /// taken. As the flag depends on the arguments defined before, define the
+    /// arguments in a fixed order to keep it stable.
+    pub fn named_auto_short(name: &'a str) -> OptArg<'a> {
+        OptArg { name: OptName::Normal(name), auto_short: true }
     }
 
     /// Returns the option name of this argument.
@@ -69,7 +207,8 @@ impl<'a> Arg<'a> {
             OptOnePlus(optname) |
             Switch(optname) |
             Interrupt(optname) |
-            PassAlong(optname) => Some(optname),
+            PassAlong(optname) |
+            Digits(optname) => Some(optname),
             _ => None,
         }
     }
@@ -84,7 +223,55 @@ impl<'a> Arg<'a> {
             OptOnePlus(opt) |
             Switch(opt) |
             Interrupt(opt) |
-            PassAlong(opt) => opt.name(),
+            PassAlong(opt) |
+            Digits(opt) => opt.name(),
+        }
+    }
+
+    /// Returns the long flag of this optional argument, without its dashes.
+    pub fn long(self) -> Option<&'a str> {
+        self.option_name().map(|name| name.name())
+    }
+
+    /// Returns the short flag of this optional argument, if it has one.
+    pub fn short(self) -> Option<char> {
+        match self.option_name() {
+            Some(OptName::NormalAndShort(_, short)) => Some(short),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this is a positional argument or a trail.
+    pub fn is_positional(self) -> bool {
+        self.option_name().is_none()
+    }
+
+    /// Returns whether this is a switch (counted or not).
+    pub fn is_switch(self) -> bool {
+        matches!(self.argtype, ArgType::Switch(_))
+    }
+
+    /// Returns whether this is an interrupt.
+    pub fn is_interrupt(self) -> bool {
+        matches!(self.argtype, ArgType::Interrupt(_))
+    }
+
+    /// Returns whether this is a pass-along.
+    pub fn is_passalong(self) -> bool {
+        matches!(self.argtype, ArgType::PassAlong(_))
+    }
+
+    /// Returns the least and the most values this argument takes each time
+    /// it is given, like `Iterator::size_hint`. Trails, interrupts and
+    /// pass-alongs have no upper bound, as they may take all the arguments
+    /// after them.
+    pub fn arity(self) -> (usize, Option<usize>) {
+        use self::ArgType::*;
+        match self.argtype {
+            Switch(_) => (0, Some(0)),
+            Single(_) | OptSingle(_) | Digits(_) => (1, Some(1)),
+            ZeroPlus(_) | OptZeroPlus(_) | Interrupt(_) | PassAlong(_) => (0, None),
+            OnePlus(_) | OptOnePlus(_) => (1, None),
         }
     }
 
@@ -98,58 +285,685 @@ impl<'a> Arg<'a> {
         self.help
     }
 
-    /// Sets the parameter name for this argument definition.
-    pub fn set_param(mut self, name: &'a str) -> Self {
-        self.param = Some(name);
-        self
+    /// Returns the deprecation note of this argument definition, if any.
+    pub fn deprecated(self) -> Option<&'a str> {
+        self.deprecated
+    }
+
+    /// Returns the values accepted by this argument definition, if limited.
+    pub fn choices(self) -> Option<ChoiceList<'a>> {
+        self.choices
+    }
+
+    /// Returns the check on the values of this argument definition, if any.
+    pub fn validator(self) -> Option<Validator> {
+        self.validator
+    }
+
+    /// Returns the integers accepted by this argument definition, if limited.
+    pub fn value_range(self) -> Option<RangeInclusive<i64>> {
+        self.range.map(|(min, max)| min..=max)
+    }
+
+    /// Returns whether this is a switch that counts how often it is given.
+    pub fn is_counted(self) -> bool {
+        self.counted
+    }
+
+    /// Returns whether this pass-along ends at the next known flag.
+    pub fn stops_at_flags(self) -> bool {
+        self.stop_at_flags
+    }
+
+    /// Returns whether this option leaves values for the positional arguments.
+    pub fn is_lazy(self) -> bool {
+        self.lazy
+    }
+
+    /// Returns the character that the values of this option are split at, if
+    /// any.
+    pub fn delimiter(self) -> Option<char> {
+        self.delimiter
+    }
+
+    /// Returns whether this optional argument may be given more than once.
+    pub fn allows_duplicates(self) -> bool {
+        self.allow_duplicates
+    }
+
+    /// Returns whether the short flag of this optional argument is picked when
+    /// it is defined (see `named_auto_short`).
+    pub fn has_auto_short(self) -> bool {
+        self.auto_short
+    }
+
+    /// Returns whether this option can be given as `-NUM`.
+    pub fn takes_number_flag(self) -> bool {
+        self.number_flag
+    }
+
+    /// Returns whether this optional argument must be given.
+    pub fn is_required(self) -> bool {
+        self.required
+    }
+
+    /// Returns the argument whose presence makes this one required, if any.
+    pub fn required_if(self) -> Option<&'a str> {
+        self.required_if
+    }
+
+    /// Returns the argument and the value of it that make this one required,
+    /// if any.
+    pub fn required_if_value(self) -> Option<(&'a str, &'a str)> {
+        self.required_if_value
+    }
+
+    /// Returns the argument whose absence makes this one required, if any.
+    pub fn required_unless(self) -> Option<&'a str> {
+        self.required_unless
+    }
+
+    /// Returns the position of this argument within its help section, if set.
+    pub fn display_order(self) -> Option<u32> {
+        self.display_order
+    }
+
+    /// Returns the precedence of this interrupt over other interrupts.
+    pub fn precedence(self) -> u32 {
+        self.precedence
+    }
+
+    /// Returns the name of the argument this one overrides, if any.
+    pub fn overridden(self) -> Option<&'a str> {
+        self.overrides
+    }
+
+    /// Returns the other long flags of this argument that are shown in the
+    /// help message.
+    pub fn aliases(self) -> &'a [&'a str] {
+        self.aliases
+    }
+
+    /// Returns the other long flags of this argument that are accepted but
+    /// not shown.
+    pub fn hidden_aliases(self) -> &'a [&'a str] {
+        self.hidden_aliases
+    }
+
+    /// Returns the value used when this argument is not given, if any.
+    pub fn default(self) -> Option<&'a str> {
+        self.default
+    }
+
+    /// Returns the function computing the default of this argument, if any.
+    pub fn derived_default(self) -> Option<DerivedDefault> {
+        self.derived_default
+    }
+
+    /// Returns the environment variable read when this argument is not
+    /// given, if any.
+    pub fn env(self) -> Option<&'a str> {
+        self.env
+    }
+
+    /// Returns whether the values of this argument definition are expanded
+    /// like paths when collected.
+    pub fn expands_path(self) -> bool {
+        self.expand_path
+    }
+
+    /// Returns the kind of value this argument takes, if it is set.
+    pub fn value_hint(self) -> Option<ValueHint> {
+        self.value_hint
+    }
+
+    /// Returns the pattern the values of this argument must match, if any.
+    #[cfg(feature = "regex")]
+    pub fn regex(self) -> Option<&'a str> {
+        self.regex
     }
 
     /// Sets the help text for this argument definition.
+    ///
+    /// The text may have several lines (e.g. paragraphs or a list), which
+    /// are indented to the same column in the help message.
     pub fn set_help(mut self, text: &'a str) -> Self {
         self.help = Some(text);
         self
     }
+
+    /// Sets the position of this argument within its section of the help
+    /// message.
+    ///
+    /// Arguments with a lower order come first, and arguments without one
+    /// come last, in the order of `Parser::set_help_order`.
+    pub fn set_display_order(mut self, order: u32) -> Self {
+        self.display_order = Some(order);
+        self
+    }
+}
+
+// The settings of definitions that are built at runtime (like the ones of a
+// spec file), for which `Parser::define` checks that they apply to the kind
+pub fn internal_set_param<'a>(arg: Arg<'a>, name: &'a str) -> Arg<'a> {
+    Arg { param: Some(name), ..arg }
+}
+
+pub fn internal_set_deprecated<'a>(arg: Arg<'a>, note: &'a str) -> Arg<'a> {
+    Arg { deprecated: Some(note), ..arg }
+}
+
+pub fn internal_set_choices<'a>(arg: Arg<'a>, choices: &'a [&'a str]) -> Arg<'a> {
+    Arg { choices: Some(ChoiceList { choices: Choices::Borrowed(choices) }), ..arg }
+}
+
+pub fn internal_set_owned_choices<'a>(arg: Arg<'a>, choices: &'a [String]) -> Arg<'a> {
+    Arg { choices: Some(ChoiceList { choices: Choices::Owned(choices) }), ..arg }
+}
+
+pub fn internal_set_default<'a>(arg: Arg<'a>, value: &'a str) -> Arg<'a> {
+    Arg { default: Some(value), ..arg }
+}
+
+pub fn internal_set_required(arg: Arg) -> Arg {
+    Arg { required: true, ..arg }
+}
+
+/// Returns the range like it is written in Rust, without unbounded ends
+/// (e.g. `1..=65535` or `0..`).
+pub fn internal_describe_range(range: &RangeInclusive<i64>) -> String {
+    match (*range.start(), *range.end()) {
+        (i64::MIN, i64::MAX) => "..".to_owned(),
+        (i64::MIN, max) => format!("..={}", max),
+        (min, i64::MAX) => format!("{}..", min),
+        (min, max) => format!("{}..={}", min, max),
+    }
 }
 
 pub fn internal_get_raw(arg: Arg) -> ArgType {
     arg.argtype
 }
 
+/// Gives the optional argument the short flag, in place of a picked one.
+pub fn internal_with_short<'a>(arg: Arg<'a>, short: char) -> Arg<'a> {
+    use self::ArgType::*;
+    let with = |name: OptName<'a>| OptName::NormalAndShort(name.name(), short);
+    let argtype = match arg.argtype {
+        OptSingle(name) => OptSingle(with(name)),
+        OptZeroPlus(name) => OptZeroPlus(with(name)),
+        OptOnePlus(name) => OptOnePlus(with(name)),
+        Switch(name) => Switch(with(name)),
+        Interrupt(name) => Interrupt(with(name)),
+        PassAlong(name) => PassAlong(with(name)),
+        Digits(name) => Digits(with(name)),
+        argtype => argtype,
+    };
+    Arg { argtype, auto_short: false, ..arg }
+}
+
 /// A partial builder for an optional argument.
 #[derive(Debug)]
 #[must_use]
 pub struct OptArg<'a> {
     name: OptName<'a>,
+    auto_short: bool,
 }
 
 impl<'a> OptArg<'a> {
+    /// Creates the argument of the kind, with the flags of this builder.
+    fn arg(&self, argtype: ArgType<'a>) -> Arg<'a> {
+        Arg { auto_short: self.auto_short, ..Arg::new(argtype) }
+    }
+
     /// The argument takes a single parameter.
-    pub fn single(self) -> Arg<'a> {
-        Arg::new(ArgType::OptSingle(self.name))
+    ///
+    /// The parameter can also be attached to the flag after a `=`
+    /// (`--out=file` or `-o=file`).
+    pub fn single(self) -> OptSingle<'a> {
+        OptSingle(self.arg(ArgType::OptSingle(self.name)))
     }
 
     /// The argument takes one or more parameters.
-    pub fn one_or_more(self) -> Arg<'a> {
-        Arg::new(ArgType::OptOnePlus(self.name))
+    ///
+    /// A parameter attached to the flag after a `=` (`--add=1`) is the only
+    /// one, unless it is split with `OptMultiple::set_delimiter`.
+    pub fn one_or_more(self) -> OptMultiple<'a> {
+        OptMultiple(self.arg(ArgType::OptOnePlus(self.name)))
     }
 
-    /// The argument takes zero or more parameters.
-    pub fn zero_or_more(self) -> Arg<'a> {
-        Arg::new(ArgType::OptZeroPlus(self.name))
+    /// The argument takes zero or more parameters (see `one_or_more` for
+    /// attached ones).
+    pub fn zero_or_more(self) -> OptMultiple<'a> {
+        OptMultiple(self.arg(ArgType::OptZeroPlus(self.name)))
     }
 
     /// The argument is an interrupt (the parse is interrupted when it is encountered).
-    pub fn interrupt(self) -> Arg<'a> {
-        Arg::new(ArgType::Interrupt(self.name))
+    ///
+    /// The structured argument holds the arguments after the flag, so that
+    /// interrupts like `--completions zsh` can take a parameter.
+    pub fn interrupt(self) -> OptInterrupt<'a> {
+        OptInterrupt(self.arg(ArgType::Interrupt(self.name)))
     }
 
     /// The argument is a switch (boolean flag).
-    pub fn switch(self) -> Arg<'a> {
-        Arg::new(ArgType::Switch(self.name))
+    pub fn switch(self) -> OptSwitch<'a> {
+        OptSwitch(self.arg(ArgType::Switch(self.name)))
+    }
+
+    /// The argument is a switch that can be given several times (e.g. `-vvv`
+    /// for more verbosity), which is structured each time it is given.
+    ///
+    /// `ParsedArgs` collects it as the number of times it was given.
+    pub fn count(self) -> OptSwitch<'a> {
+        OptSwitch(Arg { counted: true, ..self.arg(ArgType::Switch(self.name)) })
+    }
+
+    /// The argument is a digit given as its own flag (like `-9` for
+    /// `gzip -9`), or as the parameter of the long flag (`--level 9`).
+    ///
+    /// The digits can be limited with `range` or `set_choices`. Short flags
+    /// that are defined with a digit take precedence, and only one argument of
+    /// a parser can take digit flags, which has no short name.
+    pub fn digits(self) -> OptDigits<'a> {
+        OptDigits(self.arg(ArgType::Digits(self.name)))
     }
 
     /// The argument is a passalong (all following arguments are collected)
-    pub fn passalong(self) -> Arg<'a> {
-        Arg::new(ArgType::PassAlong(self.name))
+    pub fn passalong(self) -> OptPassAlong<'a> {
+        OptPassAlong(self.arg(ArgType::PassAlong(self.name)))
     }
 }
+
+/// Returns the inclusive ends of the range, without unbounded ends.
+fn range_ends<R: RangeBounds<i64>>(range: R) -> (i64, i64) {
+    let min = match range.start_bound() {
+        Bound::Included(&min) => min,
+        Bound::Excluded(&min) => min.saturating_add(1),
+        Bound::Unbounded => i64::MIN,
+    };
+    let max = match range.end_bound() {
+        Bound::Included(&max) => max,
+        Bound::Excluded(&max) => max.saturating_sub(1),
+        Bound::Unbounded => i64::MAX,
+    };
+    (min, max)
+}
+
+/// Defines a setter of the argument builders.
+macro_rules! setter {
+    (set_help) => {
+        /// Sets the help text for this argument definition.
+        ///
+        /// The text may have several lines (e.g. paragraphs or a list), which
+        /// are indented to the same column in the help message.
+        pub fn set_help(self, text: &'a str) -> Self {
+            Self(self.0.set_help(text))
+        }
+    };
+    (set_display_order) => {
+        /// Sets the position of this argument within its section of the help
+        /// message.
+        ///
+        /// Arguments with a lower order come first, and arguments without one
+        /// come last, in the order of `Parser::set_help_order`.
+        pub fn set_display_order(self, order: u32) -> Self {
+            Self(self.0.set_display_order(order))
+        }
+    };
+    (set_param) => {
+        /// Sets the parameter name for this argument definition.
+        ///
+        /// The help message shows it as it is (e.g. `--output <FILE>` for
+        /// `FILE`) instead of the name of the argument, which is uppercased
+        /// for optional arguments.
+        pub fn set_param(self, name: &'a str) -> Self {
+            Self(internal_set_param(self.0, name))
+        }
+    };
+    (set_deprecated) => {
+        /// Marks this argument as deprecated with the given note (e.g. `use
+        /// --output instead`).
+        ///
+        /// Using the flag still works, but adds a warning to the parse.
+        pub fn set_deprecated(self, note: &'a str) -> Self {
+            Self(internal_set_deprecated(self.0, note))
+        }
+    };
+    (set_choices) => {
+        /// Limits the values of this argument to the given choices.
+        ///
+        /// Other values are rejected by the parse.
+        pub fn set_choices(self, choices: &'a [&'a str]) -> Self {
+            Self(internal_set_choices(self.0, choices))
+        }
+
+        /// Limits the values of this argument to the variants of `T` (e.g.
+        /// `set_choices_of::<Mode>()`), which can then be converted with
+        /// `ParsedArgs::single_as`.
+        pub fn set_choices_of<T: typed::Choices>(self) -> Self {
+            self.set_choices(T::variants())
+        }
+    };
+    (set_validator) => {
+        /// Checks the values of this argument with the given function.
+        ///
+        /// Values it returns an error for are rejected by the parse, with the
+        /// error as the reason. Each value is checked as soon as it is found,
+        /// including the values of the trail, so the parse reports the name
+        /// of the argument and the value that was rejected.
+        pub fn set_validator(mut self, validator: Validator) -> Self {
+            self.0.validator = Some(validator);
+            self
+        }
+
+        /// Only accepts values of this argument that can be converted to `T`
+        /// (e.g. `set_type::<u16>()` for a port number).
+        ///
+        /// The values can then be converted with `ParsedArgs::single_as` and
+        /// `ParsedArgs::multiple_as`.
+        pub fn set_type<T: FromValue>(self) -> Self {
+            self.set_validator(typed::validate::<T>)
+        }
+    };
+    (range) => {
+        /// Only accepts integer values of this argument in the given range
+        /// (e.g. `range(1..=65535)` for a port number).
+        ///
+        /// The range is shown in the help message.
+        pub fn range<R: RangeBounds<i64>>(mut self, range: R) -> Self {
+            self.0.range = Some(range_ends(range));
+            self
+        }
+    };
+    (stop_at_flags) => {
+        /// Ends this pass-along at the next flag the parser knows or at a
+        /// `--`, instead of collecting all the following arguments. The parse
+        /// then goes on from there.
+        ///
+        /// This allows several pass-alongs in one invocation, like
+        /// `--cargo-args --release --rustc-args -C opt-level=3`.
+        pub fn stop_at_flags(mut self) -> Self {
+            self.0.stop_at_flags = true;
+            self
+        }
+    };
+    (lazy) => {
+        /// Makes this option with several parameters leave enough values for
+        /// the positional arguments that are still missing, so that in
+        /// `prog --add 1 2 input.txt` the file is not taken as a parameter.
+        ///
+        /// Values given after later flags count as well, and an option with
+        /// one or more parameters always takes one.
+        pub fn lazy(mut self) -> Self {
+            self.0.lazy = true;
+            self
+        }
+    };
+    (set_delimiter) => {
+        /// Splits the values of this option with several parameters at the
+        /// character, so that `--add 1,2,3` and `--add=1,2,3` give the
+        /// parameters `1`, `2` and `3`.
+        ///
+        /// Each part is checked like a separate value, and empty parts are
+        /// kept as empty values (`--add=1,,3` gives `1`, an empty value and
+        /// `3`, and `--add=,` gives two empty values). The quotes of the shell
+        /// are removed before the parse, so a value cannot protect the
+        /// character from the split (`--add="a,b"` gives `a` and `b`).
+        pub fn set_delimiter(mut self, delimiter: char) -> Self {
+            self.0.delimiter = Some(delimiter);
+            self
+        }
+    };
+    (allow_duplicates) => {
+        /// Lets this optional argument be given more than once (e.g. a flag
+        /// that a shell alias always adds), instead of failing the parse with
+        /// a `ParseError::DuplicatePositionalArgument`.
+        ///
+        /// Each occurrence is structured, and the last one wins in
+        /// `ParsedArgs`. The values of an option taking multiple parameters
+        /// are merged instead: all its occurrences are structured as one
+        /// `Multiple` once the other arguments are parsed.
+        pub fn allow_duplicates(mut self) -> Self {
+            self.0.allow_duplicates = true;
+            self
+        }
+    };
+    (set_number_flag) => {
+        /// Lets this option with a single parameter be given as a dash
+        /// followed by a number, like `head -5` for `head --lines 5`.
+        ///
+        /// A defined short flag like `-5` takes precedence, but a number of
+        /// several digits is never read as grouped short flags. Only one
+        /// option of a parser can be given this way.
+        pub fn set_number_flag(mut self) -> Self {
+            self.0.number_flag = true;
+            self
+        }
+    };
+    (set_required) => {
+        /// Requires this optional argument to be given, or the parse ends
+        /// with a `ParseError::MissingOption`.
+        ///
+        /// The argument is marked as required in the help message.
+        pub fn set_required(self) -> Self {
+            Self(internal_set_required(self.0))
+        }
+
+        /// Requires this optional argument to be given when the named
+        /// optional argument is (e.g. `--key` with `--tls`), or the parse ends
+        /// with a `ParseError::MissingOptionIf`.
+        pub fn set_required_if(mut self, other: &'a str) -> Self {
+            self.0.required_if = Some(other);
+            self
+        }
+
+        /// Requires this optional argument to be given when the named
+        /// argument is given the value (e.g. `--region` with `--provider
+        /// aws`), or the parse ends with a `ParseError::MissingOptionIfValue`.
+        ///
+        /// The named argument may be optional or positional. With several
+        /// values, any of them triggers the requirement.
+        pub fn set_required_if_value(mut self, other: &'a str, value: &'a str) -> Self {
+            self.0.required_if_value = Some((other, value));
+            self
+        }
+
+        /// Requires this optional argument to be given unless the named
+        /// optional argument is (e.g. `--file` without `--stdin`), or the
+        /// parse ends with a `ParseError::MissingOptionUnless`.
+        pub fn set_required_unless(mut self, other: &'a str) -> Self {
+            self.0.required_unless = Some(other);
+            self
+        }
+    };
+    (set_precedence) => {
+        /// Sets the precedence of this interrupt when several are given (e.g.
+        /// a higher one for `--help` so that it wins over `--version --help`).
+        ///
+        /// The parse is interrupted by the given interrupt with the highest
+        /// precedence, or by the first of those with the same precedence. All
+        /// interrupts have a precedence of 0 by default.
+        pub fn set_precedence(mut self, precedence: u32) -> Self {
+            self.0.precedence = precedence;
+            self
+        }
+    };
+    (overrides) => {
+        /// Makes this optional argument and the named one cancel each other,
+        /// so that the last one given wins (e.g. `--verbose` and `--quiet`).
+        ///
+        /// Giving one after the other is not a duplicate, and `ParsedArgs`
+        /// only keeps the last one.
+        pub fn overrides(mut self, other: &'a str) -> Self {
+            self.0.overrides = Some(other);
+            self
+        }
+    };
+    (set_aliases) => {
+        /// Accepts the given long flags for this optional argument too, and
+        /// shows them after its own in the help message (e.g. `--color,
+        /// --colour`).
+        ///
+        /// The structured arguments have the name of the argument whichever
+        /// flag was given.
+        pub fn set_aliases(mut self, aliases: &'a [&'a str]) -> Self {
+            self.0.aliases = aliases;
+            self
+        }
+
+        /// Accepts the given long flags for this optional argument too,
+        /// without showing them anywhere (e.g. the old names of renamed
+        /// flags).
+        pub fn set_hidden_aliases(mut self, aliases: &'a [&'a str]) -> Self {
+            self.0.hidden_aliases = aliases;
+            self
+        }
+    };
+    (set_default) => {
+        /// Sets the value collected into `ParsedArgs` when this optional
+        /// argument is not given.
+        ///
+        /// The default is shown in the help message. It is not checked against
+        /// the choices or the validator of the argument.
+        pub fn set_default(self, value: &'a str) -> Self {
+            Self(internal_set_default(self.0, value))
+        }
+
+        /// Computes the value collected into `ParsedArgs` when this optional
+        /// argument is not given from the values of the other arguments, e.g.
+        /// `--work-dir` from `--project`:
+        ///
+        /// ```text
+        /// Arg::named("work-dir").single().set_default_with(|args| {
+        ///     args.single("project").map(|project| format!("{}/build", project))
+        /// })
+        /// ```
+        ///
+        /// The function is called after the given arguments, the environment
+        /// variables and the plain defaults are collected, in the order of the
+        /// definitions, so it also sees the derived defaults of the arguments
+        /// defined before this one. It cannot be combined with `set_default`.
+        pub fn set_default_with(mut self, derive: DerivedDefault) -> Self {
+            self.0.derived_default = Some(derive);
+            self
+        }
+    };
+    (set_env) => {
+        /// Reads the value of this optional argument from the given
+        /// environment variable when it is not given, before falling back to
+        /// the default.
+        ///
+        /// Switches are given when the variable is a true boolean (see
+        /// `parse_bool`). Empty variables are ignored, and the variable is
+        /// shown in the help message.
+        pub fn set_env(mut self, var: &'a str) -> Self {
+            self.0.env = Some(var);
+            self
+        }
+    };
+    (expand_path) => {
+        /// Expands a leading `~` and environment variables like `$HOME` in the
+        /// values of this argument when they are collected into `ParsedArgs`.
+        ///
+        /// See `expand_path` for the details.
+        pub fn expand_path(mut self) -> Self {
+            self.0.expand_path = true;
+            self
+        }
+    };
+    (set_value_hint) => {
+        /// Sets the kind of value this argument takes (e.g.
+        /// `ValueHint::FilePath` for `--config`), so that completion lets the
+        /// shell complete it when there are no choices.
+        pub fn set_value_hint(mut self, hint: ValueHint) -> Self {
+            self.0.value_hint = Some(hint);
+            self
+        }
+    };
+    (matches_regex) => {
+        /// Only accepts values of this argument that match the given regular
+        /// expression (e.g. `r"^[a-z]+\d+$"`).
+        ///
+        /// The pattern is compiled when the argument is defined, which errors
+        /// if it is invalid. Use anchors to match the whole value.
+        #[cfg(feature = "regex")]
+        pub fn matches_regex(mut self, pattern: &'a str) -> Self {
+            self.0.regex = Some(pattern);
+            self
+        }
+    };
+}
+
+/// Defines the builder of a kind of argument, with the setters that apply to
+/// that kind, so that e.g. a parameter name on a switch does not compile.
+macro_rules! builder {
+    ($(#[$attr:meta])* $builder:ident: $($setter:ident),*) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy)]
+        #[must_use]
+        pub struct $builder<'a>(Arg<'a>);
+
+        impl<'a> $builder<'a> {
+            setter!(set_help);
+            setter!(set_display_order);
+            $(setter!($setter);)*
+        }
+
+        impl<'a> From<$builder<'a>> for Arg<'a> {
+            fn from(builder: $builder<'a>) -> Arg<'a> {
+                builder.0
+            }
+        }
+    };
+}
+
+builder! {
+    /// A builder for a positional argument, from `Arg::positional`.
+    PositionalArg: set_param, set_choices, set_validator, range, expand_path, set_value_hint,
+                   matches_regex
+}
+
+builder! {
+    /// A builder for a trail, from `Arg::optional_trail` or
+    /// `Arg::required_trail`.
+    TrailArg: set_param, set_choices, set_validator, range, expand_path, set_value_hint,
+              matches_regex
+}
+
+builder! {
+    /// A builder for an option with a single parameter, from `OptArg::single`.
+    OptSingle: set_param, set_choices, set_validator, range, expand_path, set_value_hint,
+               matches_regex, set_deprecated, set_aliases, overrides, allow_duplicates,
+               set_required, set_default, set_env, set_number_flag
+}
+
+builder! {
+    /// A builder for an option with several parameters, from
+    /// `OptArg::one_or_more` or `OptArg::zero_or_more`.
+    OptMultiple: set_param, set_choices, set_validator, range, expand_path, set_value_hint,
+                 matches_regex, set_deprecated, set_aliases, overrides, allow_duplicates,
+                 set_required, set_default, set_env, lazy, set_delimiter
+}
+
+builder! {
+    /// A builder for a switch, from `OptArg::switch` or `OptArg::count`.
+    OptSwitch: set_deprecated, set_aliases, overrides, allow_duplicates, set_env
+}
+
+builder! {
+    /// A builder for an interrupt, from `OptArg::interrupt`.
+    OptInterrupt: set_deprecated, set_aliases, set_precedence
+}
+
+builder! {
+    /// A builder for an argument taking digit flags, from `OptArg::digits`.
+    OptDigits: set_param, set_choices, set_validator, range, expand_path, set_value_hint,
+               matches_regex, set_deprecated, set_aliases, overrides, allow_duplicates,
+               set_default, set_env
+}
+
+builder! {
+    /// A builder for a pass-along, from `OptArg::passalong`.
+    OptPassAlong: set_param, expand_path, set_value_hint, set_deprecated, set_aliases,
+                  overrides, allow_duplicates, stop_at_flags
+}
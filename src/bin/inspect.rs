@@ -0,0 +1,89 @@
+//! Shows how a parser structures the arguments given to it, one structured
+//! argument per line.
+//!
+//! The parser is read from a docopt-style usage text (see
+//! `Parser::from_usage`), or is a small demo parser that collects the flags it
+//! does not know:
+//!
+//! ```text
+//! $ argonaut-inspect --usage "Usage: prog [-v] --out=<file> <input>..." -- -v a b
+//! Ok(Switch { name: "v", flag: Short('v'), occurrence: 1 })
+//! Ok(Trail { values: ["a", "b"] })
+//! ```
+
+extern crate argonaut;
+
+use argonaut::{Arg, Parser, generate_help, report_error};
+use std::process;
+
+/// Returns the demo parser, which has an argument of each common kind.
+fn demo_parser() -> Parser<'static> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::named_and_short("help", 'h').interrupt().into(),
+                    Arg::named_and_short("verbose", 'v').count().into(),
+                    Arg::named_and_short("output", 'o').single().into(),
+                    Arg::named_and_short("exclude", 'x').one_or_more().into(),
+                    Arg::named("dry-run").switch().into(),
+                    Arg::optional_trail("files").into(),
+                    Arg::named("").passalong().into()])
+          .unwrap();
+    parser.set_collect_unknown(true);
+    parser
+}
+
+fn main() {
+    use argonaut::StructuredArgument::*;
+
+    let mut parser = Parser::new();
+    parser.define(&[Arg::named_and_short("usage", 'u')
+                        .single()
+                        .set_param("TEXT")
+                        .set_help("The usage text of the parser to inspect (default: a demo \
+                                   parser).")
+                        .into(),
+                    Arg::named_and_short("help", 'h')
+                        .interrupt()
+                        .set_help("Prints this help message and exits.")
+                        .into(),
+                    Arg::named("")
+                        .passalong()
+                        .set_param("ARGS")
+                        .set_help("The arguments to structure.")
+                        .into()])
+          .unwrap();
+    parser.set_usage("Usage: argonaut-inspect [--usage TEXT] -- ARGS...");
+
+    let mut usage = None;
+    let mut args = Vec::new();
+    for item in parser.parse_env() {
+        match item {
+            Err(err) => {
+                report_error(&err, &parser);
+                process::exit(2);
+            }
+            Ok(Single { name: "usage", parameter, .. }) => usage = Some(parameter),
+            Ok(PassAlong { args: passed, .. }) => args = passed,
+            Ok(Interrupt { name: "help", .. }) => {
+                return println!("{}\n\n{}", parser.usage().unwrap_or(""), generate_help(&parser));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let inspected = match usage {
+        Some(text) => match Parser::from_usage(text) {
+            Ok(parser) => parser,
+            Err(err) => {
+                eprintln!("error: invalid usage text: {}", err);
+                process::exit(1);
+            }
+        },
+        None => demo_parser(),
+    };
+    for item in inspected.parse(&args) {
+        match item {
+            Ok(structured) => println!("Ok({:?})", structured),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! A C interface, for programs in C or C++ (or other languages with a foreign
+//! function interface) that want to parse their arguments with argonaut.
+//!
+//! The parser is described by a JSON spec (see `SpecDocument`). The functions
+//! are declared in `include/argonaut.h`, and are exported by a `staticlib` or
+//! `cdylib` crate that depends on argonaut with the `capi` feature:
+//!
+//! ```text
+//! char *error = NULL;
+//! ArgonautParser *parser = argonaut_parser_new(spec, &error);
+//! if (parser == NULL) {
+//!     fprintf(stderr, "invalid spec: %s\n", error);
+//!     argonaut_string_free(error);
+//!     return 1;
+//! }
+//! ArgonautArgs *args = argonaut_parse(parser, argc - 1, argv + 1);
+//! if (argonaut_args_error(args) != NULL) {
+//!     fprintf(stderr, "error: %s\n", argonaut_args_error(args));
+//!     return 1;
+//! }
+//! int verbose = argonaut_args_is_present(args, "verbose");
+//! const char *out = argonaut_args_single(args, "out");
+//! for (size_t i = 0; i < argonaut_args_multiple_len(args, "tag"); i++) {
+//!     puts(argonaut_args_multiple_at(args, "tag", i));
+//! }
+//! argonaut_args_free(args);
+//! argonaut_parser_free(parser);
+//! ```
+//!
+//! The strings returned for the results stay valid until the results are
+//! freed. Strings returned as `char *` are owned by the caller, and freed
+//! with `argonaut_string_free`.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use serde_json;
+
+use parsed::{ParsedArgs, Value};
+use parser::Parser;
+use spec::SpecDocument;
+use utils::generate_help;
+
+/// A parser created from a spec with `argonaut_parser_new`.
+#[derive(Debug)]
+pub struct ArgonautParser {
+    spec: SpecDocument,
+}
+
+/// The results of `argonaut_parse`: the collected arguments, or the error of
+/// the parse.
+#[derive(Debug)]
+pub struct ArgonautArgs {
+    result: Result<ParsedArgs, CString>,
+    // The values of the arguments, as given out to the caller
+    values: HashMap<String, Vec<CString>>,
+}
+
+/// Converts a string for the caller, dropping what follows a nul character.
+fn to_c_string(text: &str) -> CString {
+    let text = text.split('\0').next().unwrap_or("");
+    CString::new(text).unwrap_or_default()
+}
+
+/// Returns the string behind the pointer, or `None` if it is null or not
+/// UTF-8.
+unsafe fn from_c_str<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// Returns the values of the argument, which are empty if it was not given or
+/// has none.
+unsafe fn values_of<'a>(args: *const ArgonautArgs, name: *const c_char) -> &'a [CString] {
+    match (args.as_ref(), from_c_str(name)) {
+        (Some(args), Some(name)) => args.values.get(name).map_or(&[], |values| values),
+        _ => &[],
+    }
+}
+
+/// Returns the `argc` arguments in `argv`, or why they cannot be read.
+unsafe fn read_argv(argc: c_int, argv: *const *const c_char) -> Result<Vec<String>, String> {
+    if argv.is_null() && argc > 0 {
+        return Err("no arguments were given".to_owned());
+    }
+    (0..argc.max(0) as usize).map(|i| {
+                                 from_c_str(*argv.add(i)).map(|arg| arg.to_owned()).ok_or_else(|| {
+                                     format!("the argument {} is not a UTF-8 string", i + 1)
+                                 })
+                             })
+                             .collect()
+}
+
+/// Returns the collected arguments of the command line, or why it could not
+/// be parsed.
+fn parse_args(spec: &SpecDocument, args: &[String]) -> Result<ParsedArgs, String> {
+    let parser = Parser::from_spec(spec)?;
+    ParsedArgs::from_parse(parser.parse(args)).map_err(|err| err.message(parser.strings()))
+}
+
+/// Creates a parser from a JSON spec. Returns null if the spec is invalid,
+/// after setting `*error` (if `error` is not null) to the reason.
+///
+/// # Safety
+///
+/// `spec` must be a nul-terminated string, and `error` null or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_parser_new(spec: *const c_char,
+                                             error: *mut *mut c_char)
+                                             -> *mut ArgonautParser {
+    let spec = from_c_str(spec).ok_or_else(|| "the spec is not a UTF-8 string".to_owned())
+                               .and_then(|spec| {
+                                   serde_json::from_str::<SpecDocument>(spec)
+                                       .map_err(|err| format!("invalid spec: {}", err))
+                               });
+    // The arguments are defined again for each parse, so check them once here
+    let spec = spec.and_then(|spec| {
+        Parser::from_spec(&spec)?;
+        Ok(spec)
+    });
+    match spec {
+        Ok(spec) => Box::into_raw(Box::new(ArgonautParser { spec })),
+        Err(message) => {
+            if !error.is_null() {
+                *error = to_c_string(&message).into_raw();
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a parser. Does nothing if it is null.
+///
+/// # Safety
+///
+/// `parser` must be null or come from `argonaut_parser_new`, and not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_parser_free(parser: *mut ArgonautParser) {
+    if !parser.is_null() {
+        drop(Box::from_raw(parser));
+    }
+}
+
+/// Returns the usage line (if the spec has one) and the help message of the
+/// parser, to be freed with `argonaut_string_free`. Returns null if the
+/// parser is null.
+///
+/// # Safety
+///
+/// `parser` must be null or come from `argonaut_parser_new`.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_parser_help(parser: *const ArgonautParser) -> *mut c_char {
+    let spec = match parser.as_ref() {
+        Some(parser) => &parser.spec,
+        None => return ptr::null_mut(),
+    };
+    let parser = match Parser::from_spec(spec) {
+        Ok(parser) => parser,
+        Err(_) => return ptr::null_mut(),
+    };
+    let help = match parser.usage() {
+        Some(usage) => format!("{}\n\n{}", usage, generate_help(&parser)),
+        None => generate_help(&parser),
+    };
+    to_c_string(&help).into_raw()
+}
+
+/// Parses the `argc` arguments in `argv` (without the program name). Always
+/// returns results, which hold the error if the parse failed (see
+/// `argonaut_args_error`), and are freed with `argonaut_args_free`.
+///
+/// # Safety
+///
+/// `parser` must come from `argonaut_parser_new`, and `argv` point to `argc`
+/// nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_parse(parser: *const ArgonautParser,
+                                        argc: c_int,
+                                        argv: *const *const c_char)
+                                        -> *mut ArgonautArgs {
+    let result = match parser.as_ref() {
+        Some(parser) => read_argv(argc, argv).and_then(|args| parse_args(&parser.spec, &args)),
+        None => Err("no parser was given".to_owned()),
+    };
+
+    let mut values = HashMap::new();
+    if let Ok(ref parsed) = result {
+        for (name, value) in parsed {
+            let value = match *value {
+                Value::Single(ref value) => vec![to_c_string(value)],
+                Value::Multiple(ref values) => values.iter().map(|value| to_c_string(value))
+                                                     .collect(),
+                _ => Vec::new(),
+            };
+            values.insert(name.to_owned(), value);
+        }
+    }
+    let result = result.map_err(|message| to_c_string(&message));
+    Box::into_raw(Box::new(ArgonautArgs { result, values }))
+}
+
+/// Frees the results of a parse. Does nothing if they are null.
+///
+/// # Safety
+///
+/// `args` must be null or come from `argonaut_parse`, and neither it nor the
+/// strings returned for it be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_free(args: *mut ArgonautArgs) {
+    if !args.is_null() {
+        drop(Box::from_raw(args));
+    }
+}
+
+/// Returns the error message of the parse, or null if it succeeded.
+///
+/// # Safety
+///
+/// `args` must come from `argonaut_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_error(args: *const ArgonautArgs) -> *const c_char {
+    match args.as_ref().map(|args| &args.result) {
+        Some(Err(message)) => message.as_ptr(),
+        _ => ptr::null(),
+    }
+}
+
+/// Returns 1 if the argument was given (or has a default), and 0 otherwise.
+///
+/// # Safety
+///
+/// `args` must come from `argonaut_parse`, and `name` be a nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_is_present(args: *const ArgonautArgs,
+                                                  name: *const c_char)
+                                                  -> c_int {
+    match (args.as_ref().map(|args| &args.result), from_c_str(name)) {
+        (Some(Ok(parsed)), Some(name)) => parsed.is_present(name) as c_int,
+        _ => 0,
+    }
+}
+
+/// Returns how many times the argument was given.
+///
+/// # Safety
+///
+/// `args` must come from `argonaut_parse`, and `name` be a nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_count(args: *const ArgonautArgs,
+                                             name: *const c_char)
+                                             -> usize {
+    match (args.as_ref().map(|args| &args.result), from_c_str(name)) {
+        (Some(Ok(parsed)), Some(name)) => parsed.count(name),
+        _ => 0,
+    }
+}
+
+/// Returns the value of an argument with a single value, or null if it was
+/// not given.
+///
+/// # Safety
+///
+/// `args` must come from `argonaut_parse`, and `name` be a nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_single(args: *const ArgonautArgs,
+                                              name: *const c_char)
+                                              -> *const c_char {
+    match values_of(args, name) {
+        [value] => value.as_ptr(),
+        _ => ptr::null(),
+    }
+}
+
+/// Returns the number of values of an argument with multiple values.
+///
+/// # Safety
+///
+/// `args` must come from `argonaut_parse`, and `name` be a nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_multiple_len(args: *const ArgonautArgs,
+                                                    name: *const c_char)
+                                                    -> usize {
+    values_of(args, name).len()
+}
+
+/// Returns the value at the index of an argument with multiple values, or
+/// null if there is none.
+///
+/// # Safety
+///
+/// `args` must come from `argonaut_parse`, and `name` be a nul-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_args_multiple_at(args: *const ArgonautArgs,
+                                                   name: *const c_char,
+                                                   index: usize)
+                                                   -> *const c_char {
+    values_of(args, name).get(index).map_or(ptr::null(), |value| value.as_ptr())
+}
+
+/// Frees a string returned by another function. Does nothing if it is null.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned as `char *` by argonaut, and not
+/// be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn argonaut_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
@@ -0,0 +1,84 @@
+//! The decision of whether to color the output, which the help message and
+//! the error reports both go through.
+
+use std::env;
+use std::io::{self, IsTerminal};
+
+use typed::Choices;
+
+/// When to color the output written by the reporting helpers.
+///
+/// It can be the value of a `--color` option (see `Parser::define_color`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color the output when it is written to a terminal, unless `NO_COLOR`
+    /// is set (to anything but an empty value), or when `CLICOLOR_FORCE` is
+    /// set (to anything but `0`).
+    #[default]
+    Auto,
+    /// Always color the output.
+    Always,
+    /// Never color the output.
+    Never,
+}
+
+impl ColorChoice {
+    /// Returns whether output written to stderr should be colored.
+    pub fn for_stderr(self) -> bool {
+        self.enabled(|| io::stderr().is_terminal())
+    }
+
+    /// Returns whether output written to stdout should be colored.
+    pub fn for_stdout(self) -> bool {
+        self.enabled(|| io::stdout().is_terminal())
+    }
+
+    /// Decides whether to color output written to a stream, which is checked
+    /// to be a terminal if needed.
+    fn enabled<F: FnOnce() -> bool>(self, is_terminal: F) -> bool {
+        let set = |var: &str| env::var_os(var).filter(|value| !value.is_empty());
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if set("NO_COLOR").is_some() => false,
+            ColorChoice::Auto if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") => true,
+            ColorChoice::Auto => {
+                is_terminal() && env::var_os("TERM").is_none_or(|term| term != "dumb")
+            }
+        }
+    }
+}
+
+impl Choices for ColorChoice {
+    fn variants() -> &'static [&'static str] {
+        &["auto", "always", "never"]
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+/// The styles used when coloring output.
+#[derive(Debug, Clone, Copy)]
+pub enum Style {
+    Error,
+    Emphasis,
+}
+
+/// Wraps the text in the escape codes of the style if coloring is enabled.
+pub fn paint(text: &str, style: Style, enabled: bool) -> String {
+    if !enabled {
+        return text.to_owned();
+    }
+    let code = match style {
+        Style::Error => "1;31",
+        Style::Emphasis => "1",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
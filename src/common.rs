@@ -3,7 +3,9 @@ use std::fmt;
 /// A single name of a flag.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FlagName<'a> {
+    /// A short flag (`-v`).
     Short(char),
+    /// A long flag, without its dashes (`--verbose`).
     Long(&'a str),
 }
 
@@ -0,0 +1,395 @@
+//! A clap-like builder on top of the parser, to ease migrating from clap.
+//!
+//! Only the common parts of the clap 2 interface are covered:
+//!
+//! ```text
+//! use argonaut::compat::clap::{App, Arg};
+//!
+//! let matches = App::new("tool")
+//!     .version("1.0")
+//!     .arg(Arg::with_name("input").required(true))
+//!     .arg(Arg::with_name("output").short("o").long("output").takes_value(true))
+//!     .get_matches();
+//! ```
+//!
+//! Unlike clap, every optional argument is given a long flag, which is the
+//! name of the argument unless `long` is used, and single positional
+//! arguments are always required.
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::process;
+
+use arg;
+use parsed::{ParsedArgs, Value};
+use parser::Parser;
+use utils::generate_help;
+
+/// An argument definition, like `clap::Arg`.
+#[derive(Debug, Clone)]
+pub struct Arg<'a> {
+    name: &'a str,
+    short: Option<char>,
+    long: Option<&'a str>,
+    takes_value: bool,
+    multiple: bool,
+    required: bool,
+    help: Option<&'a str>,
+    value_name: Option<&'a str>,
+    possible_values: Option<&'a [&'a str]>,
+    default_value: Option<&'a str>,
+}
+
+impl<'a> Arg<'a> {
+    /// Creates an argument with the given name.
+    pub fn with_name(name: &'a str) -> Arg<'a> {
+        Arg {
+            name,
+            short: None,
+            long: None,
+            takes_value: false,
+            multiple: false,
+            required: false,
+            help: None,
+            value_name: None,
+            possible_values: None,
+            default_value: None,
+        }
+    }
+
+    /// Sets the short flag (only the first character is used).
+    pub fn short(mut self, short: &str) -> Self {
+        self.short = short.trim_start_matches('-').chars().next();
+        self
+    }
+
+    /// Sets the long flag.
+    pub fn long(mut self, long: &'a str) -> Self {
+        self.long = Some(long.trim_start_matches('-'));
+        self
+    }
+
+    /// Sets whether the argument takes a value.
+    pub fn takes_value(mut self, takes_value: bool) -> Self {
+        self.takes_value = takes_value;
+        self
+    }
+
+    /// Sets whether the argument takes multiple values.
+    pub fn multiple(mut self, multiple: bool) -> Self {
+        self.multiple = multiple;
+        self
+    }
+
+    /// Sets whether the argument must be given.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Sets the help text.
+    pub fn help(mut self, help: &'a str) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    /// Sets the name of the value shown in the help message.
+    pub fn value_name(mut self, name: &'a str) -> Self {
+        self.value_name = Some(name);
+        self.takes_value = true;
+        self
+    }
+
+    /// Limits the values of the argument to the given ones.
+    pub fn possible_values(mut self, values: &'a [&'a str]) -> Self {
+        self.possible_values = Some(values);
+        self.takes_value = true;
+        self
+    }
+
+    /// Sets the value used when the argument is not given.
+    pub fn default_value(mut self, value: &'a str) -> Self {
+        self.default_value = Some(value);
+        self.takes_value = true;
+        self
+    }
+
+    /// Returns whether this is a positional argument (it has no flags).
+    fn is_positional(&self) -> bool {
+        self.short.is_none() && self.long.is_none()
+    }
+
+    /// Returns the matching argonaut definition.
+    fn to_arg(&self) -> arg::Arg<'a> {
+        let mut def: arg::Arg = if self.is_positional() {
+            match (self.multiple, self.required) {
+                (false, _) => arg::Arg::positional(self.name).into(),
+                (true, false) => arg::Arg::optional_trail(self.name).into(),
+                (true, true) => arg::Arg::required_trail(self.name).into(),
+            }
+        } else {
+            let long = self.long.unwrap_or(self.name);
+            let opt = match self.short {
+                Some(short) => arg::Arg::named_and_short(long, short),
+                None => arg::Arg::named(long),
+            };
+            match (self.takes_value, self.multiple) {
+                (false, false) => opt.switch().into(),
+                (false, true) => opt.count().into(),
+                (true, false) => opt.single().into(),
+                (true, true) => opt.one_or_more().into(),
+            }
+        };
+        if let Some(help) = self.help {
+            def = def.set_help(help);
+        }
+        if let Some(name) = self.value_name {
+            def = arg::internal_set_param(def, name);
+        }
+        if let Some(values) = self.possible_values {
+            def = arg::internal_set_choices(def, values);
+        }
+        if let Some(value) = self.default_value {
+            def = arg::internal_set_default(def, value);
+        }
+        def
+    }
+}
+
+/// The kind of an `Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The help message was requested, and is the message of the error.
+    HelpDisplayed,
+    /// The version was requested, and is the message of the error.
+    VersionDisplayed,
+    /// A required argument was not given.
+    MissingRequiredArgument,
+    /// The arguments could not be parsed.
+    InvalidArguments,
+}
+
+/// An error from `App::get_matches_from_safe`, like `clap::Error`.
+#[derive(Debug, Clone)]
+pub struct Error {
+    /// The message to show to the user.
+    pub message: String,
+    /// The kind of error.
+    pub kind: ErrorKind,
+}
+
+impl Error {
+    /// Prints the message and exits the program, successfully if the help or
+    /// version was requested.
+    pub fn exit(&self) -> ! {
+        match self.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => {
+                println!("{}", self.message);
+                process::exit(0);
+            }
+            _ => {
+                eprintln!("{}", self.message);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+/// A command-line application, like `clap::App`.
+#[derive(Debug, Clone)]
+pub struct App<'a> {
+    name: &'a str,
+    version: Option<&'a str>,
+    about: Option<&'a str>,
+    args: Vec<Arg<'a>>,
+}
+
+impl<'a> App<'a> {
+    /// Creates an application with the given name.
+    pub fn new(name: &'a str) -> App<'a> {
+        App {
+            name,
+            version: None,
+            about: None,
+            args: Vec::new(),
+        }
+    }
+
+    /// Sets the version, which adds a `--version` flag.
+    pub fn version(mut self, version: &'a str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Sets the description shown in the help message.
+    pub fn about(mut self, about: &'a str) -> Self {
+        self.about = Some(about);
+        self
+    }
+
+    /// Adds an argument.
+    pub fn arg(mut self, arg: Arg<'a>) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Adds several arguments.
+    pub fn args(mut self, args: &[Arg<'a>]) -> Self {
+        self.args.extend(args.iter().cloned());
+        self
+    }
+
+    /// Parses the arguments of the program, and exits on errors or when the
+    /// help or version was requested.
+    pub fn get_matches(self) -> ArgMatches {
+        self.get_matches_from(env::args())
+    }
+
+    /// Parses the given arguments (starting with the program name), and exits
+    /// on errors or when the help or version was requested.
+    pub fn get_matches_from<I, S>(self, args: I) -> ArgMatches
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.get_matches_from_safe(args).unwrap_or_else(|err| err.exit())
+    }
+
+    /// Parses the given arguments (starting with the program name).
+    pub fn get_matches_from_safe<I, S>(self, args: I) -> Result<ArgMatches, Error>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        let argv: Vec<String> = args.into_iter()
+                                    .skip(1)
+                                    .map(|arg| arg.as_ref().to_owned())
+                                    .collect();
+
+        let mut parser = Parser::new();
+        let mut defs: Vec<arg::Arg> = self.args.iter().map(|arg| arg.to_arg()).collect();
+        defs.push(arg::Arg::named_and_short("help", 'h')
+                      .interrupt()
+                      .set_precedence(1)
+                      .set_help("Prints help information")
+                      .into());
+        if self.version.is_some() {
+            defs.push(arg::Arg::named_and_short("version", 'V')
+                          .interrupt()
+                          .set_help("Prints version information")
+                          .into());
+        }
+        // Invalid definitions are bugs in the application, like in clap
+        parser.define(&defs).unwrap_or_else(|err| panic!("{}", err));
+
+        let invalid = |message: String| {
+            Error {
+                message: format!("error: {}", message),
+                kind: ErrorKind::InvalidArguments,
+            }
+        };
+        let args = ParsedArgs::from_parse(parser.parse(&argv))
+                           .map_err(|err| invalid(err.to_string()))?;
+
+        if args.is_present("help") {
+            return Err(Error {
+                message: self.help(&parser),
+                kind: ErrorKind::HelpDisplayed,
+            });
+        }
+        if args.is_present("version") {
+            return Err(Error {
+                message: format!("{} {}", self.name, self.version.unwrap_or("")),
+                kind: ErrorKind::VersionDisplayed,
+            });
+        }
+
+        // Store the values under the clap names, rather than the long flags
+        let mut matches = ParsedArgs::new();
+        for arg in &self.args {
+            let key = if arg.is_positional() {
+                arg.name
+            } else {
+                arg.long.unwrap_or(arg.name)
+            };
+            let value = match (args.get(key).cloned(), arg.default_value) {
+                (Some(value), _) => value,
+                (None, Some(default)) => Value::Single(default.to_owned()),
+                (None, None) if arg.required => {
+                    return Err(Error {
+                        message: format!("error: the argument '{}' is required", arg.name),
+                        kind: ErrorKind::MissingRequiredArgument,
+                    });
+                }
+                (None, None) => continue,
+            };
+            matches.insert(arg.name, value);
+        }
+        Ok(ArgMatches { args: matches })
+    }
+
+    /// Returns the help message for the application.
+    fn help(&self, parser: &Parser) -> String {
+        let mut help = String::from(self.name);
+        if let Some(version) = self.version {
+            help.push(' ');
+            help.push_str(version);
+        }
+        if let Some(about) = self.about {
+            help.push('\n');
+            help.push_str(about);
+        }
+        help.push_str("\n\n");
+        help.push_str(&generate_help(parser));
+        help
+    }
+}
+
+/// The parsed arguments of an `App`, like `clap::ArgMatches`.
+#[derive(Debug, Clone, Default)]
+pub struct ArgMatches {
+    args: ParsedArgs,
+}
+
+impl ArgMatches {
+    /// Returns whether the argument was given (or has a default value).
+    pub fn is_present(&self, name: &str) -> bool {
+        self.args.is_present(name)
+    }
+
+    /// Returns the (first) value of the argument.
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        match self.args.get(name) {
+            Some(Value::Single(value)) => Some(value),
+            Some(Value::Multiple(values)) => values.first().map(|value| value.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of the argument.
+    pub fn values_of(&self, name: &str) -> Option<Vec<&str>> {
+        match self.args.get(name) {
+            Some(Value::Single(value)) => Some(vec![value.as_str()]),
+            Some(Value::Multiple(values)) => Some(values.iter().map(|value| value.as_str()).collect()),
+            _ => None,
+        }
+    }
+
+    /// Returns how many times the argument was given (at most once, unless
+    /// it is a flag with `multiple(true)`).
+    pub fn occurrences_of(&self, name: &str) -> u64 {
+        self.args.count(name) as u64
+    }
+
+    /// Returns the collected arguments.
+    pub fn parsed_args(&self) -> &ParsedArgs {
+        &self.args
+    }
+}
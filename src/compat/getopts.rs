@@ -0,0 +1,283 @@
+//! A getopts-like interface on top of the parser, for porting code from
+//! getopts one call site at a time.
+//!
+//! ```text
+//! use argonaut::compat::getopts::Options;
+//!
+//! let mut opts = Options::new();
+//! opts.optopt("o", "output", "set the output file", "NAME");
+//! opts.optflag("h", "help", "print this help menu");
+//! let matches = opts.parse(&args[1..])?;
+//! let output = matches.opt_str("o");
+//! ```
+//!
+//! Unlike getopts, every option is given a long flag (the short name is used
+//! if the long name is empty), and multiple values for an option are given
+//! after a single flag (`-i a b`) rather than by repeating the flag.
+
+use std::error;
+use std::fmt;
+
+use arg::{self, Arg};
+use parsed::{ParsedArgs, Value};
+use parser::{ParseError, Parser};
+use utils::generate_help;
+
+/// The name used for the free arguments (which no option can have).
+const FREE: &str = "\u{0}free";
+
+/// How many values an option takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HasArg {
+    No,
+    Count,
+    Yes,
+    Multi,
+}
+
+/// A single option definition.
+#[derive(Debug, Clone)]
+struct OptGroup {
+    short: String,
+    long: String,
+    desc: String,
+    hint: String,
+    has_arg: HasArg,
+    required: bool,
+}
+
+impl OptGroup {
+    /// Returns the long flag name used for the option.
+    fn flag(&self) -> &str {
+        if self.long.is_empty() { &self.short } else { &self.long }
+    }
+}
+
+/// A set of options, like `getopts::Options`.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    groups: Vec<OptGroup>,
+}
+
+impl Options {
+    /// Creates an empty set of options.
+    pub fn new() -> Options {
+        Options { groups: Vec::new() }
+    }
+
+    fn opt(&mut self,
+           short: &str,
+           long: &str,
+           desc: &str,
+           hint: &str,
+           has_arg: HasArg,
+           required: bool)
+           -> &mut Options {
+        self.groups.push(OptGroup {
+            short: short.to_owned(),
+            long: long.to_owned(),
+            desc: desc.to_owned(),
+            hint: hint.to_owned(),
+            has_arg,
+            required,
+        });
+        self
+    }
+
+    /// Adds a flag without a value.
+    pub fn optflag(&mut self, short: &str, long: &str, desc: &str) -> &mut Options {
+        self.opt(short, long, desc, "", HasArg::No, false)
+    }
+
+    /// Adds a flag without a value that can be given several times.
+    pub fn optflagmulti(&mut self, short: &str, long: &str, desc: &str) -> &mut Options {
+        self.opt(short, long, desc, "", HasArg::Count, false)
+    }
+
+    /// Adds an optional option taking a value.
+    pub fn optopt(&mut self, short: &str, long: &str, desc: &str, hint: &str) -> &mut Options {
+        self.opt(short, long, desc, hint, HasArg::Yes, false)
+    }
+
+    /// Adds a required option taking a value.
+    pub fn reqopt(&mut self, short: &str, long: &str, desc: &str, hint: &str) -> &mut Options {
+        self.opt(short, long, desc, hint, HasArg::Yes, true)
+    }
+
+    /// Adds an optional option taking one or more values.
+    pub fn optmulti(&mut self, short: &str, long: &str, desc: &str, hint: &str) -> &mut Options {
+        self.opt(short, long, desc, hint, HasArg::Multi, false)
+    }
+
+    /// Returns the definitions of the options, followed by those of the free
+    /// arguments.
+    fn definitions(&self) -> Vec<Arg<'_>> {
+        let mut defs = Vec::new();
+        for group in &self.groups {
+            let opt = match group.short.chars().next() {
+                Some(short) => Arg::named_and_short(group.flag(), short),
+                None => Arg::named(group.flag()),
+            };
+            let mut arg: Arg = match group.has_arg {
+                HasArg::No => opt.switch().into(),
+                HasArg::Count => opt.count().into(),
+                HasArg::Yes => opt.single().into(),
+                HasArg::Multi => opt.one_or_more().into(),
+            };
+            arg = arg.set_help(&group.desc);
+            if group.required {
+                arg = arg::internal_set_required(arg);
+            }
+            if !group.hint.is_empty() {
+                arg = arg::internal_set_param(arg, &group.hint);
+            }
+            defs.push(arg);
+        }
+        defs.push(Arg::optional_trail(FREE).into());
+        // Everything after '--' is free as well
+        defs.push(Arg::named("").passalong().into());
+        defs
+    }
+
+    /// Parses the given arguments (without the program name).
+    pub fn parse<S: AsRef<str>>(&self, args: &[S]) -> Result<Matches, Fail> {
+        let mut parser = Parser::new();
+        parser.define(&self.definitions()).map_err(Fail::OptionDuplicated)?;
+
+        let parsed = ParsedArgs::from_parse(parser.parse(args)).map_err(Fail::from_parse_error)?;
+        let mut free = parsed.multiple(FREE).map(|free| free.to_vec()).unwrap_or_default();
+        free.extend(parsed.multiple("").unwrap_or(&[]).iter().cloned());
+        Ok(Matches {
+            groups: self.groups.clone(),
+            parsed,
+            free,
+        })
+    }
+
+    /// Returns the help message for the options, after the given brief text.
+    pub fn usage(&self, brief: &str) -> String {
+        let mut parser = Parser::new();
+        let defs = self.definitions();
+        // The free arguments are not listed with the options
+        let _ = parser.define(&defs[..self.groups.len()]);
+        format!("{}\n\n{}", brief, generate_help(&parser))
+    }
+}
+
+/// An error from `Options::parse`, like `getopts::Fail`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fail {
+    /// The option requires a value, but none was given.
+    ArgumentMissing(String),
+    /// The option is not defined.
+    UnrecognizedOption(String),
+    /// A required option was not given.
+    OptionMissing(String),
+    /// The option was given more than once.
+    OptionDuplicated(String),
+    /// The option was given a value it does not accept.
+    UnexpectedArgument(String),
+}
+
+impl Fail {
+    fn from_parse_error(err: ParseError) -> Fail {
+        use parser::ParseError::*;
+        match err {
+            UnknownOptionalArgument { arg, .. } => Fail::UnrecognizedOption(arg.to_owned()),
+            GroupedNonSwitch { invalid, .. } => Fail::ArgumentMissing(invalid),
+            MissingParameter { arg } => Fail::ArgumentMissing(arg.to_owned()),
+            MissingPositionalArgument { arg } | MissingOption { arg } | MissingTrail { arg } |
+            MissingOptionIf { arg, .. } | MissingOptionIfValue { arg, .. } |
+            MissingOptionUnless { arg, .. } => {
+                Fail::OptionMissing(arg.to_owned())
+            }
+            MissingGroup { group, .. } => Fail::OptionMissing(group.to_owned()),
+            DuplicatePositionalArgument { arg } => Fail::OptionDuplicated(arg.to_owned()),
+            ConflictingGroup { other, .. } => Fail::OptionDuplicated(other.to_owned()),
+            UnexpectedArgument { arg, .. } => Fail::UnexpectedArgument(arg.to_owned()),
+            InvalidChoice { arg, .. } | InvalidValue { arg, .. } => {
+                Fail::UnexpectedArgument(arg.to_owned())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Fail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Fail::ArgumentMissing(ref name) => write!(f, "Argument to option '{}' missing", name),
+            Fail::UnrecognizedOption(ref name) => write!(f, "Unrecognized option: '{}'", name),
+            Fail::OptionMissing(ref name) => write!(f, "Required option '{}' missing", name),
+            Fail::OptionDuplicated(ref name) => {
+                write!(f, "Option '{}' given more than once", name)
+            }
+            Fail::UnexpectedArgument(ref name) => {
+                write!(f, "Option '{}' does not take an argument", name)
+            }
+        }
+    }
+}
+
+impl error::Error for Fail {}
+
+/// The result of `Options::parse`, like `getopts::Matches`.
+#[derive(Debug, Clone)]
+pub struct Matches {
+    groups: Vec<OptGroup>,
+    parsed: ParsedArgs,
+    /// The arguments that are not options.
+    pub free: Vec<String>,
+}
+
+impl Matches {
+    /// Returns the value of the option with the given short or long name.
+    fn value(&self, name: &str) -> Option<&Value> {
+        self.groups
+            .iter()
+            .find(|group| group.short == name || group.long == name)
+            .and_then(|group| self.parsed.get(group.flag()))
+    }
+
+    /// Returns whether the option was given.
+    pub fn opt_present(&self, name: &str) -> bool {
+        self.value(name).is_some()
+    }
+
+    /// Returns whether any of the options were given.
+    pub fn opts_present(&self, names: &[String]) -> bool {
+        names.iter().any(|name| self.opt_present(name))
+    }
+
+    /// Returns the (first) value of the option.
+    pub fn opt_str(&self, name: &str) -> Option<String> {
+        self.opt_strs(name).into_iter().next()
+    }
+
+    /// Returns all the values of the option.
+    pub fn opt_strs(&self, name: &str) -> Vec<String> {
+        match self.value(name) {
+            Some(Value::Single(value)) => vec![value.clone()],
+            Some(Value::Multiple(values)) => values.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the value of the option, or the default if it was not given.
+    pub fn opt_default(&self, name: &str, default: &str) -> Option<String> {
+        if self.opt_present(name) {
+            Some(self.opt_str(name).unwrap_or_else(|| default.to_owned()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns how many times the option was given (at most once, unless it
+    /// was added with `optflagmulti`).
+    pub fn opt_count(&self, name: &str) -> usize {
+        match self.value(name) {
+            Some(&Value::Count(count)) => count,
+            Some(_) => 1,
+            None => 0,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Interfaces of other argument parsers, implemented with this one.
+
+pub mod clap;
+pub mod getopts;
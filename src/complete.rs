@@ -0,0 +1,320 @@
+//! Completion of arguments by the program itself, using the definitions of
+//! its parser.
+//!
+//! The shell runs the program with `_ARGONAUT_COMPLETE` set to its name and
+//! the words of the command line as arguments (the last one being the word to
+//! complete). `complete_from_env` then prints the candidates, one per line,
+//! and exits. `completion_script` returns the snippet that sets this up, and
+//! `Parser::define_completions` adds a `--completions <SHELL>` interrupt that
+//! prints it:
+//!
+//! ```text
+//! parser.define_completions()?;
+//! argonaut::complete_from_env(&parser);
+//! ```
+//!
+//! A value of an argument with a `ValueHint` and no choices is left to the
+//! shell, by giving the hint as the only candidate (`:file`, `:dir`, `:host`,
+//! `:user` or `:command`).
+
+use std::env;
+use std::path::Path;
+use std::process;
+
+use arg::{self, Arg, ArgType, ValueHint};
+use common::OptName;
+use parser::{Parser, StructuredArgument};
+use typed::Choices;
+
+/// The environment variable that asks the program to complete its arguments.
+pub const COMPLETE_VAR: &str = "_ARGONAUT_COMPLETE";
+
+/// A shell that arguments can be completed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shell {
+    /// Bash, with the `complete` builtin.
+    Bash,
+    /// Zsh, with its completion system (`compinit`).
+    Zsh,
+    /// Fish.
+    Fish,
+}
+
+impl Choices for Shell {
+    fn variants() -> &'static [&'static str] {
+        &["bash", "zsh", "fish"]
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the definition with the given flag (like `--out` or `-o`), which
+/// may be one of its aliases.
+fn find_flag<'a>(args: &[Arg<'a>], flag: &str) -> Option<Arg<'a>> {
+    args.iter().cloned().find(|arg| {
+        let mut aliases = arg.aliases().iter().chain(arg.hidden_aliases());
+        if aliases.any(|alias| flag == format!("--{}", alias)) {
+            return true;
+        }
+        match arg.option_name() {
+            Some(OptName::Normal(long)) => flag == format!("--{}", long),
+            Some(OptName::NormalAndShort(long, short)) => {
+                flag == format!("--{}", long) || flag == format!("-{}", short)
+            }
+            None => false,
+        }
+    })
+}
+
+/// Returns whether the argument is the interrupt of
+/// `Parser::define_completions`.
+fn is_completions(arg: Arg) -> bool {
+    matches!(arg::internal_get_raw(arg), ArgType::Interrupt(name) if name.name() == "completions")
+}
+
+/// Returns the choices of the argument that start with the prefix, or its
+/// value hint for the shell.
+fn values(arg: Arg, prefix: &str) -> Vec<String> {
+    if let Some(choices) = arg.choices() {
+        return choices.iter()
+                      .filter(|choice| choice.starts_with(prefix))
+                      .map(|choice| choice.to_owned())
+                      .collect();
+    }
+    let hint = match arg.value_hint() {
+        _ if prefix.starts_with('-') => return Vec::new(),
+        Some(ValueHint::FilePath) => ":file",
+        Some(ValueHint::DirPath) => ":dir",
+        Some(ValueHint::Hostname) => ":host",
+        Some(ValueHint::Username) => ":user",
+        Some(ValueHint::CommandName) => ":command",
+        None => return Vec::new(),
+    };
+    vec![hint.to_owned()]
+}
+
+/// Returns the candidates for the last of the words, which are the arguments
+/// given so far (without the program name).
+///
+/// Flags are completed from their names, and values from the choices or the
+/// value hint of the argument they are given to.
+pub fn complete<S: AsRef<str>>(parser: &Parser, words: &[S]) -> Vec<String> {
+    let args = parser.args().as_slice();
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (current.as_ref(), before),
+        None => ("", words),
+    };
+
+    // Walk the earlier words to find what the current one is given to
+    let mut positionals = 0;
+    let mut single = None;
+    let mut multiple = None;
+    let mut flags_ended = false;
+    for (i, word) in before.iter().enumerate() {
+        let word = word.as_ref();
+        if single.take().is_some() {
+            continue;
+        }
+        if !flags_ended && word == "--" {
+            flags_ended = true;
+            multiple = None;
+            continue;
+        }
+        if !flags_ended && word.starts_with('-') && word != "-" {
+            multiple = None;
+            match find_flag(args, word).map(|arg| (arg, arg::internal_get_raw(arg))) {
+                Some((arg, ArgType::OptSingle(_))) => single = Some(arg),
+                Some((arg, ArgType::OptZeroPlus(_))) |
+                Some((arg, ArgType::OptOnePlus(_))) => multiple = Some(arg),
+                // Everything after these belongs to them
+                Some((arg, ArgType::Interrupt(_))) if is_completions(arg) &&
+                                                      i + 1 == before.len() => {
+                    return Shell::variants()
+                               .iter()
+                               .filter(|shell| shell.starts_with(current))
+                               .map(|shell| shell.to_string())
+                               .collect();
+                }
+                Some((_, ArgType::Interrupt(_))) => return Vec::new(),
+                Some((arg, ArgType::PassAlong(_))) if i + 1 == before.len() => {
+                    return values(arg, current);
+                }
+                Some((_, ArgType::PassAlong(_))) => return Vec::new(),
+                _ => {}
+            }
+            continue;
+        }
+        if multiple.is_none() {
+            positionals += 1;
+        }
+    }
+
+    if let Some(arg) = single {
+        return values(arg, current);
+    }
+    let mut candidates = Vec::new();
+    if let Some(arg) = multiple {
+        candidates.extend(values(arg, current));
+    }
+    if !flags_ended && current.starts_with('-') {
+        for arg in args {
+            match arg.option_name() {
+                Some(OptName::Normal(long)) => candidates.push(format!("--{}", long)),
+                Some(OptName::NormalAndShort(long, short)) => {
+                    candidates.push(format!("--{}", long));
+                    candidates.push(format!("-{}", short));
+                }
+                None => {}
+            }
+            candidates.extend(arg.aliases().iter().map(|alias| format!("--{}", alias)));
+            let switch = matches!(arg::internal_get_raw(*arg), ArgType::Switch(_));
+            if switch && parser.negates_switches() && !arg.is_counted() {
+                candidates.push(format!("--no-{}", arg.name()));
+            }
+        }
+        candidates.retain(|candidate| candidate.starts_with(current));
+    } else if multiple.is_none() {
+        // The positional arguments are followed by the trail, if any
+        let mut seen = 0;
+        for &arg in args {
+            match arg::internal_get_raw(arg) {
+                ArgType::Single(_) if seen == positionals => {
+                    candidates.extend(values(arg, current));
+                    break;
+                }
+                ArgType::Single(_) => seen += 1,
+                ArgType::ZeroPlus(_) | ArgType::OnePlus(_) if seen <= positionals => {
+                    candidates.extend(values(arg, current));
+                }
+                _ => {}
+            }
+        }
+    }
+    candidates
+}
+
+/// Answers a completion query if `_ARGONAUT_COMPLETE` is set, by printing the
+/// candidates for the arguments of the program and exiting.
+///
+/// Also handles the interrupt of `Parser::define_completions`, by printing
+/// the completion script for the given shell and exiting. Call this before
+/// parsing the arguments.
+pub fn complete_from_env(parser: &Parser) {
+    if env::var_os(COMPLETE_VAR).is_some() {
+        let words: Vec<String> = env::args().skip(1).collect();
+        for candidate in complete(parser, &words) {
+            println!("{}", candidate);
+        }
+        process::exit(0);
+    }
+
+    if !parser.args().any(|&arg| is_completions(arg)) {
+        return;
+    }
+    // Errors before the interrupt are left to the real parse
+    for item in parser.parse_env() {
+        let args = match item {
+            Ok(StructuredArgument::Interrupt { name: "completions", args }) => args,
+            Ok(_) => continue,
+            Err(_) => return,
+        };
+        let program = env::args().next().unwrap_or_default();
+        let program = Path::new(&program).file_name()
+                                         .and_then(|name| name.to_str())
+                                         .unwrap_or("");
+        match args.first().and_then(|name| Shell::from_str(name)) {
+            Some(shell) => {
+                print!("{}", completion_script(shell, program));
+                process::exit(0);
+            }
+            None => {
+                eprintln!("expected a shell after --completions (choose from: {})",
+                          Shell::variants().join(", "));
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Returns the shell snippet that completes the arguments of the program by
+/// asking it (see `complete_from_env`).
+///
+/// The snippet is meant to be sourced by the shell, e.g. from `.bashrc` with
+/// `source <(prog --completions bash)`.
+pub fn completion_script(shell: Shell, program: &str) -> String {
+    let function: String = program.chars()
+                                  .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+                                  .collect();
+    match shell {
+        Shell::Bash => {
+            format!("_{function}_complete() {{\n\
+                     \x20   local IFS=$'\\n' cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n\
+                     \x20   COMPREPLY=($({var}=bash \"${{COMP_WORDS[0]}}\" \
+                     \"${{COMP_WORDS[@]:1:COMP_CWORD}}\"))\n\
+                     \x20   case \"${{COMPREPLY[0]}}\" in\n\
+                     \x20       :file) compopt -o filenames; \
+                     COMPREPLY=($(compgen -f -- \"$cur\")) ;;\n\
+                     \x20       :dir) compopt -o filenames; \
+                     COMPREPLY=($(compgen -d -- \"$cur\")) ;;\n\
+                     \x20       :host) COMPREPLY=($(compgen -A hostname -- \"$cur\")) ;;\n\
+                     \x20       :user) COMPREPLY=($(compgen -u -- \"$cur\")) ;;\n\
+                     \x20       :command) COMPREPLY=($(compgen -c -- \"$cur\")) ;;\n\
+                     \x20   esac\n\
+                     }}\n\
+                     complete -F _{function}_complete {program}\n",
+                    function = function,
+                    var = COMPLETE_VAR,
+                    program = program)
+        }
+        Shell::Zsh => {
+            format!("_{function}_complete() {{\n\
+                     \x20   local -a candidates\n\
+                     \x20   candidates=(${{(f)\"$({var}=zsh \"${{words[1]}}\" \
+                     \"${{(@)words[2,CURRENT]}}\")\"}})\n\
+                     \x20   case $candidates[1] in\n\
+                     \x20       :file) _files ;;\n\
+                     \x20       :dir) _files -/ ;;\n\
+                     \x20       :host) _hosts ;;\n\
+                     \x20       :user) _users ;;\n\
+                     \x20       :command) _command_names ;;\n\
+                     \x20       *) compadd -a candidates ;;\n\
+                     \x20   esac\n\
+                     }}\n\
+                     compdef _{function}_complete {program}\n",
+                    function = function,
+                    var = COMPLETE_VAR,
+                    program = program)
+        }
+        Shell::Fish => {
+            format!("function __{function}_complete\n\
+                     \x20   set -l candidates (env {var}=fish {program} \
+                     (commandline -opc)[2..-1] (commandline -ct))\n\
+                     \x20   switch \"$candidates[1]\"\n\
+                     \x20       case :file\n\
+                     \x20           __fish_complete_path (commandline -ct)\n\
+                     \x20       case :dir\n\
+                     \x20           __fish_complete_directories (commandline -ct)\n\
+                     \x20       case :host\n\
+                     \x20           __fish_print_hostnames\n\
+                     \x20       case :user\n\
+                     \x20           __fish_complete_users\n\
+                     \x20       case :command\n\
+                     \x20           __fish_complete_command\n\
+                     \x20       case '*'\n\
+                     \x20           printf '%s\\n' $candidates\n\
+                     \x20   end\n\
+                     end\n\
+                     complete -c {program} -f -a '(__{function}_complete)'\n",
+                    function = function,
+                    var = COMPLETE_VAR,
+                    program = program)
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! The location of the configuration files of a program, following the
+//! conventions of each platform.
+//!
+//! The configuration directory is `$XDG_CONFIG_HOME` (or `~/.config`) on
+//! Linux and other Unix systems, `~/Library/Application Support` on macOS and
+//! `%APPDATA%` on Windows. A program keeps its files in a directory of its
+//! own in there, which `config_file` points to:
+//!
+//! ```text
+//! // ~/.config/mytool/config on Linux
+//! if let Some(path) = argonaut::config_file("mytool", "config") {
+//!     args = ParsedArgs::from_config(&parser, &path)?;
+//! }
+//! ```
+
+use std::env;
+use std::path::PathBuf;
+
+/// Returns the value of the environment variable as a path, if it is set to
+/// an absolute one.
+fn absolute_var(name: &str) -> Option<PathBuf> {
+    env::var_os(name).map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
+/// Returns the directory of the configuration files of the user, if it can
+/// be found.
+#[cfg(windows)]
+pub fn config_dir() -> Option<PathBuf> {
+    absolute_var("APPDATA")
+}
+
+/// Returns the directory of the configuration files of the user, if it can
+/// be found.
+#[cfg(target_os = "macos")]
+pub fn config_dir() -> Option<PathBuf> {
+    absolute_var("HOME").map(|home| home.join("Library").join("Application Support"))
+}
+
+/// Returns the directory of the configuration files of the user, if it can
+/// be found.
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn config_dir() -> Option<PathBuf> {
+    absolute_var("XDG_CONFIG_HOME").or_else(|| {
+        absolute_var("HOME").map(|home| home.join(".config"))
+    })
+}
+
+/// Returns the path of a configuration file of the program, in its own
+/// directory of the configuration directory (see `config_dir`).
+///
+/// The file need not exist.
+pub fn config_file(program: &str, file: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(program).join(file))
+}
@@ -0,0 +1,354 @@
+//! Deserialization of collected arguments into user types with serde.
+//!
+//! Every argument is a field named after the argument, with the dashes
+//! replaced by underscores (`dry-run` becomes `dry_run`). Values are converted
+//! from their text as the field type requests:
+//!
+//! - Switches are `true`, and `false` when missing or negated. Counted
+//!   switches can also be integers.
+//! - Single values become strings, numbers, characters, booleans or unit enum
+//!   variants (eg. for choices).
+//! - Multiple values become sequences, and a single value becomes a sequence
+//!   with one item. Missing sequences are empty.
+//! - Missing arguments become `None` for option fields.
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess,
+                SeqAccess, Visitor};
+
+use parsed::{ParsedArgs, Value};
+use parser::{Parse, ParseError};
+use typed;
+
+/// An error found when deserializing arguments.
+#[derive(Debug)]
+pub enum Error<'def, 'argv> {
+    /// The arguments could not be parsed.
+    Parse(ParseError<'def, 'argv>),
+    /// A value could not be converted to the type of its field.
+    Message(String),
+}
+
+impl<'def, 'argv> fmt::Display for Error<'def, 'argv> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse(ref err) => err.fmt(f),
+            Error::Message(ref message) => f.write_str(message),
+        }
+    }
+}
+
+impl<'def, 'argv> error::Error for Error<'def, 'argv> {}
+
+impl<'def, 'argv> de::Error for Error<'def, 'argv> {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl<'def, 'argv> From<ParseError<'def, 'argv>> for Error<'def, 'argv> {
+    fn from(err: ParseError<'def, 'argv>) -> Self {
+        Error::Parse(err)
+    }
+}
+
+/// Runs the parse to its end and deserializes the arguments into a `T`.
+pub fn from_parse<'def, 'argv, T, S>(parse: Parse<'def, 'argv, S>) -> Result<T, Error<'def, 'argv>>
+    where T: DeserializeOwned,
+          S: AsRef<str>
+{
+    let args = ParsedArgs::from_parse(parse)?;
+    from_args(&args).map_err(|err| Error::Message(err.to_string()))
+}
+
+/// Deserializes the collected arguments into a `T`.
+pub fn from_args<'de, T>(args: &'de ParsedArgs) -> Result<T, Error<'static, 'static>>
+    where T: Deserialize<'de>
+{
+    T::deserialize(ArgsDeserializer { args })
+}
+
+/// A deserializer over all the collected arguments, as a map or struct.
+struct ArgsDeserializer<'de> {
+    args: &'de ParsedArgs,
+}
+
+impl<'de> de::Deserializer<'de> for ArgsDeserializer<'de> {
+    type Error = Error<'static, 'static>;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let entries = self.args.iter().map(|(name, value)| (name, Some(value))).collect();
+        visitor.visit_map(ArgsAccess {
+            entries,
+            index: 0,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self,
+                                           _name: &'static str,
+                                           fields: &'static [&'static str],
+                                           visitor: V)
+                                           -> Result<V::Value, Self::Error> {
+        // Missing fields are given as absent values, so that switches become
+        // false and sequences become empty.
+        let entries = fields.iter()
+                            .map(|&field| {
+                                let value = self.args
+                                                .iter()
+                                                .find(|&(name, _)| field_name_matches(name, field))
+                                                .map(|(_, value)| value);
+                                (field, value)
+                            })
+                            .collect();
+        visitor.visit_map(ArgsAccess {
+            entries,
+            index: 0,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map enum
+        identifier ignored_any
+    }
+}
+
+/// Returns whether the argument name corresponds to the field name.
+fn field_name_matches(name: &str, field: &str) -> bool {
+    name.len() == field.len() &&
+    name.chars().zip(field.chars()).all(|(a, b)| a == b || (a == '-' && b == '_'))
+}
+
+struct ArgsAccess<'de> {
+    entries: Vec<(&'de str, Option<&'de Value>)>,
+    index: usize,
+}
+
+impl<'de> MapAccess<'de> for ArgsAccess<'de> {
+    type Error = Error<'static, 'static>;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self,
+                                              seed: K)
+                                              -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.get(self.index) {
+            Some(&(name, _)) => {
+                let key = name.replace('-', "_");
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self,
+                                                seed: V)
+                                                -> Result<V::Value, Self::Error> {
+        let (name, value) = self.entries[self.index];
+        self.index += 1;
+        let input = match value {
+            None => Input::Absent,
+            Some(Value::Switch) => Input::Switch,
+            Some(Value::Off) => Input::Off,
+            Some(&Value::Count(count)) => Input::Count(count),
+            Some(Value::Single(value)) => Input::Text(value),
+            Some(Value::Multiple(values)) => Input::List(values),
+        };
+        seed.deserialize(ValueDeserializer { name, input })
+    }
+}
+
+/// The collected value of a single field.
+#[derive(Clone, Copy)]
+enum Input<'de> {
+    Absent,
+    Switch,
+    Off,
+    Count(usize),
+    Text(&'de str),
+    List(&'de [String]),
+}
+
+/// A deserializer over the value of a single argument.
+struct ValueDeserializer<'de> {
+    name: &'de str,
+    input: Input<'de>,
+}
+
+impl<'de> ValueDeserializer<'de> {
+    fn error(&self, expected: &str) -> Error<'static, 'static> {
+        match self.input {
+            Input::Absent => Error::Message(format!("missing the argument '{}'", self.name)),
+            Input::Text(text) => {
+                Error::Message(format!("invalid value '{}' for '{}': expected {}",
+                                       text,
+                                       self.name,
+                                       expected))
+            }
+            _ => Error::Message(format!("invalid argument '{}': expected {}", self.name, expected)),
+        }
+    }
+
+    fn parse<T: FromStr>(&self, expected: &str) -> Result<T, Error<'static, 'static>> {
+        match self.input {
+            Input::Text(text) => text.parse().map_err(|_| self.error(expected)),
+            Input::Count(count) => count.to_string().parse().map_err(|_| self.error(expected)),
+            _ => Err(self.error(expected)),
+        }
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident, $expected:expr;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error<'static, 'static>;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Absent => visitor.visit_none(),
+            Input::Switch => visitor.visit_bool(true),
+            Input::Off => visitor.visit_bool(false),
+            Input::Count(count) => visitor.visit_u64(count as u64),
+            Input::Text(text) => visitor.visit_borrowed_str(text),
+            Input::List(_) => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Absent => visitor.visit_bool(false),
+            Input::Switch => visitor.visit_bool(true),
+            Input::Off => visitor.visit_bool(false),
+            Input::Count(count) => visitor.visit_bool(count > 0),
+            Input::Text(text) => {
+                let expected = format!("one of {}", typed::BOOL_SPELLINGS);
+                visitor.visit_bool(typed::parse_bool(text).map_err(|_| self.error(&expected))?)
+            }
+            Input::List(_) => Err(self.error("a boolean")),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8, "an integer";
+        deserialize_i16 => visit_i16, "an integer";
+        deserialize_i32 => visit_i32, "an integer";
+        deserialize_i64 => visit_i64, "an integer";
+        deserialize_i128 => visit_i128, "an integer";
+        deserialize_u8 => visit_u8, "a positive integer";
+        deserialize_u16 => visit_u16, "a positive integer";
+        deserialize_u32 => visit_u32, "a positive integer";
+        deserialize_u64 => visit_u64, "a positive integer";
+        deserialize_u128 => visit_u128, "a positive integer";
+        deserialize_f32 => visit_f32, "a number";
+        deserialize_f64 => visit_f64, "a number";
+        deserialize_char => visit_char, "a single character";
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Text(text) => visitor.visit_borrowed_str(text),
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Absent => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self,
+                                                   _name: &'static str,
+                                                   visitor: V)
+                                                   -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let values = match self.input {
+            Input::Absent => &[][..],
+            Input::Text(_) => {
+                return visitor.visit_seq(ValuesAccess {
+                    name: self.name,
+                    values: [self.input].iter().cloned().collect(),
+                })
+            }
+            Input::List(values) => values,
+            Input::Switch | Input::Off | Input::Count(_) => {
+                return Err(self.error("a list of values"))
+            }
+        };
+        visitor.visit_seq(ValuesAccess {
+            name: self.name,
+            values: values.iter().map(|value| Input::Text(value)).collect(),
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self,
+                                         _name: &'static str,
+                                         _variants: &'static [&'static str],
+                                         visitor: V)
+                                         -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Text(text) => visitor.visit_enum(text.into_deserializer()),
+            _ => Err(self.error("one of the choices")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self,
+                                                visitor: V)
+                                                -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bytes byte_buf unit_struct tuple tuple_struct map struct identifier
+    }
+}
+
+struct ValuesAccess<'de> {
+    name: &'de str,
+    values: VecDeque<Input<'de>>,
+}
+
+impl<'de> SeqAccess<'de> for ValuesAccess<'de> {
+    type Error = Error<'static, 'static>;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self,
+                                                  seed: T)
+                                                  -> Result<Option<T::Value>, Self::Error> {
+        match self.values.pop_front() {
+            Some(input) => {
+                seed.deserialize(ValueDeserializer {
+                        name: self.name,
+                        input,
+                    })
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
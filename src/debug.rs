@@ -0,0 +1,87 @@
+//! The log of the decisions of a parse, enabled by the `debug` feature.
+//!
+//! A parse records what it took each argument as when it is made with
+//! `Parse::traced`, or when the `ARGONAUT_DEBUG` environment variable is set
+//! to `1`, in which case each decision is also printed to stderr. The log
+//! answers questions like why an option took a value meant as a positional
+//! argument:
+//!
+//! ```text
+//! let mut parse = parser.parse(&args).traced();
+//! for item in &mut parse { ... }
+//! for decision in parse.decisions() {
+//!     eprintln!("{}", decision);
+//! }
+//! ```
+
+use std::env;
+use std::fmt;
+
+/// The environment variable that makes every parse record its decisions and
+/// print them to stderr.
+pub const DEBUG_VAR: &str = "ARGONAUT_DEBUG";
+
+/// Returns whether the decisions of every parse are printed.
+pub fn debug_from_env() -> bool {
+    env::var_os(DEBUG_VAR).is_some_and(|value| value == "1")
+}
+
+/// What an argument was taken as by the parse, with the name of the
+/// definition it matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role<'a> {
+    /// A positional argument.
+    Positional(&'a str),
+    /// A value of the trail.
+    Trail(&'a str),
+    /// A flag of an optional argument (one of several for a group of short
+    /// flags).
+    Flag(&'a str),
+    /// The negated flag of a switch.
+    Negated(&'a str),
+    /// A parameter of an optional argument.
+    Parameter(&'a str),
+    /// An argument collected by a pass-along.
+    PassedAlong(&'a str),
+    /// An argument after an interrupt.
+    Interrupted(&'a str),
+    /// A flag the parser does not know.
+    Unknown,
+    /// A value after all the positional arguments, without a trail.
+    Unexpected,
+    /// The argument the parse stopped at with `Parser::set_stop_at_unknown`.
+    Stopped,
+    /// The `--` that ends the options.
+    EndOfOptions,
+}
+
+/// A decision of the parse about one argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision<'def, 'argv> {
+    /// The index of the argument.
+    pub index: usize,
+    /// The argument as given.
+    pub arg: &'argv str,
+    /// What the argument was taken as.
+    pub role: Role<'def>,
+}
+
+impl<'def, 'argv> fmt::Display for Decision<'def, 'argv> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Role::*;
+        write!(f, "#{} '{}': ", self.index, self.arg)?;
+        match self.role {
+            Positional(name) => write!(f, "the positional argument '{}'", name),
+            Trail(name) => write!(f, "a value of the trail '{}'", name),
+            Flag(name) => write!(f, "a flag of '{}'", name),
+            Negated(name) => write!(f, "the negated flag of '{}'", name),
+            Parameter(name) => write!(f, "a parameter of '{}'", name),
+            PassedAlong(name) => write!(f, "passed along by '{}'", name),
+            Interrupted(name) => write!(f, "after the interrupt '{}'", name),
+            Unknown => write!(f, "an unknown flag"),
+            Unexpected => write!(f, "an unexpected value"),
+            Stopped => write!(f, "where the parse stopped"),
+            EndOfOptions => write!(f, "the end of the options"),
+        }
+    }
+}
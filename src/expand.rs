@@ -0,0 +1,131 @@
+use std::env;
+
+use parsed::ParsedArgs;
+#[cfg(feature = "glob")]
+use parsed::Value;
+
+/// Returns the home directory of the user, if known.
+fn home_dir() -> Option<String> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var(var).ok().filter(|home| !home.is_empty())
+}
+
+/// Returns whether the character can be part of an environment variable
+/// name.
+fn is_var_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+/// Expands a leading `~` to the home directory and the environment variables
+/// in the value, like a shell does for paths.
+///
+/// Variables are written as `$NAME` or `${NAME}` (and `%NAME%` on Windows).
+/// Variables that are not set, and `~` when the home directory is unknown,
+/// are kept as they are.
+pub fn expand_path(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    if let Some(after) = rest.strip_prefix('~') {
+        let separator = after.starts_with('/') || (cfg!(windows) && after.starts_with('\\'));
+        if after.is_empty() || separator {
+            if let Some(home) = home_dir() {
+                expanded.push_str(&home);
+                rest = after;
+            }
+        }
+    }
+
+    while let Some(start) = rest.find(|ch| ch == '$' || (cfg!(windows) && ch == '%')) {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, end) = if rest[start..].starts_with('%') {
+            match after.find('%') {
+                Some(len) if len > 0 && after[..len].chars().all(is_var_char) => {
+                    (&after[..len], len + 1)
+                }
+                _ => ("", 0),
+            }
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(len) => (&braced[..len], len + 2),
+                None => ("", 0),
+            }
+        } else {
+            let len = after.find(|ch| !is_var_char(ch)).unwrap_or(after.len());
+            (&after[..len], len)
+        };
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => {
+                expanded.push_str(&value);
+                rest = &after[end..];
+            }
+            _ => {
+                // Keep the unknown variable as it is
+                expanded.push_str(&rest[start..start + 1 + end]);
+                rest = &after[end..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Returns the paths matching the glob pattern, or the value itself if it is
+/// not a pattern or matches nothing.
+#[cfg(feature = "glob")]
+fn expand_glob(value: &str) -> Vec<String> {
+    if !value.contains(['*', '?', '[']) {
+        return vec![value.to_owned()];
+    }
+    let paths: Vec<String> = match glob::glob(value) {
+        Ok(paths) => {
+            paths.filter_map(Result::ok)
+                 .map(|path| path.to_string_lossy().into_owned())
+                 .collect()
+        }
+        Err(_) => Vec::new(),
+    };
+    if paths.is_empty() {
+        vec![value.to_owned()]
+    } else {
+        paths
+    }
+}
+
+impl ParsedArgs {
+    /// Expands glob patterns (like `*.txt`) in the values of the named
+    /// arguments into the matching paths, in alphabetical order.
+    ///
+    /// Values that are not patterns, or that match nothing, are kept as they
+    /// are. A single value that matches several paths becomes multiple values.
+    ///
+    /// Shells on Unix already expand patterns before the program sees them,
+    /// so this is usually only wanted on Windows:
+    ///
+    /// ```text
+    /// if cfg!(windows) {
+    ///     args.expand_globs(&["files"]);
+    /// }
+    /// ```
+    #[cfg(feature = "glob")]
+    pub fn expand_globs(&mut self, names: &[&str]) {
+        for &name in names {
+            let expanded = match self.get(name) {
+                Some(Value::Single(value)) => {
+                    let mut paths = expand_glob(value);
+                    if paths.len() == 1 {
+                        Value::Single(paths.remove(0))
+                    } else {
+                        Value::Multiple(paths)
+                    }
+                }
+                Some(Value::Multiple(values)) => {
+                    Value::Multiple(values.iter().flat_map(|value| expand_glob(value)).collect())
+                }
+                _ => continue,
+            };
+            self.insert(name, expanded);
+        }
+    }
+}
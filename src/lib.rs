@@ -1,11 +1,83 @@
 //! Lets the user structure the arguments given to a program through a
 //! command-line.
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "glob")]
+extern crate glob;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "env_logger")]
+extern crate env_logger;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "terminal_size")]
+extern crate terminal_size;
+#[cfg(feature = "dotenv")]
+extern crate dotenvy;
+#[cfg(feature = "derive")]
+extern crate argonaut_derive;
+#[cfg(any(feature = "wasm", feature = "capi"))]
+extern crate serde_json;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+
+#[macro_use]
+mod macros;
 mod common;
 mod arg;
 mod parser;
 mod utils;
+mod strings;
+mod color;
+mod parsed;
+mod spec;
+mod usage;
+mod typed;
+mod expand;
+mod quote;
+mod complete;
+mod config;
+#[cfg(feature = "debug")]
+mod debug;
+#[cfg(feature = "values")]
+pub mod values;
+pub mod compat;
+pub mod testing;
+#[cfg(feature = "log")]
+pub mod verbosity;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "capi")]
+pub mod capi;
 
-pub use arg::{Arg, OptArg};
-pub use parser::{Parser, Parse, StructuredArgument};
-pub use utils::generate_help;
+pub use arg::{Arg, ChoiceList, DerivedDefault, OptArg, OptDigits, OptInterrupt, OptMultiple,
+              OptPassAlong, OptSingle, OptSwitch, PositionalArg, TrailArg, Validator, ValueHint};
+pub use parser::{ErrorFormatter, OwnedArgument, Parser, Parse, ParseError, ParseWarning,
+                 StructuredArgument};
+pub use common::FlagName;
+pub use strings::Strings;
+pub use utils::{HelpLayout, HelpOrder, HelpStyle, format_error, format_gnu_error,
+                generate_arg_help, generate_help, generate_help_filtered, generate_usage,
+                report_error, write_help, write_help_io};
+pub use color::ColorChoice;
+pub use parsed::{ParsedArgs, Source, Value, Iter};
+pub use spec::{SpecDocument, SpecArg, SpecKind};
+pub use typed::{Choices, FileOrStdio, FromArgs, FromValue, Subcommand, parse_bool};
+#[doc(hidden)]
+pub use typed::{internal_build_parser, internal_parse_into, internal_short_flag};
+#[cfg(feature = "derive")]
+pub use argonaut_derive::{Choices, FromArgs, Subcommand};
+pub use expand::expand_path;
+pub use quote::{quote_cmd, quote_sh};
+pub use complete::{COMPLETE_VAR, Shell, complete, complete_from_env, completion_script};
+pub use config::{config_dir, config_file};
+#[cfg(feature = "serde")]
+pub use de::from_parse;
+#[cfg(feature = "debug")]
+pub use debug::{DEBUG_VAR, Decision, Role};
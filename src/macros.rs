@@ -0,0 +1,176 @@
+//! Declarative macros, for programs that do without the `derive` feature.
+
+/// Parses the arguments into a struct, with a definition and a conversion for
+/// each field.
+///
+/// This defines the arguments, parses them and fills the struct, like
+/// `#[derive(FromArgs)]` but without a proc-macro dependency:
+///
+/// ```text
+/// struct Options {
+///     input: PathBuf,
+///     verbose: usize,
+///     level: Option<u8>,
+///     exclude: Vec<String>,
+/// }
+///
+/// let options = parse_into!(&args[1..] => Options {
+///     input = Arg::positional("input") => value,
+///     verbose = Arg::named_and_short("verbose", 'v').count() => count,
+///     level = Arg::named("level").single() => optional,
+///     exclude = Arg::named_and_short("exclude", 'x').one_or_more() => values,
+/// })?;
+/// ```
+///
+/// The arguments are given as a slice of strings, or left out to parse the
+/// ones of the program (without its name). The conversions are:
+///
+/// - `present`: whether the argument was given, as a `bool`.
+/// - `count`: how many times it was given, as any integer type (too many is
+///   an error).
+/// - `value`: its value, converted with `FromValue` (missing is an error).
+/// - `optional`: its value if given, converted with `FromValue`.
+/// - `values`: its values, converted with `FromValue` (empty if not given).
+///
+/// Each field refers to its argument by the name of the definition. The
+/// result is a `Result<_, String>`, failing with the parse error or the first
+/// value that cannot be converted.
+#[macro_export]
+macro_rules! parse_into {
+    (@convert $args:ident, $arg:expr, present) => {
+        $args.is_present($arg)
+    };
+    (@convert $args:ident, $arg:expr, count) => {
+        ::std::convert::TryFrom::try_from($args.count($arg)).map_err(|_| {
+            format!("the argument '{}' was given too many times", $arg)
+        })?
+    };
+    (@convert $args:ident, $arg:expr, value) => {
+        match $args.single_as($arg)? {
+            ::std::option::Option::Some(value) => value,
+            ::std::option::Option::None => {
+                return ::std::result::Result::Err(format!("the argument '{}' is missing", $arg));
+            }
+        }
+    };
+    (@convert $args:ident, $arg:expr, optional) => {
+        $args.single_as($arg)?
+    };
+    (@convert $args:ident, $arg:expr, values) => {
+        $args.multiple_as($arg)?.unwrap_or_default()
+    };
+    ($name:ident { $($rest:tt)* }) => {
+        $crate::parse_into!(&::std::env::args().skip(1).collect::<::std::vec::Vec<_>>() =>
+                            $name { $($rest)* })
+    };
+    ($argv:expr => $name:ident { $($field:ident = $def:expr => $conv:ident),* $(,)* }) => {{
+        $(let $field: $crate::Arg = ::std::convert::Into::into($def);)*
+        $crate::internal_parse_into($argv, &[$($field),*], |args| {
+            ::std::result::Result::Ok($name {
+                $($field: $crate::parse_into!(@convert args, $field.name(), $conv),)*
+            })
+        })
+    }};
+}
+
+/// Returns a parser with the arguments of a table of definitions, one per
+/// line, or why they cannot be defined.
+///
+/// This is a compact form of the builder chains of `Arg`:
+///
+/// ```text
+/// let parser = argonaut! {
+///     positional input: "The file to read.";
+///     trail+ "more-inputs": "The other files to read.";
+///     interrupt -h, --help: "Prints this help message and exits.";
+///     switch -v, --verbose: "Prints more details.";
+///     count -q, --quiet: "Prints less (repeat for even less).";
+///     option -x, --exclude ITEM: "Skips the item.";
+///     option+ -a, --add NUMBER: "Adds the numbers.";
+///     option* --extra: "Zero or more extra values.";
+///     passalong ARGS: "Passes the remaining arguments to the command.";
+/// }?;
+/// ```
+///
+/// The kinds of arguments are `positional`, `trail+` (one or more values),
+/// `trail*` (zero or more), `switch`, `count`, `interrupt`, `option` (one
+/// value), `option+`, `option*` and `passalong`. Optional arguments have a
+/// long flag, which may follow a short one, and the options and pass-alongs
+/// may be followed by the name of their parameter. A `passalong` without
+/// flags collects the arguments after `--`. Names that are not identifiers
+/// are written as strings (like `--"dry-run"`).
+#[macro_export]
+macro_rules! argonaut {
+    (@name $name:ident) => {
+        stringify!($name)
+    };
+    (@name $name:literal) => {
+        $name
+    };
+    (@munch $parser:ident;) => {};
+    (@munch $parser:ident; positional $name:tt : $help:expr; $($rest:tt)*) => {
+        $parser.define_single($crate::Arg::positional($crate::argonaut!(@name $name))
+                                  .set_help($help))?;
+        $crate::argonaut!(@munch $parser; $($rest)*);
+    };
+    (@munch $parser:ident; trail + $name:tt : $help:expr; $($rest:tt)*) => {
+        $parser.define_single($crate::Arg::required_trail($crate::argonaut!(@name $name))
+                                  .set_help($help))?;
+        $crate::argonaut!(@munch $parser; $($rest)*);
+    };
+    (@munch $parser:ident; trail * $name:tt : $help:expr; $($rest:tt)*) => {
+        $parser.define_single($crate::Arg::optional_trail($crate::argonaut!(@name $name))
+                                  .set_help($help))?;
+        $crate::argonaut!(@munch $parser; $($rest)*);
+    };
+    (@munch $parser:ident; passalong $param:ident : $help:expr; $($rest:tt)*) => {
+        $parser.define_single($crate::Arg::named("").passalong()
+                                                    .set_param(stringify!($param))
+                                                    .set_help($help))?;
+        $crate::argonaut!(@munch $parser; $($rest)*);
+    };
+    (@munch $parser:ident; passalong $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; passalong; $($rest)*);
+    };
+    (@munch $parser:ident; switch $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; switch; $($rest)*);
+    };
+    (@munch $parser:ident; count $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; count; $($rest)*);
+    };
+    (@munch $parser:ident; interrupt $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; interrupt; $($rest)*);
+    };
+    (@munch $parser:ident; option + $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; one_or_more; $($rest)*);
+    };
+    (@munch $parser:ident; option * $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; zero_or_more; $($rest)*);
+    };
+    (@munch $parser:ident; option $($rest:tt)*) => {
+        $crate::argonaut!(@flags $parser; single; $($rest)*);
+    };
+    (@flags $parser:ident; $kind:ident;
+     - $short:ident, -- $long:tt $($param:ident)* : $help:expr; $($rest:tt)*) => {
+        let short = $crate::internal_short_flag(stringify!($short))?;
+        $parser.define_single($crate::Arg::named_and_short($crate::argonaut!(@name $long), short)
+                                  .$kind()
+                                  $(.set_param(stringify!($param)))*
+                                  .set_help($help))?;
+        $crate::argonaut!(@munch $parser; $($rest)*);
+    };
+    (@flags $parser:ident; $kind:ident;
+     -- $long:tt $($param:ident)* : $help:expr; $($rest:tt)*) => {
+        $parser.define_single($crate::Arg::named($crate::argonaut!(@name $long))
+                                  .$kind()
+                                  $(.set_param(stringify!($param)))*
+                                  .set_help($help))?;
+        $crate::argonaut!(@munch $parser; $($rest)*);
+    };
+    ($($definitions:tt)*) => {
+        $crate::internal_build_parser(|parser| {
+            $crate::argonaut!(@munch parser; $($definitions)*);
+            ::std::result::Result::Ok(())
+        })
+    };
+}
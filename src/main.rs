@@ -0,0 +1,268 @@
+//! Generates the Rust source for an argument parser from a JSON spec file.
+//!
+//! The spec has the format of `argonaut::SpecDocument`, and the output
+//! contains the argument definitions, a struct for the parsed values and the
+//! match over the structured arguments that fills it.
+
+extern crate argonaut;
+extern crate serde_json;
+
+use argonaut::{Arg, Parser, SpecArg, SpecDocument, SpecKind, generate_help, report_error};
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+
+/// Words that cannot be used as field names in the generated struct.
+const KEYWORDS: &[&str] = &["as", "async", "await", "break", "const", "continue", "crate", "dyn",
+                            "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+                            "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+                            "self", "static", "struct", "super", "trait", "true", "type",
+                            "unsafe", "use", "where", "while"];
+
+/// Returns the name of the struct field for the given argument.
+fn field_name(arg: &SpecArg) -> String {
+    let name = if arg.name.is_empty() {
+        arg.param.as_deref().unwrap_or("passed")
+    } else {
+        &arg.name
+    };
+    let mut field: String = name.chars()
+                                .map(|ch| if ch.is_alphanumeric() { ch.to_ascii_lowercase() } else { '_' })
+                                .collect();
+    if field.starts_with(|ch: char| ch.is_ascii_digit()) || KEYWORDS.contains(&field.as_str()) {
+        field.push('_');
+    }
+    field
+}
+
+/// Returns the type of the struct field for the given argument.
+fn field_type(kind: SpecKind) -> &'static str {
+    match kind {
+        SpecKind::Positional => "String",
+        SpecKind::OptionalTrail | SpecKind::RequiredTrail => "Vec<String>",
+        SpecKind::Single => "Option<String>",
+        SpecKind::ZeroOrMore | SpecKind::OneOrMore | SpecKind::Passalong => {
+            "Option<Vec<String>>"
+        }
+        SpecKind::Switch | SpecKind::Interrupt => "bool",
+        SpecKind::Count => "usize",
+    }
+}
+
+/// Returns the builder expression for the given argument.
+fn definition(arg: &SpecArg) -> String {
+    let mut expr = match arg.kind {
+        SpecKind::Positional => format!("Arg::positional({:?})", arg.name),
+        SpecKind::OptionalTrail => format!("Arg::optional_trail({:?})", arg.name),
+        SpecKind::RequiredTrail => format!("Arg::required_trail({:?})", arg.name),
+        kind => {
+            let opt = match arg.short {
+                Some(short) => format!("Arg::named_and_short({:?}, {:?})", arg.name, short),
+                None => format!("Arg::named({:?})", arg.name),
+            };
+            let method = match kind {
+                SpecKind::Single => "single",
+                SpecKind::ZeroOrMore => "zero_or_more",
+                SpecKind::OneOrMore => "one_or_more",
+                SpecKind::Switch => "switch",
+                SpecKind::Count => "count",
+                SpecKind::Interrupt => "interrupt",
+                _ => "passalong",
+            };
+            format!("{}.{}()", opt, method)
+        }
+    };
+    if let Some(ref param) = arg.param {
+        let _ = write!(expr, "\n            .set_param({:?})", param);
+    }
+    if let Some(ref help) = arg.help {
+        let _ = write!(expr, "\n            .set_help({:?})", help);
+    }
+    if let Some(ref note) = arg.deprecated {
+        let _ = write!(expr, "\n            .set_deprecated({:?})", note);
+    }
+    if !arg.choices.is_empty() {
+        let _ = write!(expr, "\n            .set_choices(&{:?})", arg.choices);
+    }
+    expr
+}
+
+/// Returns the match arm that stores the given argument in the struct.
+fn match_arm(arg: &SpecArg, field: &str) -> String {
+    match arg.kind {
+        SpecKind::Positional => {
+            format!("Positional {{ name: {:?}, value }} => args.{} = value.to_owned(),",
+                    arg.name,
+                    field)
+        }
+        SpecKind::OptionalTrail | SpecKind::RequiredTrail => {
+            format!("Trail {{ values }} => args.{} = to_owned(&values),", field)
+        }
+        SpecKind::Single => {
+            format!("Single {{ name: {:?}, parameter, .. }} => \
+                     args.{} = Some(parameter.to_owned()),",
+                    arg.name,
+                    field)
+        }
+        SpecKind::ZeroOrMore | SpecKind::OneOrMore => {
+            format!("Multiple {{ name: {:?}, parameters, .. }} => \
+                     args.{} = Some(to_owned(&parameters)),",
+                    arg.name,
+                    field)
+        }
+        SpecKind::Passalong => {
+            format!("PassAlong {{ name: {:?}, args: passed }} => \
+                     args.{} = Some(to_owned(&passed)),",
+                    arg.name,
+                    field)
+        }
+        SpecKind::Switch => {
+            format!("Switch {{ name: {:?}, .. }} => args.{} = true,", arg.name, field)
+        }
+        SpecKind::Count => {
+            format!("Switch {{ name: {:?}, .. }} => args.{} += 1,", arg.name, field)
+        }
+        SpecKind::Interrupt => {
+            format!("Interrupt {{ name: {:?}, .. }} => args.{} = true,", arg.name, field)
+        }
+    }
+}
+
+/// Generates the Rust source for the given spec.
+fn generate(spec: &SpecDocument, struct_name: &str) -> String {
+    let fields: Vec<String> = spec.args.iter().map(field_name).collect();
+    let mut out = String::new();
+
+    out.push_str("// Generated by argonaut. Edit the spec file instead of this file.\n\n");
+    out.push_str("use argonaut::{Arg, ParseError, Parser};\n\n");
+
+    let _ = writeln!(out, "/// The arguments given to the program.");
+    let _ = writeln!(out, "#[derive(Debug, Clone, Default)]");
+    let _ = writeln!(out, "pub struct {} {{", struct_name);
+    for (arg, field) in spec.args.iter().zip(&fields) {
+        if let Some(ref help) = arg.help {
+            for line in help.lines() {
+                let _ = writeln!(out, "    /// {}", line);
+            }
+        }
+        let _ = writeln!(out, "    pub {}: {},", field, field_type(arg.kind));
+    }
+    out.push_str("}\n\n");
+
+    out.push_str("/// Defines the arguments on the parser.\n");
+    out.push_str("pub fn define(parser: &mut Parser) {\n");
+    out.push_str("    parser.define(&[\n");
+    for arg in &spec.args {
+        let _ = writeln!(out, "        {}.into(),", definition(arg));
+    }
+    out.push_str("    ]).expect(\"the generated arguments are valid\");\n");
+    if let Some(ref usage) = spec.usage {
+        let _ = writeln!(out, "    parser.set_usage({:?});", usage);
+    }
+    out.push_str("}\n\n");
+
+    out.push_str("fn to_owned(values: &[&str]) -> Vec<String> {\n");
+    out.push_str("    values.iter().map(|value| value.to_string()).collect()\n");
+    out.push_str("}\n\n");
+
+    let _ = writeln!(out, "/// Parses the arguments with a parser made by `define`.");
+    let _ = writeln!(out,
+                     "pub fn parse<'a, S: AsRef<str>>(parser: &'a Parser<'a>, argv: &'a [S]) -> \
+                      Result<{}, ParseError<'a, 'a>> {{",
+                     struct_name);
+    out.push_str("    use argonaut::StructuredArgument::*;\n\n");
+    let _ = writeln!(out, "    let mut args = {}::default();", struct_name);
+    out.push_str("    for item in parser.parse(argv) {\n");
+    out.push_str("        match item? {\n");
+    for (arg, field) in spec.args.iter().zip(&fields) {
+        let _ = writeln!(out, "            {}", match_arm(arg, field));
+    }
+    out.push_str("            _ => unreachable!(),\n");
+    out.push_str("        }\n");
+    out.push_str("    }\n");
+    out.push_str("    Ok(args)\n");
+    out.push_str("}\n");
+    out
+}
+
+fn main() {
+    use argonaut::StructuredArgument::*;
+
+    let mut parser = Parser::new();
+    parser.define(&[Arg::positional("spec")
+                        .set_help("The JSON spec file describing the arguments.")
+                        .into(),
+                    Arg::named_and_short("output", 'o')
+                        .single()
+                        .set_param("FILE")
+                        .set_help("Write the source to this file instead of stdout.")
+                        .into(),
+                    Arg::named("struct-name")
+                        .single()
+                        .set_param("NAME")
+                        .set_help("The name of the generated struct (default: Args).")
+                        .into(),
+                    Arg::named_and_short("help", 'h')
+                        .interrupt()
+                        .set_help("Prints this help message and exits.")
+                        .into(),
+                    Arg::named("version")
+                        .interrupt()
+                        .set_help("Prints the version of this tool and exits.")
+                        .into()])
+          .unwrap();
+    parser.set_usage("Usage: argonaut SPEC [--output FILE] [--struct-name NAME]");
+
+    let mut spec_path = "";
+    let mut output = None;
+    let mut struct_name = "Args";
+    for item in parser.parse_env() {
+        match item {
+            Err(err) => {
+                report_error(&err, &parser);
+                process::exit(2);
+            }
+            Ok(Positional { name: "spec", value }) => spec_path = value,
+            Ok(Single { name: "output", parameter, .. }) => output = Some(parameter),
+            Ok(Single { name: "struct-name", parameter, .. }) => struct_name = parameter,
+            Ok(Interrupt { name: "help", .. }) => {
+                return println!("{}\n\n{}", parser.usage().unwrap_or(""), generate_help(&parser));
+            }
+            Ok(Interrupt { name: "version", .. }) => {
+                return println!("{}", env!("CARGO_PKG_VERSION"));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let text = match fs::read_to_string(spec_path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("error: could not read '{}': {}", spec_path, err);
+            process::exit(1);
+        }
+    };
+    let spec: SpecDocument = match serde_json::from_str(&text) {
+        Ok(spec) => spec,
+        Err(err) => {
+            eprintln!("error: invalid spec '{}': {}", spec_path, err);
+            process::exit(1);
+        }
+    };
+    // Validate the spec the same way the generated code will use it
+    if let Err(err) = Parser::from_spec(&spec) {
+        eprintln!("error: invalid spec '{}': {}", spec_path, err);
+        process::exit(1);
+    }
+
+    let source = generate(&spec, struct_name);
+    let result = match output {
+        Some(path) => fs::write(path, source),
+        None => io::stdout().write_all(source.as_bytes()),
+    };
+    if let Err(err) = result {
+        eprintln!("error: could not write the source: {}", err);
+        process::exit(1);
+    }
+}
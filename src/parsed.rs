@@ -0,0 +1,683 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::slice;
+use std::sync::{Mutex, PoisonError};
+
+use arg::{self, Arg, ArgType};
+use parser::{Parse, ParseError, Parser, internal_get_parser};
+use typed::{FromValue, parse_bool};
+use expand::expand_path;
+
+/// Returns the variables of the `.env` file of the parser, skipping the lines
+/// that cannot be read.
+#[cfg(feature = "dotenv")]
+fn read_dotenv(parser: &Parser) -> HashMap<String, String> {
+    match parser.dotenv().map(::dotenvy::from_path_iter) {
+        Some(Ok(vars)) => vars.filter_map(|var| var.ok()).collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Returns no variables, as `.env` files are read with the `dotenv` feature.
+#[cfg(not(feature = "dotenv"))]
+fn read_dotenv(_parser: &Parser) -> HashMap<String, String> {
+    HashMap::new()
+}
+
+/// Converts a value from the environment, a default or a configuration file
+/// into the kind of the argument, or `None` if it does not give the argument.
+fn fallback_value(arg: Arg, value: String) -> Option<Value> {
+    let value = if arg.expands_path() { expand_path(&value) } else { value };
+    match arg::internal_get_raw(arg) {
+        ArgType::Single(_) | ArgType::OptSingle(_) | ArgType::Digits(_) => {
+            Some(Value::Single(value))
+        }
+        ArgType::ZeroPlus(_) | ArgType::OnePlus(_) | ArgType::OptZeroPlus(_) |
+        ArgType::OptOnePlus(_) => Some(Value::Multiple(vec![value])),
+        ArgType::Switch(_) if parse_bool(&value) == Ok(true) => Some(Value::Switch),
+        ArgType::Switch(_) | ArgType::Interrupt(_) | ArgType::PassAlong(_) => None,
+    }
+}
+
+/// Removes the double quotes around the value, if any.
+fn unquote(value: &str) -> &str {
+    value.strip_prefix('"').and_then(|value| value.strip_suffix('"')).unwrap_or(value)
+}
+
+/// The collected value of an argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A switch or an interrupt that was given.
+    Switch,
+    /// A switch that was turned off with its negated flag (`--no-name`).
+    Off,
+    /// A counted switch, with the number of times it was given.
+    Count(usize),
+    /// A positional argument or an optional argument with a single parameter.
+    Single(String),
+    /// A trail, pass-along or optional argument with multiple parameters.
+    Multiple(Vec<String>),
+}
+
+/// Where a collected value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The arguments given to the parser.
+    Args,
+    /// The named environment variable.
+    Env(String),
+    /// The default of the argument definition.
+    Default,
+    /// A named layer of the configuration (see `ParsedArgs::set_args_source`
+    /// and `ParsedArgs::from_config`).
+    Layer(String),
+}
+
+/// The names looked up in a `ParsedArgs`, if they are recorded. They are
+/// not part of the collected arguments, so they are left out of comparisons.
+#[derive(Debug, Default)]
+struct Queries(Option<Mutex<Vec<String>>>);
+
+impl Queries {
+    fn names(&self) -> Option<Vec<String>> {
+        self.0.as_ref().map(|names| names.lock().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+}
+
+impl Clone for Queries {
+    fn clone(&self) -> Queries {
+        Queries(self.names().map(Mutex::new))
+    }
+}
+
+impl PartialEq for Queries {
+    fn eq(&self, _other: &Queries) -> bool {
+        true
+    }
+}
+
+impl Eq for Queries {}
+
+/// The structured arguments of a parse, collected by argument name.
+///
+/// Unlike the structured arguments, these own their values, and the values
+/// are kept in the order they were given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedArgs {
+    values: Vec<(String, Value, Source)>,
+    unknown: Vec<String>,
+    queried: Queries,
+}
+
+impl ParsedArgs {
+    /// Creates an empty collection.
+    pub fn new() -> ParsedArgs {
+        ParsedArgs {
+            values: Vec::new(),
+            unknown: Vec::new(),
+            queried: Queries::default(),
+        }
+    }
+
+    /// Runs the parse to its end and collects the structured arguments.
+    ///
+    /// The trail is collected under the name of its definition, and an
+    /// interrupt is collected as a switch (the parse ends with it). Arguments
+    /// that were not given are then read from their environment variable or
+    /// default, if set.
+    pub fn from_parse<'def, 'argv, S>(parse: Parse<'def, 'argv, S>)
+                                      -> Result<ParsedArgs, ParseError<'def, 'argv>>
+        where S: AsRef<str>
+    {
+        use parser::StructuredArgument::*;
+
+        let parser = internal_get_parser(&parse);
+        let definitions = parser.args().as_slice();
+        let trail_name = definitions.iter()
+                                    .filter_map(|&arg| match arg::internal_get_raw(arg) {
+                                        ArgType::ZeroPlus(name) | ArgType::OnePlus(name) => Some(name),
+                                        _ => None,
+                                    })
+                                    .next()
+                                    .unwrap_or("");
+        let counted: Vec<&str> = definitions.iter()
+                                            .filter(|arg| arg.is_counted())
+                                            .map(|arg| arg.name())
+                                            .collect();
+        let overrides: Vec<(&str, &str)> =
+            definitions.iter()
+                       .filter_map(|arg| arg.overridden().map(|other| (arg.name(), other)))
+                       .collect();
+        let expanded: Vec<&str> = definitions.iter()
+                                             .filter(|arg| arg.expands_path())
+                                             .map(|arg| arg.name())
+                                             .collect();
+
+        let mut parsed = ParsedArgs::new();
+        for item in parse {
+            let (name, value) = match item? {
+                Positional { name, value } => (name, Value::Single(value.to_owned())),
+                Trail { values } => (trail_name, Value::Multiple(to_owned(&values))),
+                Single { name, parameter, .. } => (name, Value::Single(parameter.to_owned())),
+                DigitFlag { name, digit } => (name, Value::Single(digit.to_string())),
+                Multiple { name, parameters, .. } => (name, Value::Multiple(to_owned(&parameters))),
+                Switch { name, .. } if counted.contains(&name) => {
+                    let count = parsed.count(name);
+                    (name, Value::Count(count + 1))
+                }
+                Switch { name, .. } | Interrupt { name, .. } => (name, Value::Switch),
+                NegatedSwitch { name } => (name, Value::Off),
+                PassAlong { name, args } => (name, Value::Multiple(to_owned(&args))),
+                Unknown { arg } => {
+                    parsed.unknown.push(arg.to_owned());
+                    continue;
+                }
+            };
+            let value = match value {
+                Value::Single(value) if expanded.contains(&name) => {
+                    Value::Single(expand_path(&value))
+                }
+                Value::Multiple(values) if expanded.contains(&name) => {
+                    Value::Multiple(values.iter().map(|value| expand_path(value)).collect())
+                }
+                value => value,
+            };
+            for &(first, second) in &overrides {
+                if name == first {
+                    parsed.remove(second);
+                } else if name == second {
+                    parsed.remove(first);
+                }
+            }
+            parsed.insert(name, value);
+        }
+
+        // Fill in the arguments that were not given from the environment and
+        // their defaults
+        let dotenv = read_dotenv(parser);
+        for &arg in definitions {
+            if parsed.get(arg.name()).is_some() {
+                continue;
+            }
+            let env_value = parser.env_var(arg).and_then(|var| {
+                env::var(&var).ok()
+                              .or_else(|| dotenv.get(&var).cloned())
+                              .filter(|value| !value.is_empty())
+                              .map(|value| (var, value))
+            });
+            let (value, source) = match (env_value, arg.default()) {
+                (Some((var, value)), _) => (value, Source::Env(var)),
+                (None, Some(default)) => (default.to_owned(), Source::Default),
+                (None, None) => continue,
+            };
+            if let Some(value) = fallback_value(arg, value) {
+                parsed.insert_from(arg.name(), value, source);
+            }
+        }
+
+        // The derived defaults see everything collected before them
+        for &arg in definitions {
+            let derive = match arg.derived_default() {
+                Some(derive) if parsed.get(arg.name()).is_none() => derive,
+                _ => continue,
+            };
+            if let Some(value) = derive(&parsed).and_then(|value| fallback_value(arg, value)) {
+                parsed.insert_from(arg.name(), value, Source::Default);
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Reads the values of a configuration file, to be merged under the ones
+    /// of the command line (see `merge` and `config_file`):
+    ///
+    /// ```text
+    /// let mut args = ParsedArgs::from_config(&parser, &path)?;
+    /// args.merge(ParsedArgs::from_parse(parser.parse_env())?);
+    /// ```
+    ///
+    /// The file has a line for each argument, with its name and value
+    /// (`out-dir = /tmp`), or only its name for a switch that is given.
+    /// Values are read like the ones of environment variables, and the lines
+    /// of an argument with several values add to them. Empty lines and lines
+    /// starting with `#` are skipped, and double quotes around a value are
+    /// removed.
+    ///
+    /// The lines after a `[name]` header belong to a profile, and are only
+    /// read for that profile (see `from_config_profile`).
+    ///
+    /// The values come from a `Source::Layer` named after the path. A missing
+    /// file gives no values, and an unreadable file or an unknown argument
+    /// gives an error.
+    pub fn from_config(parser: &Parser, path: &Path) -> Result<ParsedArgs, String> {
+        ParsedArgs::from_config_profile(parser, path, None)
+    }
+
+    /// Reads the values of a configuration file like `from_config`, with the
+    /// ones of a profile over them, e.g. for `prod` in:
+    ///
+    /// ```text
+    /// log-level = debug
+    ///
+    /// [prod]
+    /// log-level = warn
+    /// url = "https://example.com"
+    /// ```
+    ///
+    /// Without a profile, the one of the `profile` line of the file is read,
+    /// if any (see `Parser::define_profile`). The values of the profile come
+    /// from a `Source::Layer` named after the path and the profile, and a
+    /// profile without a section in the file gives an error.
+    pub fn from_config_profile(parser: &Parser,
+                               path: &Path,
+                               profile: Option<&str>)
+                               -> Result<ParsedArgs, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(ParsedArgs::new()),
+            Err(err) => return Err(format!("cannot read '{}': {}", path.display(), err)),
+        };
+
+        // The lines of each section, the first one being the top of the file
+        let mut sections = vec![(None, Vec::new())];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                sections.push((Some(name.trim()), Vec::new()));
+                continue;
+            }
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name.trim(), unquote(value.trim())),
+                None => (line, "true"),
+            };
+            let arg = parser.args().find(|arg| arg.name() == name).ok_or_else(|| {
+                format!("{}:{}: unknown argument '{}'", path.display(), number + 1, name)
+            })?;
+            if let Some(section) = sections.last_mut() {
+                section.1.push((*arg, value));
+            }
+        }
+
+        let mut parsed = ParsedArgs::new();
+        parsed.read_config_lines(&sections[0].1, Source::Layer(path.display().to_string()));
+        let profile = match profile {
+            Some(profile) => profile.to_owned(),
+            None => match parsed.single("profile") {
+                Some(profile) => profile.to_owned(),
+                None => return Ok(parsed),
+            },
+        };
+        let lines = match sections.iter().find(|section| section.0 == Some(&profile)) {
+            Some(section) => &section.1,
+            None => return Err(format!("no profile '{}' in '{}'", profile, path.display())),
+        };
+        parsed.read_config_lines(lines, Source::Layer(format!("{} [{}]", path.display(), profile)));
+        Ok(parsed)
+    }
+
+    /// Sets the values of the lines of a configuration file, which replace
+    /// the ones already set or add to them.
+    fn read_config_lines(&mut self, lines: &[(Arg, &str)], source: Source) {
+        for &(arg, value) in lines {
+            let value = match (self.remove(arg.name()), fallback_value(arg, value.to_owned())) {
+                (Some(Value::Multiple(mut values)), Some(Value::Multiple(more))) => {
+                    values.extend(more);
+                    Value::Multiple(values)
+                }
+                (_, Some(value)) => value,
+                // A later line that turns a switch off removes it
+                (_, None) => continue,
+            };
+            self.insert_from(arg.name(), value, source.clone());
+        }
+    }
+
+    /// Sets the value of the named argument, replacing any previous value.
+    ///
+    /// A replaced value keeps its source, and a new one comes from
+    /// `Source::Args`.
+    pub fn insert(&mut self, name: &str, value: Value) {
+        match self.values.iter_mut().find(|entry| entry.0 == name) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((name.to_owned(), value, Source::Args)),
+        }
+    }
+
+    /// Sets the value of the named argument and where it comes from,
+    /// replacing any previous value.
+    pub fn insert_from(&mut self, name: &str, value: Value, source: Source) {
+        match self.values.iter_mut().find(|entry| entry.0 == name) {
+            Some(entry) => {
+                entry.1 = value;
+                entry.2 = source;
+            }
+            None => self.values.push((name.to_owned(), value, source)),
+        }
+    }
+
+    /// Removes the value of the named argument, and returns it.
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        let index = self.values.iter().position(|entry| entry.0 == name)?;
+        Some(self.values.remove(index).1)
+    }
+
+    /// Returns where the value of the named argument comes from, if it was
+    /// given.
+    pub fn source(&self, name: &str) -> Option<&Source> {
+        self.values.iter().find(|entry| entry.0 == name).map(|entry| &entry.2)
+    }
+
+    /// Replaces the source of the values that come from `Source::Args`, e.g.
+    /// with `Source::Layer("config".into())` for a parse of the arguments in
+    /// a configuration file.
+    pub fn set_args_source(&mut self, source: Source) {
+        for entry in &mut self.values {
+            if entry.2 == Source::Args {
+                entry.2 = source.clone();
+            }
+        }
+    }
+
+    /// Merges the values of another collection over these, like the values
+    /// of the command line over those of a configuration file.
+    ///
+    /// The values of `over` replace these, except its defaults, which only
+    /// fill in the missing values. The values keep their source.
+    pub fn merge(&mut self, over: ParsedArgs) {
+        self.merge_with(over, |_, _, value| value);
+    }
+
+    /// Merges the values of another collection over these like `merge`, but
+    /// resolves the values given in both with the function, which gets the
+    /// name, the value of `self` and the value of `over` (e.g. to join lists).
+    ///
+    /// A resolved value comes from the source of `over`.
+    pub fn merge_with<F>(&mut self, over: ParsedArgs, mut resolve: F)
+        where F: FnMut(&str, Value, Value) -> Value
+    {
+        for (name, value, source) in over.values {
+            let entry = match self.values.iter_mut().find(|entry| entry.0 == name) {
+                Some(entry) => entry,
+                None => {
+                    self.values.push((name, value, source));
+                    continue;
+                }
+            };
+            if source == Source::Default {
+                continue;
+            }
+            let old = mem::replace(&mut entry.1, Value::Switch);
+            entry.1 = resolve(&name, old, value);
+            entry.2 = source;
+        }
+        self.unknown.extend(over.unknown);
+    }
+
+    /// Returns the value of the named argument, if it was given.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        if let Some(ref queried) = self.queried.0 {
+            let mut queried = queried.lock().unwrap_or_else(PoisonError::into_inner);
+            if !queried.iter().any(|queried| queried == name) {
+                queried.push(name.to_owned());
+            }
+        }
+        self.value(name)
+    }
+
+    /// Returns the value of the named argument without recording the query.
+    fn value(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|entry| entry.0 == name).map(|entry| &entry.1)
+    }
+
+    /// Sets whether the names looked up from now on are recorded, to find
+    /// the arguments that the program never reads (with `unread`) and the
+    /// names that it looks up by mistake (with `undefined_queries`).
+    ///
+    /// All the getters (`get`, `is_present`, `single` and so on) record the
+    /// name. Turning the recording off forgets the recorded names.
+    pub fn set_track_queries(&mut self, track: bool) {
+        if track != self.queried.0.is_some() {
+            self.queried = Queries(if track { Some(Mutex::new(Vec::new())) } else { None });
+        }
+    }
+
+    /// Returns the names of the arguments of the parser that were never
+    /// looked up, in the order of their definitions. Empty unless the
+    /// queries are tracked (see `set_track_queries`).
+    pub fn unread<'p>(&self, parser: &Parser<'p>) -> Vec<&'p str> {
+        let queried = match self.queried.names() {
+            Some(queried) => queried,
+            None => return Vec::new(),
+        };
+        parser.args()
+              .map(|arg| arg.name())
+              .filter(|&name| !queried.iter().any(|queried| queried == name))
+              .collect()
+    }
+
+    /// Returns the names that were looked up but are not defined by the
+    /// parser (like a misspelled name), in the order they were first looked
+    /// up. Empty unless the queries are tracked (see `set_track_queries`).
+    pub fn undefined_queries(&self, parser: &Parser) -> Vec<String> {
+        let mut queried = self.queried.names().unwrap_or_default();
+        queried.retain(|name| !parser.args().any(|arg| arg.name() == name));
+        queried
+    }
+
+    /// Panics if an argument of the parser was never read, or if a name that
+    /// it does not define was looked up, when the queries are tracked. Like
+    /// `debug_assert!`, this only checks in debug builds:
+    ///
+    /// ```text
+    /// let mut args = ParsedArgs::from_parse(parser.parse(&argv))?;
+    /// args.set_track_queries(cfg!(debug_assertions));
+    /// run(&args);
+    /// args.assert_queries(&parser);
+    /// ```
+    #[track_caller]
+    pub fn assert_queries(&self, parser: &Parser) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let unread = self.unread(parser);
+        let undefined = self.undefined_queries(parser);
+        let mut problems = Vec::new();
+        if !unread.is_empty() {
+            problems.push(format!("never read: {}", unread.join(", ")));
+        }
+        if !undefined.is_empty() {
+            problems.push(format!("not defined: {}", undefined.join(", ")));
+        }
+        if !problems.is_empty() {
+            panic!("unused or undefined arguments ({})", problems.join("; "));
+        }
+    }
+
+    /// Returns whether the named argument was given (and is not a switch that
+    /// was turned off).
+    pub fn is_present(&self, name: &str) -> bool {
+        !matches!(self.get(name), None | Some(Value::Off))
+    }
+
+    /// Returns how many times the named switch was given (at most one for
+    /// switches that are not counted).
+    pub fn count(&self, name: &str) -> usize {
+        match self.get(name) {
+            Some(&Value::Count(count)) => count,
+            Some(Value::Off) | None => 0,
+            Some(_) => 1,
+        }
+    }
+
+    /// Returns the value of a named argument with a single value.
+    pub fn single(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(Value::Single(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of a named argument with multiple values.
+    pub fn multiple(&self, name: &str) -> Option<&[String]> {
+        match self.get(name) {
+            Some(Value::Multiple(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Converts the value of a named argument with a single value.
+    ///
+    /// Errors with a message for the user if the value cannot be converted.
+    pub fn single_as<T: FromValue>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.single(name) {
+            Some(value) => convert(name, value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts the values of a named argument with multiple values.
+    ///
+    /// Errors with a message for the user if a value cannot be converted.
+    pub fn multiple_as<T: FromValue>(&self, name: &str) -> Result<Option<Vec<T>>, String> {
+        match self.multiple(name) {
+            Some(values) => {
+                values.iter()
+                      .map(|value| convert(name, value))
+                      .collect::<Result<_, _>>()
+                      .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the unknown flags, with `Parser::set_collect_unknown`.
+    pub fn unknown(&self) -> &[String] {
+        &self.unknown
+    }
+
+    /// Returns the arguments that the parser structures into these values
+    /// again (e.g. to re-run the program with modified arguments).
+    ///
+    /// The arguments are in a canonical order: the positional arguments and
+    /// then the trail, the optional arguments in the order of their
+    /// definitions with their long flags, and the pass-alongs last. Unknown
+    /// flags are left out.
+    ///
+    /// A value is attached to its flag (`--offset=-5`), and so are several
+    /// values that can be joined with the delimiter of the option. The
+    /// positional values from the first one that looks like a flag on come
+    /// after a `--`, following the optional arguments. Two kinds of values
+    /// can still not be given back: the values of an option with several
+    /// parameters that look like flags, and values after a `--` that a
+    /// pass-along has to follow.
+    pub fn to_args(&self, parser: &Parser) -> Vec<String> {
+        let mut positional = Vec::new();
+        let mut trail = Vec::new();
+        let mut optional = Vec::new();
+        let mut passing = Vec::new();
+        for &arg in parser.args() {
+            let value = match self.value(arg.name()) {
+                Some(value) => value,
+                None => continue,
+            };
+            let values = match *value {
+                Value::Single(ref value) => slice::from_ref(value),
+                Value::Multiple(ref values) => values.as_slice(),
+                Value::Switch | Value::Off | Value::Count(_) => &[],
+            };
+            let flag = match *value {
+                Value::Off => format!("--no-{}", arg.name()),
+                _ => format!("--{}", arg.name()),
+            };
+            match arg::internal_get_raw(arg) {
+                ArgType::Single(_) => positional.extend_from_slice(values),
+                ArgType::ZeroPlus(_) | ArgType::OnePlus(_) => trail.extend_from_slice(values),
+                ArgType::Digits(_) => {
+                    optional.extend(values.iter().map(|value| format!("-{}", value)))
+                }
+                ArgType::PassAlong(_) => {
+                    passing.push(flag);
+                    passing.extend_from_slice(values);
+                }
+                _ => optional.extend(flag_args(arg, flag, value)),
+            }
+        }
+
+        // The values from the first one that looks like a flag go after a '--'
+        positional.extend(trail);
+        let escaped = positional.iter()
+                                .position(|value| value.starts_with('-') && value != "-")
+                                .unwrap_or(positional.len());
+        let rest = positional.split_off(escaped);
+        positional.extend(optional);
+        if !rest.is_empty() {
+            positional.push("--".to_owned());
+            positional.extend(rest);
+        }
+        positional.extend(passing);
+        positional
+    }
+
+    /// Returns an iterator over the collected arguments in the order they
+    /// were given.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { inner: self.values.iter() }
+    }
+}
+
+impl<'a> IntoIterator for &'a ParsedArgs {
+    type Item = (&'a str, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the collected arguments of a `ParsedArgs`.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    inner: slice::Iter<'a, (String, Value, Source)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (entry.0.as_str(), &entry.1))
+    }
+}
+
+/// Returns the arguments that give the value to the optional argument with
+/// the flag.
+fn flag_args(arg: Arg, flag: String, value: &Value) -> Vec<String> {
+    match *value {
+        Value::Switch | Value::Off => vec![flag],
+        Value::Count(count) => vec![flag; count],
+        Value::Single(ref value) => vec![format!("{}={}", flag, value)],
+        Value::Multiple(ref values) => match (values.as_slice(), arg.delimiter()) {
+            ([], _) => vec![flag],
+            ([value], _) => vec![format!("{}={}", flag, value)],
+            (values, Some(delimiter)) if !values.iter().any(|value| value.contains(delimiter)) => {
+                vec![format!("{}={}", flag, values.join(&delimiter.to_string()))]
+            }
+            (values, _) => Some(flag).into_iter().chain(values.iter().cloned()).collect(),
+        },
+    }
+}
+
+fn convert<T: FromValue>(name: &str, value: &str) -> Result<T, String> {
+    T::from_value(value)
+        .map_err(|reason| format!("invalid value '{}' for '{}': {}", value, name, reason))
+}
+
+fn to_owned(values: &[&str]) -> Vec<String> {
+    values.iter().map(|&value| value.to_owned()).collect()
+}
@@ -1,6 +1,51 @@
 use std::collections::{HashMap, HashSet};
+use std::env;
+use std::error;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::{Range, RangeInclusive};
+use std::slice;
+use std::sync::OnceLock;
 use common::{FlagName, OptName};
-use arg::{self, Arg};
+use arg::{self, Arg, ArgType, ChoiceList, Validator};
+use strings::{self, Strings};
+use color::ColorChoice;
+#[cfg(feature = "debug")]
+use debug::{self, Decision, Role};
+use utils::{HelpOrder, HelpStyle};
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+/// Emits a `tracing` event at the given level with the `tracing` feature, and
+/// nothing otherwise.
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ($level:ident, $($fields:tt)*) => {
+        ::tracing::$level!($($fields)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_event {
+    ($($tokens:tt)*) => {};
+}
+
+/// Records what the arguments at the given index or range were taken as with
+/// the `debug` feature (see `Parse::traced`), and does nothing otherwise.
+#[cfg(feature = "debug")]
+macro_rules! record {
+    ($parse:expr, range $range:expr, $role:expr) => {
+        $parse.record($range, $role)
+    };
+    ($parse:expr, $index:expr, $role:expr) => {
+        $parse.record($index..$index + 1, $role)
+    };
+}
+
+#[cfg(not(feature = "debug"))]
+macro_rules! record {
+    ($($tokens:tt)*) => {};
+}
 
 /// The possible types of an optional argument.
 #[derive(Debug, Clone)]
@@ -28,16 +73,25 @@ enum ReqType {
 
 /// Creates an argument name (fat pointer) to the given argument if it is
 /// valid as such.
-fn argument_type(arg: &str) -> GivenArgument {
+fn argument_type(arg: &str) -> GivenArgument<'_> {
     use self::GivenArgument::*;
     use common::FlagName::*;
-    if arg.starts_with("--") {
-        Flag(Long(&arg[2..]))
-    } else if arg.starts_with('-') {
-        if arg.len() == 2 {
-            Flag(Short(arg.chars().nth(1).unwrap()))
-        } else {
-            ShortFlags(arg.chars().skip(1).map(Short).collect())
+    if let Some(long) = arg.strip_prefix("--") {
+        // A long flag with an attached value (--out=file)
+        match long.split_once('=') {
+            Some((long, value)) => Attached(Long(long), value),
+            None => Flag(Long(long)),
+        }
+    } else if arg.starts_with('-') && arg != "-" {
+        // Short flags are counted in chars, as they need not be ASCII (-é)
+        let mut chars = arg.chars().skip(1);
+        match (chars.next(), chars.next()) {
+            // A short flag with an attached value (-o=file)
+            (Some(short), Some('=')) => {
+                Attached(Short(short), arg.get(short.len_utf8() + 2..).unwrap_or(""))
+            }
+            (Some(short), None) => Flag(Short(short)),
+            _ => ShortFlags(arg.chars().skip(1).map(Short).collect()),
         }
 
     } else {
@@ -45,39 +99,245 @@ fn argument_type(arg: &str) -> GivenArgument {
     }
 }
 
+/// Returns the arguments given to this program, without the program path.
+///
+/// They are read once, and then kept for the rest of the program.
+fn env_args() -> &'static [String] {
+    static ARGS: OnceLock<Vec<String>> = OnceLock::new();
+    ARGS.get_or_init(|| env::args().skip(1).collect())
+}
+
 /// An error found when attempting to parse a set of arguments.
 #[derive(Debug)]
-pub enum ParseError<'a> {
+pub enum ParseError<'def, 'argv> {
     /// This optional argument is not recognized by the parser.
+    ///
+    /// The suggestion is a defined long flag (without dashes) that the
+    /// argument closely resembles, if any.
     UnknownOptionalArgument {
-        arg: &'a str,
+        arg: &'argv str,
+        suggestion: Option<&'def str>,
     },
-    /// The given short flag takes input and therefore cannot be grouped when
-    /// used (if '-x' takes the argument 'FOO', you cannot call '-vasx').
+    /// The given short flag is an interrupt or a pass-along and therefore
+    /// cannot be grouped when used (if '-h' is an interrupt, you cannot call
+    /// '-vh').
     GroupedNonSwitch {
-        arg: &'a str,
+        arg: &'argv str,
         invalid: String,
     },
     /// This argument is missing a parameter.
     MissingParameter {
-        arg: &'a str,
+        arg: &'argv str,
     },
     /// This positional argument wasn't given.
     MissingPositionalArgument {
-        arg: &'a str,
+        arg: &'def str,
+    },
+    /// This required optional argument wasn't given.
+    MissingOption {
+        arg: &'def str,
+    },
+    /// This optional argument is required because the other one was given.
+    MissingOptionIf {
+        arg: &'def str,
+        other: &'def str,
+    },
+    /// This optional argument is required because the other one was given
+    /// this value.
+    MissingOptionIfValue {
+        arg: &'def str,
+        other: &'def str,
+        value: &'def str,
+    },
+    /// This optional argument is required because the other one wasn't given.
+    MissingOptionUnless {
+        arg: &'def str,
+        other: &'def str,
+    },
+    /// None of the optional arguments in this group was given.
+    MissingGroup {
+        group: &'def str,
+        members: &'def [&'def str],
+    },
+    /// Two optional arguments of an exclusive group were given.
+    ConflictingGroup {
+        group: &'def str,
+        arg: &'def str,
+        other: &'def str,
     },
     /// This optional argument was given twice.
     DuplicatePositionalArgument {
-        arg: &'a str,
+        arg: &'argv str,
     },
     /// The required trail argument is missing.
     MissingTrail {
-        arg: &'a str,
+        arg: &'def str,
     },
     /// The given positional argument was not expected by the parser.
+    ///
+    /// The suggestion is a defined long flag (without dashes) that the
+    /// argument closely resembles (e.g. `verbose` for `--verbose`), if any.
     UnexpectedArgument {
-        arg: &'a str,
+        arg: &'argv str,
+        suggestion: Option<&'def str>,
+    },
+    /// The value given to this argument is not one of its choices.
+    InvalidChoice {
+        arg: &'argv str,
+        value: &'argv str,
+        choices: ChoiceList<'def>,
+    },
+    /// The value given to this argument was rejected by its validator.
+    InvalidValue {
+        arg: &'argv str,
+        value: &'argv str,
+        reason: String,
+    },
+}
+
+impl<'def, 'argv> ParseError<'def, 'argv> {
+    /// Returns the message for this error using the given texts.
+    pub fn message(&self, strings: &Strings) -> String {
+        use self::ParseError::*;
+        match *self {
+            UnknownOptionalArgument { arg, suggestion } => {
+                let message = strings::fill(strings.unknown_optional_argument, &[("arg", arg)]);
+                with_suggestion(message, suggestion, strings)
+            }
+            GroupedNonSwitch { arg, ref invalid } => {
+                strings::fill(strings.grouped_non_switch,
+                              &[("arg", arg), ("invalid", invalid)])
+            }
+            MissingParameter { arg } => strings::fill(strings.missing_parameter, &[("arg", arg)]),
+            MissingPositionalArgument { arg } => {
+                strings::fill(strings.missing_positional_argument, &[("arg", arg)])
+            }
+            MissingOption { arg } => strings::fill(strings.missing_option, &[("arg", arg)]),
+            MissingOptionIf { arg, other } => {
+                strings::fill(strings.missing_option_if, &[("arg", arg), ("other", other)])
+            }
+            MissingOptionIfValue { arg, other, value } => {
+                strings::fill(strings.missing_option_if_value,
+                              &[("arg", arg), ("other", other), ("value", value)])
+            }
+            MissingOptionUnless { arg, other } => {
+                strings::fill(strings.missing_option_unless, &[("arg", arg), ("other", other)])
+            }
+            MissingGroup { group, members } => {
+                let members = members.iter()
+                                     .map(|member| format!("--{}", member))
+                                     .collect::<Vec<_>>()
+                                     .join(", ");
+                strings::fill(strings.missing_group, &[("group", group), ("members", &members)])
+            }
+            ConflictingGroup { group, arg, other } => {
+                strings::fill(strings.conflicting_group,
+                              &[("group", group), ("arg", arg), ("other", other)])
+            }
+            DuplicatePositionalArgument { arg } => {
+                strings::fill(strings.duplicate_argument, &[("arg", arg)])
+            }
+            MissingTrail { arg } => strings::fill(strings.missing_trail, &[("arg", arg)]),
+            UnexpectedArgument { arg, suggestion } => {
+                let message = strings::fill(strings.unexpected_argument, &[("arg", arg)]);
+                with_suggestion(message, suggestion, strings)
+            }
+            InvalidChoice { arg, value, choices } => {
+                let choices = choices.iter().collect::<Vec<_>>().join(", ");
+                strings::fill(strings.invalid_choice,
+                              &[("arg", arg), ("value", value), ("choices", &choices)])
+            }
+            InvalidValue { arg, value, ref reason } => {
+                strings::fill(strings.invalid_value,
+                              &[("arg", arg), ("value", value), ("reason", reason)])
+            }
+        }
+    }
+}
+
+/// Adds the suggested flag to an error message.
+fn with_suggestion(mut message: String, suggestion: Option<&str>, strings: &Strings) -> String {
+    if let Some(flag) = suggestion {
+        message.push(' ');
+        message.push_str(&strings::fill(strings.did_you_mean, &[("flag", flag)]));
+    }
+    message
+}
+
+/// Returns the number of single-character edits between the two texts.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+impl<'def, 'argv> fmt::Display for ParseError<'def, 'argv> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message(&Strings::english()))
+    }
+}
+
+impl<'def, 'argv> error::Error for ParseError<'def, 'argv> {}
+
+/// A non-fatal remark about the arguments, found during a parse.
+#[derive(Debug, Clone, Copy)]
+pub enum ParseWarning<'def, 'argv> {
+    /// A deprecated optional argument was used.
+    DeprecatedFlag {
+        arg: &'argv str,
+        note: &'def str,
+    },
+    /// This value has the name of a defined flag, so the dashes were probably
+    /// forgotten (eg. 'verbose' instead of '--verbose').
+    SuspiciousValue {
+        value: &'argv str,
+        flag: &'def str,
     },
+    /// An earlier occurrence of this optional argument was dropped, as it was
+    /// given again (see `allow_duplicates`) or an argument that overrides it
+    /// was given (see `overrides`). The previous value is the one dropped, or
+    /// the flag if it takes no parameter.
+    OverriddenValue {
+        arg: &'def str,
+        previous: &'argv str,
+    },
+}
+
+impl<'def, 'argv> ParseWarning<'def, 'argv> {
+    /// Returns the message for this warning using the given texts.
+    pub fn message(&self, strings: &Strings) -> String {
+        use self::ParseWarning::*;
+        match *self {
+            DeprecatedFlag { arg, note } => {
+                strings::fill(strings.deprecated_flag, &[("arg", arg), ("note", note)])
+            }
+            SuspiciousValue { value, flag } => {
+                strings::fill(strings.suspicious_value, &[("value", value), ("flag", flag)])
+            }
+            OverriddenValue { arg, previous } => {
+                strings::fill(strings.overridden_value, &[("arg", arg), ("previous", previous)])
+            }
+        }
+    }
+}
+
+impl<'def, 'argv> fmt::Display for ParseWarning<'def, 'argv> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message(&Strings::english()))
+    }
 }
 
 /// An argument given by the user.
@@ -86,164 +346,1096 @@ enum GivenArgument<'a> {
     Value(&'a str),
     Flag(FlagName<'a>),
     ShortFlags(Vec<FlagName<'a>>),
+    Attached(FlagName<'a>, &'a str),
 }
 
+/// A named set of optional arguments of which at least one must be given.
+#[derive(Debug, Clone, Copy)]
+struct Group<'a> {
+    name: &'a str,
+    members: &'a [&'a str],
+    /// Whether only one of the members may be given.
+    exclusive: bool,
+}
+
+/// The checks on the values of an argument.
+#[derive(Debug, Clone)]
+struct Constraints<'a> {
+    choices: Option<ChoiceList<'a>>,
+    validator: Option<Validator>,
+    range: Option<RangeInclusive<i64>>,
+    #[cfg(feature = "regex")]
+    regex: Option<Regex>,
+}
+
+/// Formats the report of a parse error for the user, with the parser for
+/// context (see `Parser::set_error_formatter`).
+pub type ErrorFormatter = fn(&ParseError, &Parser) -> String;
+
 /// An argument parser.
+///
+/// Everything the parser lists or walks (the help message, the usage line,
+/// completions, `ParsedArgs` and the flag suggestions) follows the order the
+/// arguments were defined in, or the order set with `set_help_order`, so the
+/// output is the same on every run and can be compared with snapshots.
 #[derive(Debug)]
 pub struct Parser<'a> {
     positional: Vec<&'a str>,
     trail: Option<(&'a str, ReqType)>,
-    options: HashMap<OptName<'a>, OptType>,
-    switches: HashSet<OptName<'a>>,
-    interrupts: HashSet<OptName<'a>>,
-    used_flags: HashSet<FlagName<'a>>,
-    aliases: HashMap<FlagName<'a>, OptName<'a>>,
-    passalongs: HashSet<OptName<'a>>,
+    digits: Option<OptName<'a>>,
+    number_option: Option<OptName<'a>>,
+    required: Vec<OptName<'a>>,
+    required_if: Vec<(OptName<'a>, &'a str)>,
+    required_if_value: Vec<(OptName<'a>, &'a str, &'a str)>,
+    required_unless: Vec<(OptName<'a>, &'a str)>,
+    overrides: Vec<(&'a str, &'a str)>,
+    /// The definitions, which everything about an argument is read from
     definitions: Vec<Arg<'a>>,
+    /// The checks on the values of each definition, at the same index
+    constraints: Vec<Constraints<'a>>,
+    /// The index of the definition of each flag, including the aliases
+    flags: HashMap<FlagName<'a>, usize>,
+    strings: Strings<'a>,
+    usage: Option<&'a str>,
+    env_prefix: Option<&'a str>,
+    #[cfg(feature = "dotenv")]
+    dotenv: Option<&'a str>,
+    error_formatter: Option<ErrorFormatter>,
+    color: ColorChoice,
+    help_order: HelpOrder,
+    help_style: HelpStyle<'a>,
+    help_width: Option<usize>,
+    stop_at_unknown: bool,
+    collect_unknown: bool,
+    continue_after_unknown: bool,
+    positionals_after_passalong: bool,
+    normalize_long_names: bool,
+    negate_switches: bool,
+    posix_mode: bool,
+    groups: Vec<Group<'a>>,
 }
 
 /// One or more arguments structured by the parser.
-#[derive(Debug)]
-pub enum StructuredArgument<'a> {
+///
+/// Optional arguments come with the `flag` they were given with (one of their
+/// names, as spelled on the command-line), and their `occurrence`: how many
+/// times they were given so far, this time included.
+#[derive(Debug, Clone)]
+pub enum StructuredArgument<'def, 'argv> {
     /// A positional argument.
     Positional {
-        name: &'a str,
-        value: &'a str,
+        name: &'def str,
+        value: &'argv str,
     },
     /// The trail of arguments left after all the positional arguments have been
     /// found.
     Trail {
-        values: Vec<&'a str>,
+        values: Vec<&'argv str>,
     },
     /// An optional argument taking a single value.
+    ///
+    /// The flag of a `-NUM` value (see `OptArg::number_flag`) is its first
+    /// digit.
     Single {
-        name: &'a str,
-        parameter: &'a str,
+        name: &'def str,
+        parameter: &'argv str,
+        flag: FlagName<'argv>,
+        occurrence: usize,
     },
     /// An optional argument taking multiple values.
+    ///
+    /// The occurrences of an argument that may be given more than once are
+    /// merged, with the flag of the last one.
     Multiple {
-        name: &'a str,
-        parameters: &'a [&'a str],
+        name: &'def str,
+        parameters: Vec<&'argv str>,
+        flag: FlagName<'argv>,
+        occurrence: usize,
     },
     /// An optional argument that is present.
     Switch {
-        name: &'a str,
+        name: &'def str,
+        flag: FlagName<'argv>,
+        occurrence: usize,
     },
-    /// An optional argument which interrupt the parse when encountered.
+    /// A switch that was turned off with its negated flag (`--no-name`, see
+    /// `Parser::set_negate_switches`).
+    NegatedSwitch {
+        name: &'def str,
+    },
+    /// An optional argument which interrupt the parse when encountered, with
+    /// the arguments after it (e.g. the topic of `--help <topic>`).
     Interrupt {
-        name: &'a str,
+        name: &'def str,
+        args: Vec<&'argv str>,
     },
     /// An optional argument which collects all following arguments without
     /// parsing them (for parsing arguments along to a subcommand or alike).
     PassAlong {
-        name: &'a str,
-        args: &'a [&'a str],
+        name: &'def str,
+        args: Vec<&'argv str>,
+    },
+    /// A digit given as a flag (`-9`) or to the long flag of an argument
+    /// defined with `OptArg::digits`.
+    DigitFlag {
+        name: &'def str,
+        digit: u8,
+    },
+    /// A flag the parser does not know, with `Parser::set_collect_unknown`.
+    Unknown {
+        arg: &'argv str,
+    },
+}
+
+/// A structured argument that owns its values, from
+/// `StructuredArgument::into_owned`.
+///
+/// Unlike a `StructuredArgument`, it does not borrow the arguments, so it can
+/// be kept after them or sent to another thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OwnedArgument {
+    /// A positional argument.
+    Positional {
+        name: String,
+        value: String,
+    },
+    /// The trail of arguments left after the positional arguments.
+    Trail {
+        values: Vec<String>,
+    },
+    /// An optional argument taking a single value.
+    Single {
+        name: String,
+        parameter: String,
+        flag: String,
+        occurrence: usize,
+    },
+    /// An optional argument taking multiple values.
+    Multiple {
+        name: String,
+        parameters: Vec<String>,
+        flag: String,
+        occurrence: usize,
+    },
+    /// An optional argument that is present.
+    Switch {
+        name: String,
+        flag: String,
+        occurrence: usize,
+    },
+    /// A switch that was turned off with its negated flag.
+    NegatedSwitch {
+        name: String,
+    },
+    /// An interrupt, with the arguments after it.
+    Interrupt {
+        name: String,
+        args: Vec<String>,
+    },
+    /// A pass-along, with the arguments it collected.
+    PassAlong {
+        name: String,
+        args: Vec<String>,
+    },
+    /// A digit given as a flag.
+    DigitFlag {
+        name: String,
+        digit: u8,
+    },
+    /// A flag the parser does not know.
+    Unknown {
+        arg: String,
     },
 }
 
+impl<'def, 'argv> StructuredArgument<'def, 'argv> {
+    /// Returns a copy of this structured argument that owns its values.
+    pub fn into_owned(self) -> OwnedArgument {
+        use self::StructuredArgument::*;
+        let to_owned = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+        match self {
+            Positional { name, value } => {
+                OwnedArgument::Positional {
+                    name: name.to_owned(),
+                    value: value.to_owned(),
+                }
+            }
+            Trail { values } => OwnedArgument::Trail { values: to_owned(&values) },
+            Single { name, parameter, flag, occurrence } => {
+                OwnedArgument::Single {
+                    name: name.to_owned(),
+                    parameter: parameter.to_owned(),
+                    flag: flag.to_string(),
+                    occurrence,
+                }
+            }
+            Multiple { name, parameters, flag, occurrence } => {
+                OwnedArgument::Multiple {
+                    name: name.to_owned(),
+                    parameters: to_owned(&parameters),
+                    flag: flag.to_string(),
+                    occurrence,
+                }
+            }
+            Switch { name, flag, occurrence } => {
+                OwnedArgument::Switch {
+                    name: name.to_owned(),
+                    flag: flag.to_string(),
+                    occurrence,
+                }
+            }
+            NegatedSwitch { name } => OwnedArgument::NegatedSwitch { name: name.to_owned() },
+            Interrupt { name, args } => {
+                OwnedArgument::Interrupt {
+                    name: name.to_owned(),
+                    args: to_owned(&args),
+                }
+            }
+            PassAlong { name, args } => {
+                OwnedArgument::PassAlong {
+                    name: name.to_owned(),
+                    args: to_owned(&args),
+                }
+            }
+            DigitFlag { name, digit } => {
+                OwnedArgument::DigitFlag {
+                    name: name.to_owned(),
+                    digit,
+                }
+            }
+            Unknown { arg } => OwnedArgument::Unknown { arg: arg.to_owned() },
+        }
+    }
+}
+
 /// An iterator over structured arguments during a parse.
-#[derive(Debug)]
-pub struct Parse<'a> {
+///
+/// A parse can be cloned to try to structure the arguments in one way, and to
+/// go back to the clone if that does not work out.
+///
+/// The arguments can be any string-like items (`&str`, `String`, ...), and
+/// the structured arguments borrow their text from them.
+///
+/// No arguments make a parse panic: anything the parser cannot structure is
+/// a `ParseError`, so that untrusted command lines can be parsed.
+///
+/// Once `next` has returned `None`, it keeps returning `None` (the parse is a
+/// `FusedIterator`), and `size_hint` gives an upper bound on the items left.
+#[derive(Debug, Clone)]
+pub struct Parse<'def, 'argv, S: 'argv = &'argv str> {
     index: usize,
     position: usize,
-    parser: &'a Parser<'a>,
-    args: &'a [&'a str],
-    found_flags: HashSet<OptName<'a>>,
-    leftover_short_flags: Vec<FlagName<'a>>,
+    parser: &'argv Parser<'def>,
+    args: &'argv [S],
+    found_flags: HashSet<OptName<'def>>,
+    leftover_short_flags: Vec<FlagName<'argv>>,
     finished: bool,
-    trail: Vec<&'a str>,
-    passalong: Option<(&'a str, usize)>,
+    trail: Vec<&'argv str>,
+    passalong: Option<(&'def str, usize)>,
+    operands_only: bool,
+    occurrences: HashMap<OptName<'def>, usize>,
+    accumulated: Vec<StructuredArgument<'def, 'argv>>,
+    given_values: Vec<(&'def str, &'argv str)>,
+    last_given: HashMap<&'def str, &'argv str>,
+    warnings: Vec<ParseWarning<'def, 'argv>>,
+    #[cfg(feature = "debug")]
+    decisions: Option<Vec<Decision<'def, 'argv>>>,
+    #[cfg(feature = "debug")]
+    print_decisions: bool,
+    #[cfg(feature = "tracing")]
+    span: ::tracing::Span,
 }
 
-impl<'a> Parse<'a> {
+impl<'def, 'argv, S: AsRef<str>> Parse<'def, 'argv, S> {
     /// Returns the remaining unparsed arguments for this parse run.
-    pub fn remaining_args(&self) -> &'a [&'a str] {
-        &self.args[self.index..]
+    pub fn remaining_args(&self) -> &'argv [S] {
+        self.args.get(self.index..).unwrap_or(&[])
+    }
+
+    /// Returns the argument at the given index, or an empty one past the end.
+    fn arg(&self, index: usize) -> &'argv str {
+        let args = self.args;
+        args.get(index).map_or("", |arg| arg.as_ref())
+    }
+
+    /// Returns the arguments in the given range (the part of it that exists).
+    fn args_in(&self, range: Range<usize>) -> Vec<&'argv str> {
+        let args = self.args;
+        let args = args.get(range.start.min(args.len())..range.end.min(args.len()));
+        args.unwrap_or(&[]).iter().map(|arg| arg.as_ref()).collect()
+    }
+
+    /// Returns the index of the next argument to parse.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Moves the parse back (or forward) to the given argument index, as if
+    /// the arguments before it had just been parsed and those from it on had
+    /// not (e.g. to parse them in another way after a failed attempt).
+    ///
+    /// The arguments are parsed again from the start, so the parse continues
+    /// from the first argument at or after `index` that starts a structured
+    /// argument, or from an earlier one if the parse ended there. The
+    /// warnings are those of the arguments before that point.
+    pub fn rewind_to(&mut self, index: usize) {
+        let mut parse = self.parser.parse(self.args);
+        #[cfg(feature = "debug")]
+        {
+            if self.decisions.is_some() {
+                parse = parse.traced();
+            }
+        }
+        while (parse.index < index || !parse.leftover_short_flags.is_empty()) && !parse.finished {
+            match parse.next_checked() {
+                Some(Ok(structured)) => {
+                    parse.accumulate(structured);
+                }
+                Some(Err(_)) => {}
+                None => break,
+            }
+        }
+        *self = parse;
+    }
+
+    /// Runs the parse to its end, and returns the structured arguments along
+    /// with the arguments it did not consume.
+    ///
+    /// These are the arguments after an interrupt, or from the first
+    /// unrecognized one on with `Parser::set_stop_at_unknown`.
+    pub fn into_remaining(mut self)
+                          -> Result<(Vec<StructuredArgument<'def, 'argv>>, &'argv [S]),
+                                    ParseError<'def, 'argv>> {
+        let structured = self.by_ref().collect::<Result<Vec<_>, _>>()?;
+        Ok((structured, self.remaining_args()))
+    }
+
+    /// Keeps the values of a multi-valued option that may be given more than
+    /// once, to structure all its occurrences as one at the end of the parse.
+    /// Returns any other structured argument as is.
+    fn accumulate(&mut self,
+                  structured: StructuredArgument<'def, 'argv>)
+                  -> Option<StructuredArgument<'def, 'argv>> {
+        use self::StructuredArgument::Multiple;
+        let (name, parameters, flag, occurrence) = match structured {
+            Multiple { name, parameters, flag, occurrence }
+                if self.parser.option_named(name).is_some_and(|arg| arg.allows_duplicates()) => {
+                (name, parameters, flag, occurrence)
+            }
+            structured => return Some(structured),
+        };
+        let found = self.accumulated.iter_mut().find(|structured| {
+            matches!(**structured, Multiple { name: other, .. } if other == name)
+        });
+        match found {
+            Some(&mut Multiple { parameters: ref mut values,
+                                 flag: ref mut last_flag,
+                                 occurrence: ref mut last,
+                                 .. }) => {
+                values.extend(parameters);
+                *last_flag = flag;
+                *last = occurrence;
+            }
+            _ => {
+                self.accumulated.push(Multiple {
+                    name,
+                    parameters,
+                    flag,
+                    occurrence,
+                })
+            }
+        }
+        None
+    }
+
+    /// Keeps the values given to the arguments that a requirement depends on
+    /// (see `OptSingle::set_required_if_value`).
+    fn keep_values(&mut self, structured: &StructuredArgument<'def, 'argv>) {
+        use self::StructuredArgument::*;
+        let (name, values) = match *structured {
+            Positional { name, value } => (name, vec![value]),
+            Single { name, parameter, .. } => (name, vec![parameter]),
+            Multiple { name, ref parameters, .. } => (name, parameters.clone()),
+            _ => return,
+        };
+        if self.parser.required_if_value.iter().any(|&(_, other, _)| other == name) {
+            self.given_values.extend(values.into_iter().map(|value| (name, value)));
+        }
+    }
+
+    /// Structures the next argument, and keeps what the later ones are
+    /// checked against.
+    fn next_checked(&mut self)
+                    -> Option<Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>>> {
+        // The flag of grouped short flags is in the argument before
+        let start = match self.leftover_short_flags.is_empty() {
+            true => self.index,
+            false => self.index.saturating_sub(1),
+        };
+        let item = self.next_argument();
+        if let Some(Ok(ref structured)) = item {
+            self.keep_values(structured);
+            self.check_overridden(structured, self.arg(start));
+        }
+        item
+    }
+
+    /// Warns if the structured argument drops an earlier occurrence of its
+    /// argument, or of one that it overrides, from `ParsedArgs`. The flag is
+    /// the argument it was structured from.
+    fn check_overridden(&mut self, structured: &StructuredArgument<'def, 'argv>, flag: &'argv str) {
+        use self::StructuredArgument::*;
+        const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        let (name, given, replaces) = match *structured {
+            Single { name, parameter, .. } => (name, parameter, true),
+            DigitFlag { name, digit } => {
+                (name, DIGITS.get(usize::from(digit)).cloned().unwrap_or(flag), true)
+            }
+            PassAlong { name, .. } => (name, flag, true),
+            // Repeated switches give the same value, and multiple values are
+            // merged
+            Switch { name, .. } | Multiple { name, .. } => (name, flag, false),
+            _ => return,
+        };
+        if let Some(previous) = self.last_given.insert(name, given) {
+            if replaces {
+                self.warnings.push(ParseWarning::OverriddenValue { arg: name, previous });
+            }
+        }
+        for &(first, second) in &self.parser.overrides {
+            let other = if name == first {
+                second
+            } else if name == second {
+                first
+            } else {
+                continue;
+            };
+            if let Some(previous) = self.last_given.remove(other) {
+                self.warnings.push(ParseWarning::OverriddenValue { arg: other, previous });
+            }
+        }
+    }
+
+    /// Counts an occurrence of the optional argument, and returns how many
+    /// times it was given so far.
+    fn occur(&mut self, opt_name: OptName<'def>) -> usize {
+        let count = self.occurrences.entry(opt_name).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Returns whether positional arguments or the required trail are still
+    /// missing.
+    fn positionals_missing(&self) -> bool {
+        self.position < self.parser.positional.len() ||
+        matches!(self.parser.trail, Some((_, ReqType::OnePlus))) && self.trail.is_empty()
+    }
+
+    /// Returns how many of the given parameters a lazy option may take, so
+    /// that the missing positional arguments get the rest.
+    fn lazy_count(&self, opt_name: &OptName<'def>, count: usize, min: usize) -> usize {
+        if !self.parser.option(*opt_name).is_some_and(|arg| arg.is_lazy()) {
+            return count;
+        }
+        let missing = self.parser.positional.len().saturating_sub(self.position) +
+                      usize::from(matches!(self.parser.trail, Some((_, ReqType::OnePlus))) &&
+                                  self.trail.is_empty());
+        let later = self.args
+                        .get(self.index + count..)
+                        .unwrap_or(&[])
+                        .iter()
+                        .map(|arg| argument_type(arg.as_ref()))
+                        .filter(|given| matches!(given, GivenArgument::Value(_)))
+                        .count();
+        count.saturating_sub(missing.saturating_sub(later)).max(min)
+    }
+
+    /// Returns whether the argument is a flag or a group of short flags that
+    /// the parser knows.
+    fn is_known_flag(&self, arg: &str) -> bool {
+        if self.number_option(arg).is_some() {
+            return true;
+        }
+        match argument_type(arg) {
+            GivenArgument::Value(_) => false,
+            given => self.is_known(&given),
+        }
+    }
+
+    /// Returns whether the parser knows the argument, for
+    /// `Parser::set_stop_at_unknown`.
+    fn is_known(&self, given: &GivenArgument<'argv>) -> bool {
+        match *given {
+            GivenArgument::Value(_) => {
+                self.position < self.parser.positional.len() || self.parser.trail.is_some()
+            }
+            GivenArgument::Flag(flag) | GivenArgument::Attached(flag, _) => {
+                self.resolve(flag).is_some() || self.negated(flag).is_some()
+            }
+            GivenArgument::ShortFlags(ref flags) => {
+                // The flags after an option taking parameters are its value
+                for flag in flags {
+                    match self.parser.flag_option(*flag) {
+                        Some(name) if self.parser.opt_type(name).is_some() => return true,
+                        Some(_) => {}
+                        None => return false,
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Returns the option given as `-NUM` if the argument is such a number.
+    fn number_option(&self, arg: &str) -> Option<OptName<'def>> {
+        let number = arg.strip_prefix('-')?;
+        let first = number.chars().next()?;
+        if !number.bytes().all(|byte| byte.is_ascii_digit()) ||
+           number.len() == 1 && self.parser.flags.contains_key(&FlagName::Short(first)) {
+            return None;
+        }
+        self.parser.number_option
+    }
+
+    /// Structures a `-NUM` argument as the parameter of its option.
+    fn parse_number(&mut self,
+                    opt_name: OptName<'def>,
+                    arg: &'argv str)
+                    -> Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>> {
+        let allowed = self.parser.option(opt_name).is_some_and(|arg| arg.allows_duplicates());
+        if !self.found_flags.insert(opt_name) && !allowed {
+            return Err(ParseError::DuplicatePositionalArgument { arg });
+        }
+        let value = arg.strip_prefix('-').unwrap_or(arg);
+        self.check_constraints(self.parser.constraints_of(opt_name), arg, value)?;
+        let flag = FlagName::Short(value.chars().next().unwrap_or('0'));
+        Ok(StructuredArgument::Single {
+            name: opt_name.name(),
+            parameter: value,
+            flag,
+            occurrence: self.occur(opt_name),
+        })
+    }
+
+    /// Returns the argument of the given flag, which is the one taking digit
+    /// flags for an undefined short digit flag.
+    fn resolve(&self, flag: FlagName<'argv>) -> Option<OptName<'def>> {
+        match self.parser.flag_option(flag) {
+            Some(name) => Some(name),
+            None => match flag {
+                FlagName::Short(ch) if ch.is_ascii_digit() => self.parser.digits,
+                FlagName::Long(long) if self.parser.normalize_long_names => {
+                    let same = |name: &str| {
+                        name.len() == long.len() &&
+                        name.bytes().zip(long.bytes()).all(|(a, b)| {
+                            a == b || matches!((a, b), (b'-', b'_') | (b'_', b'-'))
+                        })
+                    };
+                    // The first definition wins, whatever the order of the map
+                    self.parser.definitions.iter().find_map(|arg| {
+                        let opt_name = arg.option_name()?;
+                        let mut longs = arg.aliases().iter().chain(arg.hidden_aliases());
+                        let found = same(opt_name.name()) || longs.any(|alias| same(alias));
+                        found.then_some(opt_name)
+                    })
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns the switch that the flag negates (`--no-name`), with
+    /// `Parser::set_negate_switches`.
+    fn negated(&self, flag: FlagName<'argv>) -> Option<OptName<'def>> {
+        if !self.parser.negate_switches {
+            return None;
+        }
+        let long = match flag {
+            FlagName::Long(long) => long.strip_prefix("no-")?,
+            FlagName::Short(_) => return None,
+        };
+        self.resolve(FlagName::Long(long)).filter(|name| {
+            self.parser.option(*name).is_some_and(|arg| {
+                matches!(arg::internal_get_raw(arg), ArgType::Switch(_)) && !arg.is_counted()
+            })
+        })
+    }
+
+    /// Structures a negated switch.
+    fn parse_negated(&mut self,
+                     opt_name: OptName<'def>,
+                     attached: Option<&'argv str>,
+                     arg: &'argv str)
+                     -> Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>> {
+        if attached.is_some() {
+            self.finished = true;
+            return Err(ParseError::UnexpectedArgument {
+                arg,
+                suggestion: None,
+            });
+        }
+        // The last of the switch and its negation wins
+        self.found_flags.remove(&opt_name);
+        if let Some(note) = self.parser.option(opt_name).and_then(|arg| arg.deprecated()) {
+            self.warnings.push(ParseWarning::DeprecatedFlag { arg, note });
+        }
+        Ok(StructuredArgument::NegatedSwitch { name: opt_name.name() })
+    }
+
+    /// Records what each argument is taken as during this parse, to be read
+    /// with `decisions`.
+    #[cfg(feature = "debug")]
+    pub fn traced(mut self) -> Self {
+        if self.decisions.is_none() {
+            self.decisions = Some(Vec::new());
+        }
+        self
+    }
+
+    /// Returns the decisions recorded so far in this parse run, if it is
+    /// traced (see `traced` and `ARGONAUT_DEBUG`).
+    #[cfg(feature = "debug")]
+    pub fn decisions(&self) -> &[Decision<'def, 'argv>] {
+        self.decisions.as_deref().unwrap_or(&[])
+    }
+
+    /// Records that the arguments in the range were taken in the given role,
+    /// if the parse is traced.
+    #[cfg(feature = "debug")]
+    fn record(&mut self, range: Range<usize>, role: Role<'def>) {
+        let args = self.args;
+        let decisions = match self.decisions {
+            Some(ref mut decisions) => decisions,
+            None => return,
+        };
+        for (index, arg) in args.iter().enumerate().take(range.end).skip(range.start) {
+            let decision = Decision {
+                index,
+                arg: arg.as_ref(),
+                role,
+            };
+            if self.print_decisions {
+                eprintln!("argonaut: {}", decision);
+            }
+            decisions.push(decision);
+        }
+    }
+
+    /// Returns the warnings found so far in this parse run.
+    ///
+    /// Iterate over the parse by reference (`for item in &mut parse`) to be
+    /// able to check these once it is done.
+    pub fn warnings(&self) -> &[ParseWarning<'def, 'argv>] {
+        &self.warnings
+    }
+
+    /// Warns if the given value is the name of a defined flag.
+    fn check_value(&mut self, value: &'argv str) {
+        if let Some(opt_name) = self.parser.flag_option(FlagName::Long(value)) {
+            self.warnings.push(ParseWarning::SuspiciousValue {
+                value,
+                flag: opt_name.name(),
+            });
+        }
+    }
+
+    /// Checks that the value is one of the choices and is accepted by the
+    /// validator of the argument, if any.
+    fn check_constraints(&self,
+                         constraints: Option<&Constraints<'def>>,
+                         arg: &'argv str,
+                         value: &'argv str)
+                         -> Result<(), ParseError<'def, 'argv>> {
+        let constraints = match constraints {
+            Some(constraints) => constraints,
+            None => return Ok(()),
+        };
+        if let Some(choices) = constraints.choices {
+            if !choices.contains(value) {
+                return Err(ParseError::InvalidChoice {
+                    arg,
+                    value,
+                    choices,
+                });
+            }
+        }
+        if let Some(ref range) = constraints.range {
+            if !value.parse().is_ok_and(|number| range.contains(&number)) {
+                return Err(ParseError::InvalidValue {
+                    arg,
+                    value,
+                    reason: strings::fill(self.parser.strings().out_of_range,
+                                          &[("range", &arg::internal_describe_range(range))]),
+                });
+            }
+        }
+        if let Some(validator) = constraints.validator {
+            if let Err(reason) = validator(value) {
+                return Err(ParseError::InvalidValue {
+                    arg,
+                    value,
+                    reason,
+                });
+            }
+        }
+        #[cfg(feature = "regex")]
+        {
+            if let Some(ref regex) = constraints.regex {
+                if !regex.is_match(value) {
+                    return Err(ParseError::InvalidValue {
+                        arg,
+                        value,
+                        reason: strings::fill(self.parser.strings().no_match,
+                                              &[("regex", regex.as_str())]),
+                    });
+                }
+            }
+        }
+        Ok(())
     }
 
     // Parses the given flag
     fn parse_flag(&mut self,
-                  flag: FlagName<'a>,
-                  arg: &'a str)
-                  -> Result<StructuredArgument<'a>, ParseError<'a>> {
+                  flag: FlagName<'argv>,
+                  attached: Option<&'argv str>,
+                  arg: &'argv str)
+                  -> Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>> {
         use self::ParseError::*;
         use self::StructuredArgument::*;
 
-        let opt_name = match self.parser.aliases.get(&flag) {
-            Some(name) => *name,
+        let opt_name = match self.resolve(flag) {
+            Some(name) => name,
             None => {
-                self.finished = true;
-                return Err(UnknownOptionalArgument { arg: arg });
+                if let Some(opt_name) = self.negated(flag) {
+                    record!(self, self.index - 1, Role::Negated(opt_name.name()));
+                    return self.parse_negated(opt_name, attached, arg);
+                }
+                record!(self, self.index - 1, Role::Unknown);
+                if self.parser.collect_unknown {
+                    return Ok(Unknown { arg });
+                }
+                self.finished = !self.parser.continue_after_unknown;
+                return Err(UnknownOptionalArgument {
+                    arg,
+                    suggestion: self.parser.similar_flag(arg),
+                });
             }
         };
+        trace_event!(trace, arg, flag = %flag, option = opt_name.name(), "resolved flag");
+        record!(self, self.index - 1, Role::Flag(opt_name.name()));
 
-        if self.found_flags.contains(&opt_name) {
-            return Err(DuplicatePositionalArgument { arg: arg });
+        // The flag cancels the ones it overrides, which may then be given again
+        let name = opt_name.name();
+        for &(first, second) in &self.parser.overrides {
+            let other = if name == first {
+                second
+            } else if name == second {
+                first
+            } else {
+                continue;
+            };
+            self.found_flags.retain(|found| found.name() != other);
+        }
+
+        let repeatable = self.parser
+                             .option(opt_name)
+                             .is_some_and(|arg| arg.is_counted() || arg.allows_duplicates());
+        if self.found_flags.contains(&opt_name) && !repeatable {
+            return Err(DuplicatePositionalArgument { arg });
+        }
+
+        if let Some(note) = self.parser.option(opt_name).and_then(|arg| arg.deprecated()) {
+            self.warnings.push(ParseWarning::DeprecatedFlag { arg, note });
         }
 
-        if self.parser.switches.contains(&opt_name) {
+        if let Some(value) = attached {
+            // Only an option with parameters takes an attached value
+            match self.parser.opt_type(opt_name) {
+                Some(OptType::Single) => {
+                    self.found_flags.insert(opt_name);
+                    self.check_constraints(self.parser.constraints_of(opt_name),
+                                           arg,
+                                           value)?;
+                    return Ok(Single {
+                        name: opt_name.name(),
+                        parameter: value,
+                        flag,
+                        occurrence: self.occur(opt_name),
+                    });
+                }
+                Some(_) => {
+                    self.found_flags.insert(opt_name);
+                    let params = self.split_values(opt_name, vec![value]);
+                    for &param in &params {
+                        self.check_constraints(self.parser.constraints_of(opt_name),
+                                               arg,
+                                               param)?;
+                    }
+                    return Ok(Multiple {
+                        name: opt_name.name(),
+                        parameters: params,
+                        flag,
+                        occurrence: self.occur(opt_name),
+                    });
+                }
+                // The attached value of an interrupt is its first argument
+                // (e.g. the topic of `--help=<topic>`)
+                None if self.parser.interrupt_precedence(opt_name).is_some() => {
+                    self.finished = true;
+                    let (strongest, index) = self.strongest_interrupt(opt_name);
+                    record!(self,
+                            range index..self.args.len(),
+                            Role::Interrupted(strongest.name()));
+                    let mut args = self.args_in(index..self.args.len());
+                    if strongest == opt_name && index == self.index {
+                        args.insert(0, value);
+                    }
+                    return Ok(Interrupt {
+                        name: strongest.name(),
+                        args,
+                    });
+                }
+                None => {
+                    self.finished = true;
+                    return Err(UnexpectedArgument {
+                        arg,
+                        suggestion: None,
+                    });
+                }
+            }
+
+        } else if self.parser.is_switch(opt_name) {
             self.found_flags.insert(opt_name);
-            return Ok(Switch { name: opt_name.name() });
+            return Ok(Switch {
+                name: opt_name.name(),
+                flag,
+                occurrence: self.occur(opt_name),
+            });
 
-        } else if self.parser.interrupts.contains(&opt_name) {
+        } else if self.parser.digits == Some(opt_name) {
+            self.found_flags.insert(opt_name);
+            let value = match flag {
+                FlagName::Short(_) => arg.strip_prefix('-').unwrap_or(arg),
+                FlagName::Long(_) => {
+                    self.index += 1;
+                    match self.args.get(self.index - 1).map(|value| argument_type(value.as_ref())) {
+                        Some(GivenArgument::Value(value)) => {
+                            record!(self, self.index - 1, Role::Parameter(opt_name.name()));
+                            value
+                        }
+                        _ => return Err(MissingParameter { arg }),
+                    }
+                }
+            };
+            self.check_constraints(self.parser.constraints_of(opt_name), arg, value)?;
+            let digit = match value.parse() {
+                Ok(digit) if value.len() == 1 => digit,
+                _ => {
+                    return Err(InvalidValue {
+                        arg,
+                        value,
+                        reason: self.parser.strings().not_a_digit.to_owned(),
+                    });
+                }
+            };
+            return Ok(DigitFlag {
+                name: opt_name.name(),
+                digit,
+            });
+
+        } else if self.parser.interrupt_precedence(opt_name).is_some() {
             self.finished = true;
-            return Ok(Interrupt { name: opt_name.name() });
+            let (opt_name, index) = self.strongest_interrupt(opt_name);
+            record!(self, range index..self.args.len(), Role::Interrupted(opt_name.name()));
+            return Ok(Interrupt {
+                name: opt_name.name(),
+                args: self.args_in(index..self.args.len()),
+            });
+
+        } else if self.parser.is_passalong(opt_name) &&
+                  (self.parser.option(opt_name).is_some_and(|arg| arg.stops_at_flags()) ||
+                   self.parser.positionals_after_passalong && self.positionals_missing()) {
+            self.found_flags.insert(opt_name);
+            let start = self.index;
+            let end = self.args
+                          .get(start..)
+                          .unwrap_or(&[])
+                          .iter()
+                          .position(|arg| arg.as_ref() == "--" || self.is_known_flag(arg.as_ref()))
+                          .map_or(self.args.len(), |count| start + count);
+            self.index = end;
+            record!(self, range start..end, Role::PassedAlong(opt_name.name()));
+            // Skip a '--' that ends the pass-along, unless it is a flag itself
+            if self.args.get(end).is_some_and(|arg| arg.as_ref() == "--") &&
+               !self.is_known_flag("--") {
+                self.index += 1;
+            }
+            return Ok(PassAlong {
+                name: opt_name.name(),
+                args: self.args_in(start..end),
+            });
 
-        } else if self.parser.passalongs.contains(&opt_name) {
+        } else if self.parser.is_passalong(opt_name) {
             if let Some(res) = self.check_trail() {
                 self.passalong = Some((opt_name.name(), self.index));
                 return res;
             } else {
+                self.finished = true;
+                record!(self,
+                        range self.index..self.args.len(),
+                        Role::PassedAlong(opt_name.name()));
                 return Ok(PassAlong {
                     name: opt_name.name(),
-                    args: &self.args[self.index..],
+                    args: self.args_in(self.index..self.args.len()),
                 });
             }
         }
         // The argument must be an optional one
         self.found_flags.insert(opt_name);
-        let opt_type = self.parser
-                           .options
-                           .get(&opt_name)
-                           .expect("Broken invariant: a flag was in aliases, but was not foundin \
-                                    the arg type structures");
-        self.find_parameters(arg, opt_type, opt_name)
-    }
-
-    fn validate_grouped_short(&mut self,
-                              flag: FlagName<'a>,
-                              arg: &'a str)
-                              -> Result<(), ParseError<'a>> {
+        // Every defined flag has a kind, so this only fails on a broken parser
+        match self.parser.opt_type(opt_name) {
+            Some(opt_type) => self.find_parameters(arg, opt_type, opt_name, flag),
+            None => {
+                Err(UnknownOptionalArgument {
+                    arg,
+                    suggestion: None,
+                })
+            }
+        }
+    }
+
+    /// Returns the interrupt with the highest precedence among the found one
+    /// and those given after it, along with the index of the arguments after
+    /// that interrupt. The first one wins among equals.
+    ///
+    /// Interrupts cannot be grouped with other short flags, so only single
+    /// flags are considered.
+    fn strongest_interrupt(&self, found: OptName<'def>) -> (OptName<'def>, usize) {
+        let later = self.args.get(self.index..).unwrap_or(&[]).iter().enumerate();
+        let later = later.filter_map(|(i, arg)| {
+            match argument_type(arg.as_ref()) {
+                GivenArgument::Flag(flag) => Some((flag, self.index + i + 1)),
+                _ => None,
+            }
+        });
+
+        let mut strongest = (found, self.index);
+        let mut highest = self.parser.interrupt_precedence(found).unwrap_or(0);
+        for (flag, after) in later {
+            let opt_name = match self.resolve(flag) {
+                Some(name) => name,
+                None => continue,
+            };
+            // The arguments of a pass-along are not parsed
+            if self.parser.is_passalong(opt_name) {
+                break;
+            }
+            if let Some(precedence) = self.parser.interrupt_precedence(opt_name) {
+                if precedence > highest {
+                    strongest = (opt_name, after);
+                    highest = precedence;
+                }
+            }
+        }
+        strongest
+    }
+
+    /// Parses a flag of a group of short flags. An option taking parameters
+    /// ends the group, and takes the rest of it as its value (`-vofile`) or
+    /// the parameters after the group (`-vo file`).
+    fn parse_grouped_short(&mut self,
+                           flag: FlagName<'argv>,
+                           arg: &'argv str)
+                           -> Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>> {
         use self::ParseError::*;
-        let opt_name = match self.parser.aliases.get(&flag) {
+        let opt_name = match self.parser.flag_option(flag) {
             Some(name) => name,
             None => {
-                self.finished = true;
-                return Err(UnknownOptionalArgument { arg: arg });
+                record!(self, self.index - 1, Role::Unknown);
+                self.finished = !self.parser.continue_after_unknown;
+                return Err(UnknownOptionalArgument {
+                    arg,
+                    suggestion: self.parser.similar_flag(arg),
+                });
             }
         };
-        if !self.parser.switches.contains(&opt_name) {
+        if self.parser.opt_type(opt_name).is_some() && !self.leftover_short_flags.is_empty() {
+            let rest: usize = self.leftover_short_flags
+                                  .drain(..)
+                                  .map(|flag| match flag {
+                                      FlagName::Short(ch) => ch.len_utf8(),
+                                      FlagName::Long(long) => long.len(),
+                                  })
+                                  .sum();
+            let value = arg.get(arg.len().saturating_sub(rest)..).unwrap_or("");
+            return self.parse_flag(flag, Some(value.strip_prefix('=').unwrap_or(value)), arg);
+        }
+        let groupable = self.parser.opt_type(opt_name).is_some() ||
+                        self.parser.is_switch(opt_name);
+        if !groupable {
             return Err(GroupedNonSwitch {
-                arg: arg,
+                arg,
                 invalid: flag.to_string(),
             });
         }
-        Ok(())
+        self.parse_flag(flag, None, arg)
     }
 
-    fn check_trail(&mut self) -> Option<Result<StructuredArgument<'a>, ParseError<'a>>> {
+    fn check_trail(&mut self)
+                   -> Option<Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>>> {
         use self::StructuredArgument::*;
         use self::ParseError::*;
         // A positional argument wasn't given
-        if self.position < self.parser.positional.len() {
-            let arg = self.parser.positional[self.position];
-            return Some(Err(MissingPositionalArgument { arg: arg }));
+        if let Some(&arg) = self.parser.positional.get(self.position) {
+            return Some(Err(MissingPositionalArgument { arg }));
+        }
+        let missing = self.parser.required.iter().find(|name| !self.found_flags.contains(name));
+        if let Some(name) = missing {
+            return Some(Err(MissingOption { arg: name.name() }));
+        }
+        let given = |other: &str| self.found_flags.iter().any(|found| found.name() == other);
+        for &(name, other) in &self.parser.required_if {
+            if !self.found_flags.contains(&name) && given(other) {
+                return Some(Err(MissingOptionIf { arg: name.name(), other }));
+            }
+        }
+        for &(name, other, value) in &self.parser.required_if_value {
+            let trail = self.parser.trail.as_ref().is_some_and(|&(trail, _)| trail == other) &&
+                        self.trail.contains(&value);
+            if !self.found_flags.contains(&name) &&
+               (trail || self.given_values.contains(&(other, value))) {
+                return Some(Err(MissingOptionIfValue { arg: name.name(), other, value }));
+            }
+        }
+        for &(name, other) in &self.parser.required_unless {
+            if !self.found_flags.contains(&name) && !given(other) {
+                return Some(Err(MissingOptionUnless { arg: name.name(), other }));
+            }
+        }
+        for group in &self.parser.groups {
+            let mut found = group.members.iter().filter(|&&member| given(member));
+            match (found.next(), found.next()) {
+                (None, _) => {
+                    return Some(Err(MissingGroup {
+                        group: group.name,
+                        members: group.members,
+                    }));
+                }
+                (Some(arg), Some(other)) if group.exclusive => {
+                    return Some(Err(ConflictingGroup {
+                        group: group.name,
+                        arg,
+                        other,
+                    }));
+                }
+                (Some(_), _) => {}
+            }
         }
         match self.parser.trail {
             // Validate that at least one trail argument is present
             Some((arg, ReqType::OnePlus)) => {
-                if self.trail.len() < 1 {
-                    return Some(Err(MissingTrail { arg: arg }));
+                if self.trail.is_empty() {
+                    return Some(Err(MissingTrail { arg }));
                 }
             }
             Some((_, ReqType::ZeroPlus)) => {}
@@ -256,82 +1448,99 @@ impl<'a> Parse<'a> {
         Some(Ok(Trail { values: self.trail.clone() }))
     }
 
+    /// Splits the parameters of the option at its delimiter, if it has one.
+    fn split_values(&self, opt_name: OptName<'def>, params: Vec<&'argv str>) -> Vec<&'argv str> {
+        match self.parser.option(opt_name).and_then(|arg| arg.delimiter()) {
+            Some(delimiter) => params.iter().flat_map(|param| param.split(delimiter)).collect(),
+            None => params,
+        }
+    }
+
     /// Attempts to find enough parameters for the given option type.
     fn find_parameters(&mut self,
-                       arg: &'a str,
-                       opt_type: &OptType,
-                       opt_name: OptName<'a>)
-                       -> Result<StructuredArgument<'a>, ParseError<'a>> {
+                       arg: &'argv str,
+                       opt_type: OptType,
+                       opt_name: OptName<'def>,
+                       flag: FlagName<'argv>)
+                       -> Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>> {
         use self::ParseError::*;
         use self::StructuredArgument::*;
         use self::GivenArgument::Value;
-        let args = &self.args[self.index..];
+        let args = self.args_in(self.index..self.args.len());
         // println!("Finding parameters of {} ({:?}) in {:?}", name, opt_type, args);
-        match *opt_type {
+        match opt_type {
             OptType::Single => {
                 self.index += 1;
-                if args.len() < 1 {
-                    return Err(MissingParameter { arg: arg });
-                }
-                if let Value(value) = argument_type(args[0]) {
+                if let Some(Value(value)) = args.first().map(|&value| argument_type(value)) {
+                    record!(self, self.index - 1, Role::Parameter(opt_name.name()));
+                    self.check_value(value);
+                    self.check_constraints(self.parser.constraints_of(opt_name), arg, value)?;
                     Ok(Single {
                         name: opt_name.name(),
                         parameter: value,
+                        flag,
+                        occurrence: self.occur(opt_name),
                     })
                 } else {
-                    Err(MissingParameter { arg: arg })
+                    Err(MissingParameter { arg })
                 }
             }
             OptType::ZeroPlus => {
                 let count = args.iter()
-                                .take_while(|arg| {
-                                    if let Value(_) = argument_type(arg) {
-                                        true
-                                    } else {
-                                        false
-                                    }
-                                })
+                                .take_while(|arg| matches!(argument_type(arg), Value(_)))
                                 .count();
-                let params = &self.args[self.index..self.index + count];
+                let count = self.lazy_count(&opt_name, count, 0);
+                let params = self.args_in(self.index..self.index + count);
+                let params = self.split_values(opt_name, params);
+                record!(self,
+                        range self.index..self.index + count,
+                        Role::Parameter(opt_name.name()));
                 self.index += count;
+                for &param in &params {
+                    self.check_value(param);
+                    self.check_constraints(self.parser.constraints_of(opt_name), arg, param)?;
+                }
                 Ok(Multiple {
                     name: opt_name.name(),
                     parameters: params,
+                    flag,
+                    occurrence: self.occur(opt_name),
                 })
             }
             OptType::OnePlus => {
-                if args.len() < 1 {
-                    return Err(MissingParameter { arg: arg });
-                }
-                if let Value(_) = argument_type(args[0]) {
-                } else {
-                    return Err(MissingParameter { arg: arg });
+                if !matches!(args.first().map(|&value| argument_type(value)), Some(Value(_))) {
+                    return Err(MissingParameter { arg });
                 }
                 let count = args.iter()
                                 .skip(1)
-                                .take_while(|arg| {
-                                    if let Value(_) = argument_type(arg) {
-                                        true
-                                    } else {
-                                        false
-                                    }
-                                })
+                                .take_while(|arg| matches!(argument_type(arg), Value(_)))
                                 .count() + 1;
-                let params = &self.args[self.index..self.index + count];
+                let count = self.lazy_count(&opt_name, count, 1);
+                let params = self.args_in(self.index..self.index + count);
+                let params = self.split_values(opt_name, params);
+                record!(self,
+                        range self.index..self.index + count,
+                        Role::Parameter(opt_name.name()));
                 self.index += count;
+                for &param in &params {
+                    self.check_value(param);
+                    self.check_constraints(self.parser.constraints_of(opt_name), arg, param)?;
+                }
                 Ok(Multiple {
                     name: opt_name.name(),
                     parameters: params,
+                    flag,
+                    occurrence: self.occur(opt_name),
                 })
             }
         }
     }
 }
 
-impl<'a> Iterator for Parse<'a> {
-    type Item = Result<StructuredArgument<'a>, ParseError<'a>>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<'def, 'argv, S: AsRef<str>> Parse<'def, 'argv, S> {
+    /// Finds the next structured argument.
+    fn next_argument(&mut self)
+                     -> Option<Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>>> {
         use self::GivenArgument::*;
         use self::StructuredArgument::*;
         use self::ParseError::*;
@@ -344,66 +1553,165 @@ impl<'a> Iterator for Parse<'a> {
         // Check for leftover short flag from grouped short switches eg. '-abc'
         if !self.leftover_short_flags.is_empty() {
             let flag = self.leftover_short_flags.remove(0);
-            let arg = self.args[self.index - 1];
-            match self.validate_grouped_short(flag, arg) {
-                Err(err) => return Some(Err(err)),
-                Ok(_) => return Some(self.parse_flag(flag, arg)),
-            }
+            let arg = self.arg(self.index - 1);
+            return Some(self.parse_grouped_short(flag, arg));
         }
 
         // Check for a leftover passalong argument
         if let Some((name, index)) = self.passalong {
             self.finished = true;
+            record!(self, range index..self.args.len(), Role::PassedAlong(name));
             return Some(Ok(PassAlong {
-                name: name,
-                args: &self.args[index..],
+                name,
+                args: self.args_in(index..self.args.len()),
             }));
         }
 
         while self.index < self.args.len() {
-            let arg = self.args[self.index];
+            let arg = self.arg(self.index);
             self.index += 1;
-            match argument_type(arg) {
+            if let Some(opt_name) = self.number_option(arg).filter(|_| !self.operands_only) {
+                record!(self, self.index - 1, Role::Parameter(opt_name.name()));
+                return Some(self.parse_number(opt_name, arg));
+            }
+            // A '--' that is not a flag itself ends the options
+            if !self.operands_only && arg == "--" && !self.is_known_flag(arg) {
+                self.operands_only = true;
+                record!(self, self.index - 1, Role::EndOfOptions);
+                continue;
+            }
+            let given = if self.operands_only { Value(arg) } else { argument_type(arg) };
+            trace_event!(trace, arg, given = ?given, "classified argument");
+            if self.parser.stop_at_unknown && !self.is_known(&given) {
+                // Leave the argument for the caller
+                self.index -= 1;
+                record!(self, self.index, Role::Stopped);
+                break;
+            }
+            match given {
                 Value(value) => {
+                    // In POSIX mode, the first operand ends the options
+                    self.operands_only |= self.parser.posix_mode;
+                    self.check_value(value);
                     // Trail?
                     if self.position >= self.parser.positional.len() {
-                        if let Some(_) = self.parser.trail {
+                        if let Some((name, _)) = self.parser.trail {
+                            // Each value is checked as it is found, like the
+                            // positional arguments
+                            let constraints = self.parser.value_constraints(name);
+                            record!(self, self.index - 1, Role::Trail(name));
+                            if let Err(err) = self.check_constraints(constraints, name, value) {
+                                return Some(Err(err));
+                            }
                             self.trail.push(value);
                         } else {
+                            record!(self, self.index - 1, Role::Unexpected);
                             self.finished = true;
-                            return Some(Err(UnexpectedArgument { arg: value }));
+                            return Some(Err(UnexpectedArgument {
+                                arg: value,
+                                suggestion: self.parser.similar_flag(value),
+                            }));
                         }
                         // Positional
-                    } else {
-                        let position = self.parser.positional[self.position];
+                    } else if let Some(&position) = self.parser.positional.get(self.position) {
                         self.position += 1;
+                        record!(self, self.index - 1, Role::Positional(position));
+                        let constraints = self.parser.value_constraints(position);
+                        if let Err(err) = self.check_constraints(constraints, position, value) {
+                            return Some(Err(err));
+                        }
                         return Some(Ok(Positional {
                             name: position,
-                            value: value,
+                            value,
                         }));
                     }
                 }
                 Flag(flag) => {
-                    return Some(self.parse_flag(flag, arg));
+                    return Some(self.parse_flag(flag, None, arg));
                 }
-                ShortFlags(flags) => {
+                Attached(flag, value) => {
+                    return Some(self.parse_flag(flag, Some(value), arg));
+                }
+                ShortFlags(_) if self.parser.collect_unknown &&
+                                 !self.is_known(&given) => {
+                    record!(self, self.index - 1, Role::Unknown);
+                    return Some(Ok(Unknown { arg }));
+                }
+                ShortFlags(mut flags) => {
+                    // A group has two flags or more
+                    if flags.is_empty() {
+                        continue;
+                    }
+                    let flag = flags.remove(0);
                     self.leftover_short_flags = flags;
-                    let flag = self.leftover_short_flags.remove(0);
-                    match self.validate_grouped_short(flag, arg) {
-                        Err(err) => return Some(Err(err)),
-                        Ok(_) => return Some(self.parse_flag(flag, arg)),
+                    return Some(self.parse_grouped_short(flag, arg));
+                }
+            }
+        }
+
+        // The arguments are used up: check what is missing, once
+        self.finished = true;
+        self.check_trail()
+    }
+}
+
+impl<'def, 'argv, S: AsRef<str>> Iterator for Parse<'def, 'argv, S> {
+    type Item = Result<StructuredArgument<'def, 'argv>, ParseError<'def, 'argv>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "tracing")]
+        let _entered = self.span.clone().entered();
+        let item = loop {
+            match self.next_checked() {
+                Some(Ok(structured)) => {
+                    if let Some(structured) = self.accumulate(structured) {
+                        break Some(Ok(structured));
                     }
                 }
+                Some(Err(err)) => {
+                    if self.finished {
+                        self.accumulated.clear();
+                    }
+                    break Some(Err(err));
+                }
+                None if !self.accumulated.is_empty() => {
+                    break Some(Ok(self.accumulated.remove(0)));
+                }
+                item => break item,
             }
+        };
+        #[cfg(feature = "tracing")]
+        match item {
+            Some(Ok(ref structured)) => ::tracing::debug!(?structured, "structured argument"),
+            Some(Err(ref error)) => ::tracing::debug!(%error, "parse error"),
+            None => {}
         }
+        item
+    }
 
-        if !self.finished {
-            self.finished = true;
-            self.check_trail()
-        } else {
-            self.finished = true;
-            None
+    /// The parse may end early, so the lower bound is 0. Each argument left
+    /// gives at most one item per char (for a group of short flags), after
+    /// the grouped flags and the kept values left over, and before the trail
+    /// and a pass-along.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished && self.accumulated.is_empty() {
+            return (0, Some(0));
         }
+        let args = self.args.get(self.index..).unwrap_or(&[]);
+        let upper = args.iter()
+                        .map(|arg| arg.as_ref().len().max(1))
+                        .fold(self.leftover_short_flags.len() + self.accumulated.len() + 2,
+                              usize::saturating_add);
+        (0, Some(upper))
+    }
+}
+
+// Once it is finished, a parse only gives the values it kept and then `None`
+impl<'def, 'argv, S: AsRef<str>> FusedIterator for Parse<'def, 'argv, S> {}
+
+impl<'a> Default for Parser<'a> {
+    fn default() -> Self {
+        Parser::new()
     }
 }
 
@@ -413,14 +1721,279 @@ impl<'a> Parser<'a> {
         Parser {
             positional: Vec::new(),
             trail: None,
-            options: HashMap::new(),
-            switches: HashSet::new(),
-            interrupts: HashSet::new(),
-            used_flags: HashSet::new(),
-            aliases: HashMap::new(),
-            passalongs: HashSet::new(),
+            digits: None,
+            number_option: None,
+            required: Vec::new(),
+            required_if: Vec::new(),
+            required_if_value: Vec::new(),
+            required_unless: Vec::new(),
+            overrides: Vec::new(),
             definitions: Vec::new(),
+            constraints: Vec::new(),
+            flags: HashMap::new(),
+            strings: Strings::english(),
+            usage: None,
+            error_formatter: None,
+            color: ColorChoice::Auto,
+            help_order: HelpOrder::Declaration,
+            help_style: HelpStyle::default(),
+            help_width: None,
+            env_prefix: None,
+            #[cfg(feature = "dotenv")]
+            dotenv: Some(".env"),
+            stop_at_unknown: false,
+            collect_unknown: false,
+            continue_after_unknown: false,
+            positionals_after_passalong: false,
+            normalize_long_names: false,
+            negate_switches: false,
+            posix_mode: false,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Sets the usage line shown when an error is reported
+    /// (eg. `Usage: tool [OPTIONS] FILE`).
+    pub fn set_usage(&mut self, usage: &'a str) {
+        self.usage = Some(usage);
+    }
+
+    /// Returns the usage line of this parser, if one has been set.
+    pub fn usage(&self) -> Option<&'a str> {
+        self.usage
+    }
+
+    /// Reads every option and switch that is not given from an environment
+    /// variable named after its long flag, like `MYTOOL_OUT_DIR` for
+    /// `--out-dir` with the prefix `MYTOOL`.
+    ///
+    /// A variable set with `set_env` (e.g. `OptSingle::set_env`) takes the
+    /// place of the prefixed one. The variables are read by
+    /// `ParsedArgs::from_parse`, like the ones of `set_env`.
+    pub fn set_env_prefix(&mut self, prefix: &'a str) {
+        self.env_prefix = Some(prefix);
+    }
+
+    /// Returns the prefix of the environment variables of the options, if one
+    /// has been set.
+    pub fn env_prefix(&self) -> Option<&'a str> {
+        self.env_prefix
+    }
+
+    /// Sets the file that the environment variables of the arguments are also
+    /// read from, or `None` to only read the environment.
+    ///
+    /// The file holds `NAME=value` lines, and is `.env` (in the working
+    /// directory) by default. Variables of the environment take precedence
+    /// over the ones of the file, and a missing file is ignored. The
+    /// environment of the program is left as it is.
+    #[cfg(feature = "dotenv")]
+    pub fn set_dotenv(&mut self, path: Option<&'a str>) {
+        self.dotenv = path;
+    }
+
+    /// Returns the file that the environment variables are also read from, if
+    /// any.
+    #[cfg(feature = "dotenv")]
+    pub fn dotenv(&self) -> Option<&'a str> {
+        self.dotenv
+    }
+
+    /// Returns the environment variable that the argument is read from when
+    /// it is not given, if any (see `OptSingle::set_env` and
+    /// `set_env_prefix`).
+    pub fn env_var(&self, arg: Arg) -> Option<String> {
+        if let Some(var) = arg.env() {
+            return Some(var.to_owned());
         }
+        let prefix = self.env_prefix?;
+        let long = arg.long().filter(|_| !arg.is_interrupt() && !arg.is_passalong())?;
+        let name: String = long.chars()
+                               .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+                               .collect();
+        Some(format!("{}_{}", prefix, name.to_ascii_uppercase()))
+    }
+
+    /// Sets the function that `report_error` formats errors with, e.g. to
+    /// brand them or to add hints to the report of `format_error`.
+    pub fn set_error_formatter(&mut self, formatter: ErrorFormatter) {
+        self.error_formatter = Some(formatter);
+    }
+
+    /// Returns the function that `report_error` formats errors with, if one
+    /// has been set.
+    pub fn error_formatter(&self) -> Option<ErrorFormatter> {
+        self.error_formatter
+    }
+
+    /// Sets when the help message and the reporting helpers should color
+    /// their output.
+    pub fn set_color(&mut self, color: ColorChoice) {
+        self.color = color;
+    }
+
+    /// Returns when the reporting helpers color their output.
+    pub fn color(&self) -> ColorChoice {
+        self.color
+    }
+
+    /// Sets the order of the arguments within each section of the help
+    /// message (after those with an `Arg::set_display_order`).
+    pub fn set_help_order(&mut self, order: HelpOrder) {
+        self.help_order = order;
+    }
+
+    /// Returns the order of the arguments within each section of the help
+    /// message.
+    pub fn help_order(&self) -> HelpOrder {
+        self.help_order
+    }
+
+    /// Sets whether the parse stops at the first argument it does not
+    /// recognize (an unknown flag or an unexpected value) instead of failing.
+    ///
+    /// The arguments from there on are left untouched, and can be taken with
+    /// `Parse::remaining_args` or `Parse::into_remaining`, e.g. to hand them
+    /// to an inner program.
+    pub fn set_stop_at_unknown(&mut self, stop: bool) {
+        self.stop_at_unknown = stop;
+    }
+
+    /// Returns whether the parse stops at the first argument it does not
+    /// recognize.
+    pub fn stops_at_unknown(&self) -> bool {
+        self.stop_at_unknown
+    }
+
+    /// Sets whether unknown flags are structured as
+    /// `StructuredArgument::Unknown` instead of failing the parse, e.g. for
+    /// flags meant for plugins.
+    ///
+    /// A group of short flags with an unknown one is structured as a whole.
+    /// The values after an unknown flag are parsed like any other values, as
+    /// the parser cannot know if they belong to it.
+    pub fn set_collect_unknown(&mut self, collect: bool) {
+        self.collect_unknown = collect;
+    }
+
+    /// Returns whether unknown flags are structured instead of failing the
+    /// parse.
+    pub fn collects_unknown(&self) -> bool {
+        self.collect_unknown
+    }
+
+    /// Sets whether the parse goes on after an unknown flag, instead of
+    /// ending with its `ParseError::UnknownOptionalArgument`.
+    ///
+    /// The error is still returned, and the next item is structured from the
+    /// arguments after the flag (or the rest of its group of short flags), so
+    /// that all the unknown flags can be reported at once.
+    pub fn set_continue_after_unknown(&mut self, continue_after: bool) {
+        self.continue_after_unknown = continue_after;
+    }
+
+    /// Returns whether the parse goes on after an unknown flag.
+    pub fn continues_after_unknown(&self) -> bool {
+        self.continue_after_unknown
+    }
+
+    /// Sets whether the positional arguments and the trail may be given after
+    /// a pass-along (e.g. `prog --exec cmd args -- FILE...`).
+    ///
+    /// A pass-along given before the positional arguments then ends at `--`
+    /// or at the next known flag, like with `OptPassAlong::stop_at_flags`, and
+    /// the positional arguments are checked at the end of the parse.
+    /// Otherwise they must be given before it.
+    pub fn set_positionals_after_passalong(&mut self, allow: bool) {
+        self.positionals_after_passalong = allow;
+    }
+
+    /// Returns whether the positional arguments and the trail may be given
+    /// after a pass-along.
+    pub fn allows_positionals_after_passalong(&self) -> bool {
+        self.positionals_after_passalong
+    }
+
+    /// Sets whether dashes and underscores are the same in long flags, so that
+    /// `--dry_run` is read as `--dry-run`.
+    ///
+    /// Flags that only differ in this way should not both be defined.
+    pub fn set_normalize_long_names(&mut self, normalize: bool) {
+        self.normalize_long_names = normalize;
+    }
+
+    /// Returns whether dashes and underscores are the same in long flags.
+    pub fn normalizes_long_names(&self) -> bool {
+        self.normalize_long_names
+    }
+
+    /// Sets whether every switch (that is not counted) can also be given as
+    /// `--no-<name>`, which is structured as a
+    /// `StructuredArgument::NegatedSwitch`.
+    ///
+    /// The last of a switch and its negation wins, so that a flag from a
+    /// config file or an alias can be overridden. A defined flag with the
+    /// negated name takes precedence.
+    pub fn set_negate_switches(&mut self, negate: bool) {
+        self.negate_switches = negate;
+    }
+
+    /// Returns whether switches can be given as `--no-<name>`.
+    pub fn negates_switches(&self) -> bool {
+        self.negate_switches
+    }
+
+    /// Sets whether the arguments follow the utility syntax of POSIX, like
+    /// the tools of coreutils with `POSIXLY_CORRECT`:
+    ///
+    /// - The options come before the operands: the first positional argument
+    ///   ends them, and the arguments after it are all operands.
+    /// - The flag `-W` is reserved for the extensions of the implementation,
+    ///   and cannot be defined. Set the mode before defining the arguments.
+    ///
+    /// Long flags are only ever recognized in full, in this mode or not.
+    pub fn set_posix_mode(&mut self, posix: bool) {
+        self.posix_mode = posix;
+    }
+
+    /// Returns whether the arguments follow the utility syntax of POSIX.
+    pub fn posix_mode(&self) -> bool {
+        self.posix_mode
+    }
+
+    /// Sets the layout of the help message.
+    pub fn set_help_style(&mut self, style: HelpStyle<'a>) {
+        self.help_style = style;
+    }
+
+    /// Returns the layout of the help message.
+    pub fn help_style(&self) -> &HelpStyle<'a> {
+        &self.help_style
+    }
+
+    /// Sets the width in columns that the help message is wrapped to.
+    ///
+    /// Without it, the help is wrapped to the width of the terminal (with the
+    /// `terminal_size` feature) or else to the `COLUMNS` environment
+    /// variable, and is not wrapped if neither is known.
+    pub fn set_help_width(&mut self, width: usize) {
+        self.help_width = Some(width);
+    }
+
+    /// Returns the width set for the help message, if any.
+    pub fn help_width(&self) -> Option<usize> {
+        self.help_width
+    }
+
+    /// Sets the texts used for the help message and the messages of errors
+    /// and warnings (eg. to translate them).
+    pub fn set_strings(&mut self, strings: Strings<'a>) {
+        self.strings = strings;
+    }
+
+    /// Returns the texts used for the help message, errors and warnings.
+    pub fn strings(&self) -> &Strings<'a> {
+        &self.strings
     }
 
     /// Adds a list of argument definitions to the parser.
@@ -428,32 +2001,102 @@ impl<'a> Parser<'a> {
     /// added, or if a trail is added twice.
     pub fn define(&mut self, args: &[Arg<'a>]) -> Result<(), String> {
         for arg in args {
-            try!(self.define_single(*arg));
+            self.define_single(*arg)?;
         }
         Ok(())
     }
 
     /// Adds an argument definition to the parser.
     /// Errors if an optional argument with the same name has already been
-    /// added, or if a trail is added twice.
-    pub fn define_single(&mut self, arg: Arg<'a>) -> Result<(), String> {
+    /// added, if a trail is added twice, or if a setting of a definition built
+    /// at runtime (like one of a spec file) does not apply to its kind.
+    pub fn define_single<A: Into<Arg<'a>>>(&mut self, arg: A) -> Result<(), String> {
         use arg::ArgType::*;
 
+        let arg = arg.into();
+        let arg = match arg.has_auto_short() {
+            true => arg::internal_with_short(arg, self.pick_short_flag(arg.name())?),
+            false => arg,
+        };
+
+        // The builders only have the settings of their kind, but the runtime
+        // definitions can still have ones that would otherwise be ignored
+        let takes_values = !matches!(arg::internal_get_raw(arg), Switch(_) | Interrupt(_));
+        if !takes_values && arg.param().is_some() {
+            return Err(format!("Switches and interrupts take no parameter ('{}')", arg.name()));
+        }
+        let checks_values = takes_values && !matches!(arg::internal_get_raw(arg), PassAlong(_));
+        if arg.choices().is_some() && !checks_values {
+            return Err(format!("Only arguments with checked values can have choices ('{}')",
+                               arg.name()));
+        }
+        if arg.default().is_some() && arg.derived_default().is_some() {
+            return Err(format!("An argument cannot have both a default and a derived default \
+                                ('{}')",
+                               arg.name()));
+        }
+        if arg.takes_number_flag() {
+            if let Some(other) = self.number_option.or(self.digits) {
+                return Err(format!("Numbers as flags are already taken by '{}'", other.name()));
+            }
+        }
+
+        let constraints = Constraints {
+            choices: arg.choices(),
+            validator: arg.validator(),
+            range: arg.value_range(),
+            #[cfg(feature = "regex")]
+            regex: match arg.regex() {
+                Some(pattern) => {
+                    let regex = Regex::new(pattern).map_err(|err| {
+                        format!("The pattern of '{}' is invalid: {}", arg.name(), err)
+                    })?;
+                    Some(regex)
+                }
+                None => None,
+            },
+        };
+        let mut names = arg.option_name().map(optional_flag_names).unwrap_or_default();
+        let aliases = arg.aliases().iter().chain(arg.hidden_aliases());
+        names.extend(aliases.map(|&alias| FlagName::Long(alias)));
         if let Some(optname) = arg.option_name() {
-            let names = optional_flag_names(optname);
+            if self.posix_mode && names.contains(&FlagName::Short('W')) {
+                return Err(format!("The flag '-W' is reserved in POSIX mode ('{}')",
+                                   optname.name()));
+            }
 
+            // The value of '--name=value' starts after the first '='
             for name in &names {
-                if self.used_flags.contains(name) {
-                    return Err(format!("The flag '{}' is already defined", name));
+                if let FlagName::Long(long) = *name {
+                    if long.contains('=') {
+                        return Err(format!("The name of a flag cannot contain '=' ('{}')", long));
+                    }
                 }
             }
-
             for name in &names {
-                self.used_flags.insert(*name);
-                self.aliases.insert(*name, optname);
+                if !self.flags.contains_key(name) {
+                    continue;
+                }
+                // Point out the letters that are left for a short flag
+                let free: Vec<String> = self.free_short_flags()
+                                            .iter()
+                                            .map(char::to_string)
+                                            .collect();
+                return Err(match *name {
+                    FlagName::Short(_) if free.is_empty() => {
+                        format!("The flag '{}' is already defined (no letters are free)", name)
+                    }
+                    FlagName::Short(_) => {
+                        format!("The flag '{}' is already defined (free letters: {})",
+                                name,
+                                free.join(" "))
+                    }
+                    FlagName::Long(_) => format!("The flag '{}' is already defined", name),
+                });
             }
         }
 
+        // The definition is only added once nothing can fail anymore
         match arg::internal_get_raw(arg) {
             Single(name) => {
                 if self.positional.contains(&name) {
@@ -484,45 +2127,353 @@ impl<'a> Parser<'a> {
                     }
                 }
             }
-            Switch(optname) => {
-                self.switches.insert(optname);
+            Switch(_) | Interrupt(_) | PassAlong(_) | OptSingle(_) | OptZeroPlus(_) |
+            OptOnePlus(_) => {}
+            Digits(OptName::NormalAndShort(..)) => {
+                return Err(format!("Digit flags cannot have a short name ('{}')", arg.name()));
+            }
+            Digits(optname) => {
+                if let Some(other) = self.digits.or(self.number_option) {
+                    return Err(format!("Numbers as flags are already taken by '{}'", other.name()));
+                }
+                self.digits = Some(optname);
             }
-            Interrupt(optname) => {
-                self.interrupts.insert(optname);
+        }
+        if let Some(optname) = arg.option_name().filter(|_| arg.takes_number_flag()) {
+            self.number_option = Some(optname);
+        }
+        if let Some(optname) = arg.option_name() {
+            let index = self.definitions.len();
+            for &name in &names {
+                self.flags.insert(name, index);
             }
-            PassAlong(optname) => {
-                self.passalongs.insert(optname);
+            if let Some(other) = arg.overridden() {
+                self.overrides.push((optname.name(), other));
             }
-            OptSingle(optname) => {
-                self.options.insert(optname, OptType::Single);
+            if arg.is_required() {
+                self.required.push(optname);
             }
-            OptZeroPlus(optname) => {
-                self.options.insert(optname, OptType::ZeroPlus);
+            if let Some(other) = arg.required_if() {
+                self.required_if.push((optname, other));
             }
-            OptOnePlus(optname) => {
-                self.options.insert(optname, OptType::OnePlus);
+            if let Some((other, value)) = arg.required_if_value() {
+                self.required_if_value.push((optname, other, value));
+            }
+            if let Some(other) = arg.required_unless() {
+                self.required_unless.push((optname, other));
             }
         }
         self.definitions.push(arg);
+        self.constraints.push(constraints);
+        Ok(())
+    }
+
+    /// Requires exactly one of the named optional arguments to be given (e.g.
+    /// `--file`, `--stdin` or `--url`), or the parse ends with a
+    /// `ParseError::MissingGroup` or `ParseError::ConflictingGroup`.
+    ///
+    /// The name of the group is used in the error messages. Errors if a
+    /// member is not a defined optional argument.
+    pub fn define_exclusive_group(&mut self,
+                                  name: &'a str,
+                                  members: &'a [&'a str])
+                                  -> Result<(), String> {
+        self.check_group(name, members)?;
+        self.groups.push(Group {
+            name,
+            members,
+            exclusive: true,
+        });
+        Ok(())
+    }
+
+    /// Requires at least one of the named optional arguments to be given (e.g.
+    /// `--read`, `--write` or `--admin`), or the parse ends with a
+    /// `ParseError::MissingGroup`.
+    ///
+    /// The name of the group is used in the error message. Errors if a member
+    /// is not a defined optional argument.
+    pub fn define_required_group(&mut self,
+                                 name: &'a str,
+                                 members: &'a [&'a str])
+                                 -> Result<(), String> {
+        self.check_group(name, members)?;
+        self.groups.push(Group {
+            name,
+            members,
+            exclusive: false,
+        });
+        Ok(())
+    }
+
+    /// Returns the definition of the optional argument with the given long
+    /// name.
+    fn option_named(&self, name: &'a str) -> Option<Arg<'a>> {
+        let index = *self.flags.get(&FlagName::Long(name))?;
+        self.definitions.get(index).copied()
+    }
+
+    /// Returns the definition of the optional argument.
+    fn option(&self, opt_name: OptName<'a>) -> Option<Arg<'a>> {
+        self.option_named(opt_name.name())
+    }
+
+    /// Returns the optional argument that the flag (or alias) denotes. The
+    /// flag may be borrowed from the arguments of a parse.
+    fn flag_option(&self, flag: FlagName) -> Option<OptName<'a>> {
+        let flags: &HashMap<FlagName, usize> = &self.flags;
+        let index = *flags.get(&flag)?;
+        self.definitions.get(index)?.option_name()
+    }
+
+    /// Returns whether the optional argument is a switch.
+    fn is_switch(&self, opt_name: OptName<'a>) -> bool {
+        self.option(opt_name)
+            .is_some_and(|arg| matches!(arg::internal_get_raw(arg), ArgType::Switch(_)))
+    }
+
+    /// Returns whether the optional argument is a pass-along.
+    fn is_passalong(&self, opt_name: OptName<'a>) -> bool {
+        self.option(opt_name)
+            .is_some_and(|arg| matches!(arg::internal_get_raw(arg), ArgType::PassAlong(_)))
+    }
+
+    /// Returns the precedence of the optional argument if it is an interrupt.
+    fn interrupt_precedence(&self, opt_name: OptName<'a>) -> Option<u32> {
+        self.option(opt_name)
+            .filter(|&arg| matches!(arg::internal_get_raw(arg), ArgType::Interrupt(_)))
+            .map(|arg| arg.precedence())
+    }
+
+    /// Returns the parameters the optional argument takes, if it is an option.
+    fn opt_type(&self, opt_name: OptName<'a>) -> Option<OptType> {
+        match arg::internal_get_raw(self.option(opt_name)?) {
+            ArgType::OptSingle(_) => Some(OptType::Single),
+            ArgType::OptZeroPlus(_) => Some(OptType::ZeroPlus),
+            ArgType::OptOnePlus(_) => Some(OptType::OnePlus),
+            _ => None,
+        }
+    }
+
+    /// Returns the checks on the values of the optional argument.
+    fn constraints_of(&self, opt_name: OptName<'a>) -> Option<&Constraints<'a>> {
+        let index = *self.flags.get(&FlagName::Long(opt_name.name()))?;
+        self.constraints.get(index)
+    }
+
+    /// Returns the checks on the values of the positional argument or the
+    /// trail.
+    fn value_constraints(&self, name: &str) -> Option<&Constraints<'a>> {
+        let index = self.definitions
+                        .iter()
+                        .position(|arg| arg.option_name().is_none() && arg.name() == name)?;
+        self.constraints.get(index)
+    }
+
+    /// Returns the ASCII letters that are not yet the short flag of an
+    /// argument, lowercase ones first.
+    pub fn free_short_flags(&self) -> Vec<char> {
+        ('a'..='z').chain('A'..='Z')
+                   .filter(|&ch| !self.flags.contains_key(&FlagName::Short(ch)))
+                   .filter(|&ch| !(self.posix_mode && ch == 'W'))
+                   .collect()
+    }
+
+    /// Returns the short flag for `Arg::named_auto_short`: the first free
+    /// letter of the name, or else the first one in the other case.
+    fn pick_short_flag(&self, name: &str) -> Result<char, String> {
+        let free = self.free_short_flags();
+        let letters = name.chars().filter(|ch| ch.is_ascii_alphabetic());
+        let other_case = letters.clone().map(|ch| {
+            if ch.is_ascii_lowercase() { ch.to_ascii_uppercase() } else { ch.to_ascii_lowercase() }
+        });
+        letters.chain(other_case).find(|ch| free.contains(ch)).ok_or_else(|| {
+            let free: Vec<String> = free.iter().map(char::to_string).collect();
+            format!("No letter of '{}' is free for a short flag (free letters: {})",
+                    name,
+                    free.join(" "))
+        })
+    }
+
+    /// Defines a `--completions <SHELL>` interrupt, which `complete_from_env`
+    /// handles by printing the completion script for the shell (`bash`, `zsh`
+    /// or `fish`) and exiting.
+    ///
+    /// Errors if an optional argument named `completions` is already defined.
+    pub fn define_completions(&mut self) -> Result<(), String> {
+        self.define_single(Arg::named("completions")
+                               .interrupt()
+                               .set_help("Prints the completion script for the shell (bash, \
+                                          zsh or fish)"))
+    }
+
+    /// Returns the definitions of this parser, in the order they were defined
+    /// in (e.g. for tools that document or display the arguments of a
+    /// program).
+    pub fn args(&self) -> slice::Iter<'_, Arg<'a>> {
+        self.definitions.iter()
+    }
+
+    /// Defines a `--color <WHEN>` option taking `auto`, `always` or `never`,
+    /// whose value can be given to `set_color` after the parse:
+    ///
+    /// ```text
+    /// parser.define_color()?;
+    /// let args = ParsedArgs::from_parse(parser.parse_env())?;
+    /// parser.set_color(args.single_as("color")?.unwrap_or_default());
+    /// ```
+    ///
+    /// Errors if an optional argument named `color` is already defined.
+    pub fn define_color(&mut self) -> Result<(), String> {
+        self.define_single(Arg::named("color")
+                               .single()
+                               .set_param("WHEN")
+                               .set_choices_of::<ColorChoice>()
+                               .set_help("When to color the output"))
+    }
+
+    /// Defines a `--profile <NAME>` option, which selects a profile of the
+    /// configuration file to read before the arguments:
+    ///
+    /// ```text
+    /// parser.define_profile()?;
+    /// let args = ParsedArgs::from_parse(parser.parse_env())?;
+    /// let mut merged = ParsedArgs::from_config_profile(&parser, &path, args.single("profile"))?;
+    /// merged.merge(args);
+    /// ```
+    ///
+    /// The option can also be set in the configuration file, to choose the
+    /// profile read by default. Errors if an optional argument named
+    /// `profile` is already defined.
+    pub fn define_profile(&mut self) -> Result<(), String> {
+        self.define_single(Arg::named("profile")
+                               .single()
+                               .set_param("NAME")
+                               .set_help("The profile of the configuration to use"))
+    }
+
+    /// Returns the long flag that the argument most closely resembles, if it is
+    /// close enough to be a typo or to be missing its dashes.
+    fn similar_flag(&self, arg: &str) -> Option<&'a str> {
+        let name = arg.trim_start_matches('-');
+        let allowed = name.chars().count() / 3;
+        let mut best = None;
+        for opt_name in self.definitions.iter().filter_map(|arg| arg.option_name()) {
+            let distance = edit_distance(name, opt_name.name());
+            if distance <= allowed && best.is_none_or(|(_, best)| distance < best) {
+                best = Some((opt_name.name(), distance));
+            }
+        }
+        best.map(|(name, _)| name)
+    }
+
+    /// Errors unless the members of the group are defined optional arguments.
+    fn check_group(&self, name: &str, members: &[&str]) -> Result<(), String> {
+        if members.is_empty() {
+            return Err(format!("The group '{}' has no members", name));
+        }
+        for member in members {
+            let defined = self.definitions
+                              .iter()
+                              .any(|arg| arg.option_name().is_some() && arg.name() == *member);
+            if !defined {
+                return Err(format!("The member '{}' of the group '{}' is not a defined \
+                                    optional argument",
+                                   member,
+                                   name));
+            }
+        }
         Ok(())
     }
 
     /// Starts parsing the given arguments with this parser.
-    pub fn parse(&'a self, args: &'a [&'a str]) -> Parse<'a> {
+    ///
+    /// The definitions may outlive the arguments: a parser that is kept (e.g.
+    /// in a struct) can parse arguments that only live for the parse. The
+    /// names in the structured arguments and errors borrow from the
+    /// definitions (`'a`), and their values from the arguments (`'argv`), so
+    /// the names can be kept after the arguments are dropped.
+    ///
+    /// The arguments can be a slice of any string-like items, so a
+    /// `Vec<String>` can be parsed as is.
+    ///
+    /// A `--` ends the options, so that the arguments after it are values
+    /// even if they start with a dash, unless `--` is defined as the flag of a
+    /// pass-along.
+    pub fn parse<'argv, S: AsRef<str>>(&'argv self, args: &'argv [S]) -> Parse<'a, 'argv, S> {
         Parse {
             index: 0,
             position: 0,
             parser: self,
-            args: args,
+            args,
             found_flags: HashSet::new(),
             leftover_short_flags: Vec::new(),
             finished: false,
             trail: Vec::new(),
             passalong: None,
+            operands_only: false,
+            occurrences: HashMap::new(),
+            accumulated: Vec::new(),
+            given_values: Vec::new(),
+            last_given: HashMap::new(),
+            warnings: Vec::new(),
+            #[cfg(feature = "debug")]
+            decisions: if debug::debug_from_env() { Some(Vec::new()) } else { None },
+            #[cfg(feature = "debug")]
+            print_decisions: debug::debug_from_env(),
+            #[cfg(feature = "tracing")]
+            span: {
+                let args: Vec<&str> = args.iter().map(|arg| arg.as_ref()).collect();
+                ::tracing::debug_span!("parse", ?args)
+            },
         }
     }
+
+    /// Starts parsing the given arguments, which can be any string-like items
+    /// (eg. `env::args().skip(1)` or the words of an iterator adapter).
+    ///
+    /// The structured arguments and the errors borrow their values and flags
+    /// from the arguments, so these must be stored somewhere that outlives the
+    /// parse, which the iterator is not. They are collected into the buffer
+    /// (replacing what it held), which the caller keeps for as long as the
+    /// results are used. The results can be turned into owned ones with
+    /// `ParsedArgs::from_parse` or `StructuredArgument::into_owned`, after
+    /// which the buffer can be reused, like for the lines of a prompt:
+    ///
+    /// ```text
+    /// let mut buffer = Vec::new();
+    /// for line in stdin.lock().lines() {
+    ///     let line = line?;
+    ///     match ParsedArgs::from_parse(parser.parse_from(line.split_whitespace(), &mut buffer)) {
+    ///         Ok(args) => run(&args),
+    ///         Err(err) => eprintln!("{}", err),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Prefer `parse` when the arguments are already stored in a slice, and
+    /// `parse_env` for the arguments of the program.
+    pub fn parse_from<'argv, I, S>(&'argv self,
+                                   args: I,
+                                   buffer: &'argv mut Vec<String>)
+                                   -> Parse<'a, 'argv, String>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        buffer.clear();
+        buffer.extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self.parse(buffer)
+    }
+
+    /// Starts parsing the arguments given to this program (skipping the
+    /// program path).
+    ///
+    /// Panics if any argument is not valid unicode, like `std::env::args`.
+    pub fn parse_env<'argv>(&'argv self) -> Parse<'a, 'argv, String> {
+        self.parse(env_args())
+    }
 }
 
-pub fn internal_get_definitions<'a, 'b>(parser: &'b Parser<'a>) -> &'b Vec<Arg<'a>> {
-    &parser.definitions
+pub fn internal_get_parser<'def, 'argv, S>(parse: &Parse<'def, 'argv, S>) -> &'argv Parser<'def> {
+    parse.parser
 }
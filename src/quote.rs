@@ -0,0 +1,68 @@
+/// Returns whether the character never needs quoting in a POSIX shell.
+fn is_sh_safe(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || "_-+=@%:,./".contains(ch)
+}
+
+/// Returns whether the character is special to `cmd.exe` outside of quotes.
+fn is_cmd_special(ch: char) -> bool {
+    " \t\"&|<>^()%!".contains(ch)
+}
+
+/// Quotes the arguments for a POSIX shell and joins them with spaces, so that
+/// `sh -c` gets back the same arguments (e.g. to forward a pass-along).
+///
+/// Arguments with special characters are put in single quotes.
+pub fn quote_sh<S: AsRef<str>>(args: &[S]) -> String {
+    let quoted: Vec<String> = args.iter()
+                                  .map(|arg| {
+                                      let arg = arg.as_ref();
+                                      if !arg.is_empty() && arg.chars().all(is_sh_safe) {
+                                          arg.to_owned()
+                                      } else {
+                                          format!("'{}'", arg.replace('\'', "'\\''"))
+                                      }
+                                  })
+                                  .collect();
+    quoted.join(" ")
+}
+
+/// Quotes the arguments for a Windows command line run by `cmd.exe` and joins
+/// them with spaces, so that the program gets back the same arguments.
+///
+/// Arguments with special characters are put in double quotes, following the
+/// rules of the C runtime for backslashes and quotes. `cmd.exe` still expands
+/// variables like `%PATH%` inside quotes.
+pub fn quote_cmd<S: AsRef<str>>(args: &[S]) -> String {
+    let mut quoted = Vec::with_capacity(args.len());
+    for arg in args {
+        let arg = arg.as_ref();
+        if !arg.is_empty() && !arg.contains(is_cmd_special) {
+            quoted.push(arg.to_owned());
+            continue;
+        }
+        let mut out = String::from("\"");
+        let mut backslashes = 0;
+        for ch in arg.chars() {
+            match ch {
+                '\\' => backslashes += 1,
+                '"' => {
+                    // The backslashes before a quote are escaped, and so is the quote
+                    out.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                    backslashes = 0;
+                }
+                _ => {
+                    out.extend(std::iter::repeat_n('\\', backslashes));
+                    backslashes = 0;
+                }
+            }
+            if ch != '\\' {
+                out.push(ch);
+            }
+        }
+        // The backslashes before the closing quote are escaped
+        out.extend(std::iter::repeat_n('\\', backslashes * 2));
+        out.push('"');
+        quoted.push(out);
+    }
+    quoted.join(" ")
+}
@@ -0,0 +1,136 @@
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+use arg::{self, Arg};
+use parser::Parser;
+
+/// A declarative description of the arguments of a parser.
+///
+/// With the `serde` feature this can be deserialized from any format, such as
+/// TOML, YAML or JSON, so the interface of a tool can be kept in a data file:
+///
+/// ```toml
+/// usage = "Usage: tool [OPTIONS] FILE"
+///
+/// [[args]]
+/// name = "file"
+/// kind = "positional"
+/// help = "The file to process."
+///
+/// [[args]]
+/// name = "mode"
+/// short = "m"
+/// kind = "single"
+/// choices = ["fast", "slow"]
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SpecDocument {
+    /// The usage line shown with errors.
+    pub usage: Option<String>,
+    /// The arguments, in the order they are defined.
+    pub args: Vec<SpecArg>,
+}
+
+/// The description of a single argument in a `SpecDocument`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+pub struct SpecArg {
+    /// The name of the argument (the long flag name for optional arguments).
+    pub name: String,
+    /// The short flag name of an optional argument.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub short: Option<char>,
+    /// The kind of argument.
+    pub kind: SpecKind,
+    /// The parameter name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub param: Option<String>,
+    /// The help text.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub help: Option<String>,
+    /// The deprecation note.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deprecated: Option<String>,
+    /// The values accepted by the argument (any value if empty).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub choices: Vec<String>,
+}
+
+/// The kind of an argument in a `SpecArg`, named like the `Arg` constructors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SpecKind {
+    Positional,
+    OptionalTrail,
+    RequiredTrail,
+    Single,
+    ZeroOrMore,
+    OneOrMore,
+    Switch,
+    Count,
+    Interrupt,
+    Passalong,
+}
+
+impl SpecArg {
+    /// Creates the argument definition described by this.
+    pub fn to_arg(&self) -> Result<Arg<'_>, String> {
+        let name = self.name.as_str();
+        let opt = match self.short {
+            Some(short) => Arg::named_and_short(name, short),
+            None => Arg::named(name),
+        };
+        let mut arg: Arg = match self.kind {
+            SpecKind::Positional | SpecKind::OptionalTrail | SpecKind::RequiredTrail
+                if self.short.is_some() => {
+                return Err(format!("The positional argument '{}' cannot have a short name", name));
+            }
+            SpecKind::Positional | SpecKind::OptionalTrail | SpecKind::RequiredTrail
+                if self.deprecated.is_some() => {
+                return Err(format!("The positional argument '{}' cannot be deprecated", name));
+            }
+            SpecKind::Positional => Arg::positional(name).into(),
+            SpecKind::OptionalTrail => Arg::optional_trail(name).into(),
+            SpecKind::RequiredTrail => Arg::required_trail(name).into(),
+            SpecKind::Single => opt.single().into(),
+            SpecKind::ZeroOrMore => opt.zero_or_more().into(),
+            SpecKind::OneOrMore => opt.one_or_more().into(),
+            SpecKind::Switch => opt.switch().into(),
+            SpecKind::Count => opt.count().into(),
+            SpecKind::Interrupt => opt.interrupt().into(),
+            SpecKind::Passalong => opt.passalong().into(),
+        };
+        // The settings are checked against the kind by `Parser::define`
+        if let Some(ref param) = self.param {
+            arg = arg::internal_set_param(arg, param);
+        }
+        if let Some(ref help) = self.help {
+            arg = arg.set_help(help);
+        }
+        if let Some(ref note) = self.deprecated {
+            arg = arg::internal_set_deprecated(arg, note);
+        }
+        if !self.choices.is_empty() {
+            arg = arg::internal_set_owned_choices(arg, &self.choices);
+        }
+        Ok(arg)
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser with the arguments described by the given document.
+    /// Errors like `define` if the arguments overlap.
+    pub fn from_spec(spec: &'a SpecDocument) -> Result<Parser<'a>, String> {
+        let mut parser = Parser::new();
+        for arg in &spec.args {
+            parser.define_single(arg.to_arg()?)?;
+        }
+        if let Some(ref usage) = spec.usage {
+            parser.set_usage(usage);
+        }
+        Ok(parser)
+    }
+}
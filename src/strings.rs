@@ -0,0 +1,178 @@
+/// The texts used when generating help messages, errors and warnings.
+///
+/// Override the fields to translate the output of a tool (eg. with
+/// `Strings { interrupts: "Afbrydelser:", ..Strings::default() }`), and give
+/// the result to `Parser::set_strings`.
+///
+/// The message templates may refer to the details of the error or warning
+/// with placeholders like `{arg}`, which are listed on each field.
+#[derive(Debug, Clone, Copy)]
+pub struct Strings<'a> {
+    /// The title of the required arguments section of the help.
+    pub required_arguments: &'a str,
+    /// The title of the interrupts section of the help.
+    pub interrupts: &'a str,
+    /// The title of the optional arguments section of the help.
+    pub optional_arguments: &'a str,
+    /// The title of the pass-along section of the help.
+    pub pass_alongs: &'a str,
+    /// The note on the integers accepted by an argument in the help.
+    ///
+    /// Placeholders: `{range}`.
+    pub range_note: &'a str,
+    /// The note on required optional arguments in the help.
+    pub required_note: &'a str,
+    /// The note on the default value of an argument in the help.
+    ///
+    /// Placeholders: `{value}`.
+    pub default_note: &'a str,
+    /// The note on the environment variable of an argument in the help.
+    ///
+    /// Placeholders: `{var}`.
+    pub env_note: &'a str,
+    /// The note on the choices of an argument in the help.
+    ///
+    /// Placeholders: `{choices}`.
+    pub choices_note: &'a str,
+    /// The label in front of the synopsis of `generate_usage`.
+    pub usage_label: &'a str,
+    /// The label in front of reported errors.
+    pub error_label: &'a str,
+    /// The pointer to the help flag after a reported error.
+    ///
+    /// Placeholders: `{flag}`.
+    pub help_hint: &'a str,
+    /// Placeholders: `{arg}`.
+    pub unknown_optional_argument: &'a str,
+    /// Placeholders: `{arg}`, `{invalid}`.
+    pub grouped_non_switch: &'a str,
+    /// Placeholders: `{arg}`.
+    pub missing_parameter: &'a str,
+    /// Placeholders: `{arg}`.
+    pub missing_positional_argument: &'a str,
+    /// Placeholders: `{arg}`.
+    pub missing_option: &'a str,
+    /// Placeholders: `{arg}`, `{other}`.
+    pub missing_option_if: &'a str,
+    /// Placeholders: `{arg}`, `{other}`, `{value}`.
+    pub missing_option_if_value: &'a str,
+    /// Placeholders: `{arg}`, `{other}`.
+    pub missing_option_unless: &'a str,
+    /// Placeholders: `{group}`, `{members}`.
+    pub missing_group: &'a str,
+    /// Placeholders: `{group}`, `{arg}`, `{other}`.
+    pub conflicting_group: &'a str,
+    /// Placeholders: `{arg}`.
+    pub duplicate_argument: &'a str,
+    /// Placeholders: `{arg}`.
+    pub missing_trail: &'a str,
+    /// Placeholders: `{arg}`.
+    pub unexpected_argument: &'a str,
+    /// The suggestion after an unknown or unexpected argument.
+    ///
+    /// Placeholders: `{flag}`.
+    pub did_you_mean: &'a str,
+    /// Placeholders: `{arg}`, `{value}`, `{choices}`.
+    pub invalid_choice: &'a str,
+    /// Placeholders: `{arg}`, `{value}`, `{reason}`.
+    pub invalid_value: &'a str,
+    /// The reason for an invalid value outside the range of an argument.
+    ///
+    /// Placeholders: `{range}`.
+    pub out_of_range: &'a str,
+    /// The reason for an invalid value that does not match the pattern of an
+    /// argument.
+    ///
+    /// Placeholders: `{regex}`.
+    pub no_match: &'a str,
+    /// The reason for an invalid value of a digit flag (like `-9`).
+    pub not_a_digit: &'a str,
+    /// Placeholders: `{arg}`, `{note}`.
+    pub deprecated_flag: &'a str,
+    /// Placeholders: `{value}`, `{flag}`.
+    pub suspicious_value: &'a str,
+    /// Placeholders: `{arg}`, `{previous}`.
+    pub overridden_value: &'a str,
+}
+
+impl<'a> Strings<'a> {
+    /// Returns the built-in english texts.
+    pub fn english() -> Strings<'static> {
+        Strings {
+            required_arguments: "Required arguments:",
+            interrupts: "Interrupts:",
+            optional_arguments: "Optional arguments:",
+            pass_alongs: "Pass-alongs:",
+            range_note: "[range: {range}]",
+            required_note: "[required]",
+            default_note: "[default: {value}]",
+            env_note: "[env: {var}]",
+            choices_note: "[possible: {choices}]",
+            usage_label: "Usage:",
+            error_label: "error",
+            help_hint: "For more information, try '{flag}'.",
+            unknown_optional_argument: "unknown optional argument '{arg}'",
+            grouped_non_switch: "the flag '{invalid}' in '{arg}' cannot be grouped with other \
+                                 flags",
+            missing_parameter: "missing parameter for '{arg}'",
+            missing_positional_argument: "missing the positional argument '{arg}'",
+            missing_option: "the option '--{arg}' is required",
+            missing_option_if: "the option '--{arg}' is required when '--{other}' is given",
+            missing_option_if_value: "the option '--{arg}' is required when '{other}' is \
+                                      '{value}'",
+            missing_option_unless: "the option '--{arg}' is required unless '--{other}' is \
+                                    given",
+            missing_group: "one of the options of '{group}' is required ({members})",
+            conflicting_group: "only one of the options of '{group}' can be given, but both \
+                                '--{arg}' and '--{other}' were",
+            duplicate_argument: "the argument '{arg}' was given more than once",
+            missing_trail: "expected one or more '{arg}' arguments",
+            unexpected_argument: "unexpected argument '{arg}'",
+            did_you_mean: "(did you mean '--{flag}'?)",
+            invalid_choice: "invalid value '{value}' for '{arg}' (choose from: {choices})",
+            invalid_value: "invalid value '{value}' for '{arg}': {reason}",
+            out_of_range: "expected an integer in {range}",
+            no_match: "expected a value matching '{regex}'",
+            not_a_digit: "expected a digit",
+            deprecated_flag: "'{arg}' is deprecated: {note}",
+            suspicious_value: "the value '{value}' has the name of the flag '--{flag}', were \
+                               the dashes forgotten?",
+            overridden_value: "'{previous}' is ignored, as '--{arg}' is overridden by a later \
+                               argument",
+        }
+    }
+}
+
+impl<'a> Default for Strings<'a> {
+    fn default() -> Self {
+        Strings::english()
+    }
+}
+
+/// Replaces the `{name}` placeholders of the template with the given values.
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let key = &rest[1..end];
+            values.iter()
+                  .find(|&&(name, _)| name == key)
+                  .map(|&(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                text.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
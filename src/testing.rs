@@ -0,0 +1,233 @@
+//! Renderings of the help, usage and errors of a parser for snapshot (golden
+//! file) tests.
+//!
+//! The messages of `generate_help` and `ParseError::message` are meant for
+//! people, and may be worded or laid out better in any release. The
+//! renderings here only change with the definitions: each `SnapshotFormat`
+//! renders the same way in every later release, and a new rendering is added
+//! as a new format, which the tests opt into by naming it.
+//!
+//! ```text
+//! let help = snapshot_help(&parser, SnapshotFormat::V1);
+//! assert_eq!(help, fs::read_to_string("tests/help.txt")?);
+//! ```
+
+use std::fmt::Write;
+
+use arg::{self, Arg, ArgType};
+use parser::{Parser, ParseError};
+
+/// A version of the renderings of this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnapshotFormat {
+    /// The first rendering.
+    ///
+    /// The help lists the positional arguments and then the optional ones in
+    /// the order they were defined, each with its flags and parameter on a
+    /// line of its own, and its help text and the notes on its values
+    /// indented below. Nothing is aligned or wrapped, so a change to an
+    /// argument only changes its own lines:
+    ///
+    /// ```text
+    /// Positional arguments:
+    ///   <input>
+    ///       The file to read.
+    ///
+    /// Optional arguments:
+    ///   --verbose, -v
+    ///       Prints more details.
+    ///   --out, -o <FILE>
+    ///       default: out.txt
+    /// ```
+    ///
+    /// The usage is a synopsis like `[--help] [--verbose] --out <FILE>
+    /// <input>`, and an error is its kind and its fields, like
+    /// `missing-option arg="out"`.
+    V1,
+}
+
+/// Returns the flags of the argument with its parameter, or its parameter if
+/// it is positional (like `--out, -o <FILE>` or `<input>...`). Only the long
+/// flag is shown unless `every_flag` is set.
+fn synopsis(arg: Arg, every_flag: bool) -> String {
+    use arg::ArgType::*;
+
+    let param = match arg.param() {
+        Some(param) => param.trim_start_matches('<').trim_end_matches('>').to_owned(),
+        None if arg.option_name().is_none() => arg.name().to_owned(),
+        None if arg.name().is_empty() => "ARGS".to_owned(),
+        None => arg.name().to_uppercase(),
+    };
+    let mut flags = format!("--{}", arg.name());
+    if every_flag {
+        for alias in arg.aliases() {
+            write!(flags, ", --{}", alias).unwrap_or(());
+        }
+        if let Some(short) = arg.short() {
+            write!(flags, ", -{}", short).unwrap_or(());
+        }
+    }
+    match arg::internal_get_raw(arg) {
+        Single(_) => format!("<{}>", param),
+        OnePlus(_) => format!("<{}>...", param),
+        ZeroPlus(_) => format!("[<{}>...]", param),
+        OptSingle(_) => format!("{} <{}>", flags, param),
+        OptOnePlus(_) => format!("{} <{}>...", flags, param),
+        OptZeroPlus(_) | PassAlong(_) => format!("{} [<{}>...]", flags, param),
+        Switch(_) if arg.is_counted() => format!("{}...", flags),
+        Switch(_) | Interrupt(_) => flags,
+        Digits(_) => "-<digit>".to_owned(),
+    }
+}
+
+/// Returns the help of the arguments of the parser in the given format.
+pub fn snapshot_help(parser: &Parser, format: SnapshotFormat) -> String {
+    match format {
+        SnapshotFormat::V1 => help_v1(parser),
+    }
+}
+
+fn help_v1(parser: &Parser) -> String {
+    let (positional, optional): (Vec<Arg>, Vec<Arg>) =
+        parser.args().partition(|arg| arg.is_positional());
+    let mut sections = Vec::new();
+    for &(title, ref args) in &[("Positional arguments:", positional),
+                                ("Optional arguments:", optional)] {
+        if args.is_empty() {
+            continue;
+        }
+        let mut section = title.to_owned();
+        for &arg in args {
+            write!(section, "\n  {}", synopsis(arg, true)).unwrap_or(());
+            let mut lines: Vec<String> = arg.help().unwrap_or("")
+                                            .lines()
+                                            .filter(|line| !line.is_empty())
+                                            .map(|line| line.to_owned())
+                                            .collect();
+            if arg.option_name().is_some() && arg.is_required() {
+                lines.push("required".to_owned());
+            }
+            if let Some(value) = arg.default() {
+                lines.push(format!("default: {}", value));
+            }
+            if let Some(var) = parser.env_var(arg) {
+                lines.push(format!("env: {}", var));
+            }
+            if let Some(choices) = arg.choices() {
+                lines.push(format!("choices: {}", choices.iter().collect::<Vec<_>>().join(", ")));
+            }
+            if let Some(note) = arg.deprecated() {
+                lines.push(format!("deprecated: {}", note));
+            }
+            for line in lines {
+                write!(section, "\n      {}", line).unwrap_or(());
+            }
+        }
+        sections.push(section);
+    }
+    sections.join("\n\n")
+}
+
+/// Returns the synopsis of the arguments of the parser in the given format.
+pub fn snapshot_usage(parser: &Parser, format: SnapshotFormat) -> String {
+    match format {
+        SnapshotFormat::V1 => usage_v1(parser),
+    }
+}
+
+fn usage_v1(parser: &Parser) -> String {
+    // The positional arguments go after the optional ones, in their order
+    let mut parts: Vec<(bool, String)> = parser.args().map(|&arg| {
+        let synopsis = synopsis(arg, false);
+        match arg::internal_get_raw(arg) {
+            ArgType::Single(_) | ArgType::OnePlus(_) | ArgType::ZeroPlus(_) => (true, synopsis),
+            _ if arg.is_required() => (false, synopsis),
+            _ => (false, format!("[{}]", synopsis)),
+        }
+    }).collect();
+    parts.sort_by_key(|&(positional, _)| positional);
+    parts.into_iter().map(|(_, part)| part).collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the error in the given format.
+pub fn snapshot_error(error: &ParseError, format: SnapshotFormat) -> String {
+    match format {
+        SnapshotFormat::V1 => error_v1(error),
+    }
+}
+
+/// A field of an error, as rendered in a snapshot.
+enum Field<'a> {
+    Text(&'a str, &'a str),
+    Maybe(&'a str, Option<&'a str>),
+    List(&'a str, Vec<&'a str>),
+}
+
+/// Returns the text in double quotes, with backslashes before the quotes and
+/// backslashes in it.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn error_v1(error: &ParseError) -> String {
+    use self::Field::*;
+    use parser::ParseError::*;
+
+    let (kind, fields) = match *error {
+        UnknownOptionalArgument { arg, suggestion } => {
+            ("unknown-optional-argument", vec![Text("arg", arg), Maybe("suggestion", suggestion)])
+        }
+        GroupedNonSwitch { arg, ref invalid } => {
+            ("grouped-non-switch", vec![Text("arg", arg), Text("invalid", invalid)])
+        }
+        MissingParameter { arg } => ("missing-parameter", vec![Text("arg", arg)]),
+        MissingPositionalArgument { arg } => {
+            ("missing-positional-argument", vec![Text("arg", arg)])
+        }
+        MissingOption { arg } => ("missing-option", vec![Text("arg", arg)]),
+        MissingOptionIf { arg, other } => {
+            ("missing-option-if", vec![Text("arg", arg), Text("other", other)])
+        }
+        MissingOptionIfValue { arg, other, value } => {
+            ("missing-option-if-value",
+             vec![Text("arg", arg), Text("other", other), Text("value", value)])
+        }
+        MissingOptionUnless { arg, other } => {
+            ("missing-option-unless", vec![Text("arg", arg), Text("other", other)])
+        }
+        MissingGroup { group, members } => {
+            ("missing-group", vec![Text("group", group), List("members", members.to_vec())])
+        }
+        ConflictingGroup { group, arg, other } => {
+            ("conflicting-group",
+             vec![Text("group", group), Text("arg", arg), Text("other", other)])
+        }
+        DuplicatePositionalArgument { arg } => ("duplicate-argument", vec![Text("arg", arg)]),
+        MissingTrail { arg } => ("missing-trail", vec![Text("arg", arg)]),
+        UnexpectedArgument { arg, suggestion } => {
+            ("unexpected-argument", vec![Text("arg", arg), Maybe("suggestion", suggestion)])
+        }
+        InvalidChoice { arg, value, choices } => {
+            let choices = List("choices", choices.iter().collect());
+            ("invalid-choice", vec![Text("arg", arg), Text("value", value), choices])
+        }
+        InvalidValue { arg, value, ref reason } => {
+            ("invalid-value", vec![Text("arg", arg), Text("value", value), Text("reason", reason)])
+        }
+    };
+
+    let mut text = kind.to_owned();
+    for field in fields {
+        match field {
+            Text(name, value) | Maybe(name, Some(value)) => {
+                write!(text, " {}={}", name, quoted(value)).unwrap_or(());
+            }
+            Maybe(_, None) => {}
+            List(name, values) => {
+                let values = values.iter().map(|value| quoted(value)).collect::<Vec<_>>();
+                write!(text, " {}=[{}]", name, values.join(", ")).unwrap_or(());
+            }
+        }
+    }
+    text
+}
@@ -0,0 +1,322 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use arg::Arg;
+use parsed::ParsedArgs;
+use parser::Parser;
+
+/// A type that the value of an argument can be converted to.
+///
+/// Use `set_type` on the argument builders (like `OptSingle::set_type`) to
+/// reject values that cannot be converted during the parse, and
+/// `ParsedArgs::single_as` to convert them afterwards.
+pub trait FromValue: Sized {
+    /// Converts the value, or returns why it cannot be converted.
+    fn from_value(value: &str) -> Result<Self, String>;
+}
+
+/// Checks that the value can be converted to `T`.
+pub fn validate<T: FromValue>(value: &str) -> Result<(), String> {
+    T::from_value(value).map(|_| ())
+}
+
+/// An enum that the value of an argument can be one of (e.g. `enum Mode {
+/// Fast, Slow }`).
+///
+/// Use `set_choices_of` on the argument builders (like
+/// `OptSingle::set_choices_of`) to only accept the values of the variants
+/// (which are then listed in the help message), and `ParsedArgs::single_as`
+/// to convert them. With the `derive` feature, `#[derive(Choices)]` implements it
+/// for enums of unit variants, with the names of the variants in kebab-case
+/// (`DryRun` is `dry-run`) unless renamed with `#[choice(rename = "...")]`.
+pub trait Choices: Sized {
+    /// Returns the values of the variants, in order.
+    fn variants() -> &'static [&'static str];
+
+    /// Returns the variant with the given value.
+    fn from_str(value: &str) -> Option<Self>;
+}
+
+impl<T: Choices> FromValue for T {
+    fn from_value(value: &str) -> Result<Self, String> {
+        T::from_str(value).ok_or_else(|| format!("expected one of {}", T::variants().join(", ")))
+    }
+}
+
+/// A struct that the arguments of a program are collected into, with a field
+/// for each argument.
+///
+/// With the `derive` feature, `#[derive(FromArgs)]` implements it for structs
+/// with named fields. The doc comment of a field is the help text of its
+/// argument, and the kind of argument follows from the type of the field:
+///
+/// - `bool` is a switch.
+/// - `Option<T>` is an optional single value.
+/// - `Vec<T>` is an option with one or more values, or an optional trail.
+/// - Any other `T` is a required single value, or a positional argument.
+///
+/// Fields are options named after the field in kebab-case (`dry_run` is
+/// `--dry-run`), which is changed with `#[arg(...)]`:
+///
+/// ```text
+/// #[derive(FromArgs)]
+/// struct Args {
+///     /// The file to read
+///     #[arg(positional)]
+///     input: PathBuf,
+///     /// Prints more details (repeat for even more)
+///     #[arg(short = 'v', count)]
+///     verbose: u8,
+///     #[arg(long = "out", param = "FILE", default = "-")]
+///     output: FileOrStdio,
+/// }
+/// ```
+///
+/// The settings of `#[arg(...)]` are `positional`, `short = '<char>'`,
+/// `long = "<name>"`, `count`, `zero_or_more`, `required` (for trails),
+/// `choices` (for `Choices` types), `param`, `default` and `env`. Values are
+/// checked with the `set_type` of the argument builders during the parse.
+///
+/// A field with `#[arg(flatten)]` is another `FromArgs` struct, whose
+/// arguments are added to the ones of this struct. This shares common options
+/// (like a `LoggingArgs` struct) between commands. Arguments of the same name
+/// in both structs make `parser` fail.
+pub trait FromArgs: Sized {
+    /// Returns the definitions of the arguments.
+    fn args() -> Vec<Arg<'static>>;
+
+    /// Converts the collected arguments, or returns why they cannot be
+    /// converted.
+    fn from_args(args: &ParsedArgs) -> Result<Self, String>;
+
+    /// Returns a parser with the arguments defined.
+    fn parser() -> Result<Parser<'static>, String> {
+        let mut parser = Parser::new();
+        parser.define(&Self::args())?;
+        Ok(parser)
+    }
+}
+
+/// Defines the arguments, parses the given ones and converts them with
+/// `fill`, for `parse_into!`.
+pub fn internal_parse_into<S, T, F>(argv: &[S], defs: &[Arg], fill: F) -> Result<T, String>
+    where S: AsRef<str>,
+          F: FnOnce(&ParsedArgs) -> Result<T, String>
+{
+    let mut parser = Parser::new();
+    parser.define(defs)?;
+    let args = ParsedArgs::from_parse(parser.parse(argv)).map_err(|err| err.to_string())?;
+    fill(&args)
+}
+
+/// Returns a parser with the arguments defined by `define`, for `argonaut!`.
+pub fn internal_build_parser<'a, F>(define: F) -> Result<Parser<'a>, String>
+    where F: FnOnce(&mut Parser<'a>) -> Result<(), String>
+{
+    let mut parser = Parser::new();
+    define(&mut parser)?;
+    Ok(parser)
+}
+
+/// Returns the character of a short flag written as an identifier, for
+/// `argonaut!`.
+pub fn internal_short_flag(flag: &str) -> Result<char, String> {
+    let mut chars = flag.chars();
+    match (chars.next(), chars.next()) {
+        (Some(short), None) => Ok(short),
+        _ => Err(format!("'-{}' is not a short flag", flag)),
+    }
+}
+
+/// An enum of subcommands, like `add` and `remove` in `prog add FILE`, which
+/// each have their own arguments.
+///
+/// With the `derive` feature, `#[derive(Subcommand)]` implements it for enums
+/// whose variants hold a `FromArgs` struct (or nothing, for subcommands
+/// without arguments):
+///
+/// ```text
+/// #[derive(Subcommand)]
+/// enum Command {
+///     /// Adds files to the index
+///     Add(AddArgs),
+///     /// Shows the state of the index
+///     Status,
+/// }
+///
+/// let command = Command::from_argv(&args)?;
+/// ```
+///
+/// The doc comments of the variants are the help texts, and the names are the
+/// ones of the variants in kebab-case unless renamed with `#[command(rename =
+/// "...")]`. For options before the subcommand, parse them with a parser that
+/// stops at unknown arguments (`Parser::set_stop_at_unknown`) and hand
+/// `Parse::remaining_args` to `from_argv`.
+pub trait Subcommand: Sized {
+    /// Returns the names of the subcommands with their help texts.
+    fn commands() -> &'static [(&'static str, Option<&'static str>)];
+
+    /// Returns the definitions of the arguments of the subcommand, or `None`
+    /// if there is no such subcommand.
+    fn args(command: &str) -> Option<Vec<Arg<'static>>>;
+
+    /// Converts the collected arguments of the subcommand, or returns why they
+    /// cannot be converted.
+    fn from_args(command: &str, args: &ParsedArgs) -> Result<Self, String>;
+
+    /// Returns a parser with the arguments of the subcommand defined (e.g. to
+    /// show its help message).
+    fn parser(command: &str) -> Result<Parser<'static>, String> {
+        let args = Self::args(command).ok_or_else(|| {
+            format!("unknown command '{}' (choose from: {})", command, Self::names())
+        })?;
+        let mut parser = Parser::new();
+        parser.define(&args)?;
+        Ok(parser)
+    }
+
+    /// Parses the arguments of a subcommand, starting with its name.
+    fn from_argv<S: AsRef<str>>(argv: &[S]) -> Result<Self, String> {
+        let command = match argv.first() {
+            Some(command) => command.as_ref(),
+            None => return Err(format!("a command is required (choose from: {})", Self::names())),
+        };
+        let parser = Self::parser(command)?;
+        let args = ParsedArgs::from_parse(parser.parse(&argv[1..])).map_err(|err| err.to_string())?;
+        Self::from_args(command, &args)
+    }
+
+    /// Returns the names of the subcommands, separated by commas.
+    fn names() -> String {
+        let names: Vec<&str> = Self::commands().iter().map(|&(name, _)| name).collect();
+        names.join(", ")
+    }
+
+    /// Returns the list of subcommands with their help texts, for the help
+    /// message of the program.
+    fn help() -> String {
+        let commands = Self::commands();
+        let width = commands.iter().map(|&(name, _)| name.len()).max().unwrap_or(0);
+        let mut help = String::from("Commands:\n");
+        for &(name, text) in commands {
+            match text {
+                Some(text) => help.push_str(&format!("  {:2$}    {}\n", name, text, width)),
+                None => help.push_str(&format!("  {}\n", name)),
+            }
+        }
+        help
+    }
+}
+
+/// The accepted spellings of booleans, for messages.
+pub const BOOL_SPELLINGS: &str = "true, false, yes, no, on, off, 1 or 0";
+
+/// Parses a boolean written as `true`/`false`, `yes`/`no`, `on`/`off` or
+/// `1`/`0` (in any case), like values from the environment or config files.
+pub fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("expected one of {}", BOOL_SPELLINGS)),
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_bool(value)
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &str) -> Result<Self, String> {
+        Ok(value.to_owned())
+    }
+}
+
+impl FromValue for PathBuf {
+    fn from_value(value: &str) -> Result<Self, String> {
+        Ok(PathBuf::from(value))
+    }
+}
+
+impl FromValue for OsString {
+    fn from_value(value: &str) -> Result<Self, String> {
+        Ok(OsString::from(value))
+    }
+}
+
+macro_rules! from_str_values {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &str) -> Result<Self, String> {
+                    value.parse().map_err(|err| format!("{}", err))
+                }
+            }
+        )*
+    }
+}
+
+from_str_values!(char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128,
+                 usize);
+
+/// A file path, or `-` for the standard input or output, as accepted by most
+/// filter-like tools.
+///
+/// Converting the value does not touch the file, so `set_type` does not check
+/// that it exists. It is opened by `open` or created by `create`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileOrStdio {
+    /// The value `-`.
+    Stdio,
+    /// Any other value.
+    Path(PathBuf),
+}
+
+impl FileOrStdio {
+    /// Returns whether this is the standard input or output.
+    pub fn is_stdio(&self) -> bool {
+        *self == FileOrStdio::Stdio
+    }
+
+    /// Opens the file for reading, or returns the standard input.
+    pub fn open(&self) -> io::Result<Box<dyn BufRead>> {
+        match *self {
+            FileOrStdio::Stdio => Ok(Box::new(io::stdin().lock())),
+            FileOrStdio::Path(ref path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+        }
+    }
+
+    /// Creates (or truncates) the file for writing, or returns the standard
+    /// output.
+    ///
+    /// The file is buffered, so flush the writer to see write errors.
+    pub fn create(&self) -> io::Result<Box<dyn Write>> {
+        match *self {
+            FileOrStdio::Stdio => Ok(Box::new(io::stdout())),
+            FileOrStdio::Path(ref path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        }
+    }
+}
+
+impl fmt::Display for FileOrStdio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileOrStdio::Stdio => f.write_str("-"),
+            FileOrStdio::Path(ref path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl FromValue for FileOrStdio {
+    fn from_value(value: &str) -> Result<Self, String> {
+        match value {
+            "-" => Ok(FileOrStdio::Stdio),
+            "" => Err("expected a file path, or '-' for the standard input or output".to_owned()),
+            path => Ok(FileOrStdio::Path(PathBuf::from(path))),
+        }
+    }
+}
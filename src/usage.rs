@@ -0,0 +1,332 @@
+use arg::Arg;
+use parser::Parser;
+
+/// An option described in the `Options:` section of a usage text.
+struct OptionDesc<'a> {
+    long: Option<&'a str>,
+    short: Option<(&'a str, char)>,
+    param: Option<&'a str>,
+    help: Option<&'a str>,
+    multiple: bool,
+}
+
+impl<'a> OptionDesc<'a> {
+    /// Creates an option without flags or a parameter.
+    fn new() -> OptionDesc<'a> {
+        OptionDesc {
+            long: None,
+            short: None,
+            param: None,
+            help: None,
+            multiple: false,
+        }
+    }
+
+    /// Reads a line like `-o FILE, --output=FILE  Help text.`, or returns
+    /// `None` if the line does not describe an option.
+    fn parse(line: &'a str) -> Result<Option<OptionDesc<'a>>, String> {
+        let line = line.trim();
+        if !line.starts_with('-') {
+            return Ok(None);
+        }
+        // The flags are separated from the help text by at least two spaces
+        let (flags, help) = match line.find("  ") {
+            Some(index) => (&line[..index], Some(line[index..].trim())),
+            None => (line, None),
+        };
+        let mut desc = OptionDesc::new();
+        desc.help = help;
+        for word in flags.split([',', ' ']).filter(|word| !word.is_empty()) {
+            let (flag, attached) = split_param(word);
+            if attached.is_some() {
+                desc.param = attached;
+            }
+            if let Some(name) = flag.strip_prefix("--") {
+                desc.long = Some(name);
+            } else if flag.starts_with('-') {
+                desc.short = Some(short_flag(flag)?);
+            } else if let Some(param) = positional_name(flag) {
+                desc.param = Some(param);
+            } else {
+                return Err(format!("Invalid option description '{}'", line));
+            }
+        }
+        Ok(Some(desc))
+    }
+
+    /// Returns whether the option has the given flag (like `-o` or `--out`).
+    fn has_flag(&self, flag: &str) -> bool {
+        match flag.strip_prefix("--") {
+            Some(name) => self.long == Some(name),
+            None => self.short.map(|(name, _)| name) == flag.get(1..),
+        }
+    }
+
+    /// Returns the definition of the option.
+    fn to_arg(&self) -> Arg<'a> {
+        let opt = match (self.long, self.short) {
+            (Some(long), Some((_, ch))) => Arg::named_and_short(long, ch),
+            (Some(long), None) => Arg::named(long),
+            (None, Some((name, ch))) => Arg::named_and_short(name, ch),
+            (None, None) => unreachable!(),
+        };
+        let mut arg: Arg = match self.param {
+            Some(param) if self.multiple => opt.one_or_more().set_param(param).into(),
+            Some(param) => opt.single().set_param(param).into(),
+            None if self.multiple => opt.count().into(),
+            None => opt.switch().into(),
+        };
+        if let Some(help) = self.help {
+            arg = arg.set_help(help);
+        }
+        arg
+    }
+}
+
+/// Returns whether the line starts the `Options:` section.
+fn is_options_header(line: &str) -> bool {
+    line.trim_start()
+        .get(..8)
+        .is_some_and(|start| start.eq_ignore_ascii_case("options:"))
+}
+
+/// Splits a flag like `--out=<file>` into the flag and the parameter name.
+fn split_param(word: &str) -> (&str, Option<&str>) {
+    match word.find('=') {
+        Some(index) => {
+            let param = &word[index + 1..];
+            (&word[..index], Some(positional_name(param).unwrap_or(param)))
+        }
+        None => (word, None),
+    }
+}
+
+/// Returns the name and character of a short flag like `-o`.
+fn short_flag(flag: &str) -> Result<(&str, char), String> {
+    let name = &flag[1..];
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok((name, ch)),
+        _ => Err(format!("Invalid short flag '{}'", flag)),
+    }
+}
+
+/// Splits the usage pattern into words, with brackets, bars and ellipses as
+/// words of their own.
+fn tokenize(pattern: &str) -> Vec<&str> {
+    const SEPARATORS: [char; 5] = ['[', ']', '(', ')', '|'];
+    let mut tokens = Vec::new();
+    for word in pattern.split_whitespace() {
+        let mut rest = word;
+        while !rest.is_empty() {
+            let end = if rest.starts_with("...") {
+                3
+            } else if rest.starts_with(SEPARATORS) {
+                1
+            } else {
+                rest.find(SEPARATORS)
+                    .into_iter()
+                    .chain(rest.find("..."))
+                    .min()
+                    .unwrap_or(rest.len())
+            };
+            tokens.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+/// Returns the name of a positional argument like `<file>` or `FILE`.
+fn positional_name(token: &str) -> Option<&str> {
+    let is_upper = |ch: char| ch.is_uppercase() || ch.is_ascii_digit() || ch == '_' || ch == '-';
+    if token.starts_with('<') && token.ends_with('>') && token.len() > 2 {
+        Some(&token[1..token.len() - 1])
+    } else if !token.starts_with('-') && token.chars().any(char::is_uppercase) &&
+              token.chars().all(is_upper) {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+/// Returns whether the token is a flag like `-v` or `--out=<file>`.
+fn is_option(token: &str) -> bool {
+    token.starts_with('-') && token.len() > 1 && token != "--"
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser from a usage text in the style of docopt.
+    ///
+    /// ```text
+    /// Usage: prog [-v] --out=<file> <input>...
+    ///
+    /// Options:
+    ///   -v, --verbose     Print more information.
+    ///   -o, --out=<file>  Where to write the output.
+    /// ```
+    ///
+    /// The first word of the pattern after `Usage:` is the program name, and
+    /// the rest is read as:
+    ///
+    /// - `<name>` or `NAME`: a positional argument, or a trail when followed
+    ///   by `...` (optional when in brackets).
+    /// - `--name`, `-n` or `-abc`: switches, which are counted when followed
+    ///   by `...`.
+    /// - `--name=<param>`: an option with a single parameter, or one or more
+    ///   parameters when followed by `...`.
+    /// - `-n|--name`: a short and long flag for the same option.
+    /// - `-- <args>...`: a pass-along of everything after `--`.
+    ///
+    /// As in docopt, a parameter given after a space (`--name <param>`)
+    /// belongs to the option only if the `Options:` section says so. The
+    /// options in that section are all defined, with their help text, and
+    /// flags on the same line are the same option. Brackets around options
+    /// and `[options]` are ignored, as options are always optional. Only a
+    /// single pattern is supported.
+    ///
+    /// Options without a long flag use their short name as the long one. The
+    /// text before `Options:` is used as the usage line of the parser.
+    pub fn from_usage(usage: &'a str) -> Result<Parser<'a>, String> {
+        let (usage_text, options_text) = match usage.lines().find(|line| is_options_header(line)) {
+            Some(line) => {
+                let start = line.as_ptr() as usize - usage.as_ptr() as usize;
+                (usage[..start].trim(), &usage[start + line.len()..])
+            }
+            None => (usage.trim(), ""),
+        };
+
+        let mut descs = Vec::new();
+        for line in options_text.lines() {
+            if let Some(desc) = OptionDesc::parse(line)? {
+                descs.push(desc);
+            }
+        }
+
+        let pattern = match usage_text.get(..6) {
+            Some(start) if start.eq_ignore_ascii_case("usage:") => &usage_text[6..],
+            _ => usage_text,
+        };
+        // Skip the program name
+        let tokens = tokenize(pattern);
+        let tokens = match tokens.split_first() {
+            Some((program, rest)) if rest.contains(program) => {
+                return Err("Only a single usage pattern is supported".into());
+            }
+            Some((_, rest)) => rest,
+            None => &[],
+        };
+
+        let mut defs = Vec::new();
+        let mut depth = 0;
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = tokens[i];
+            i += 1;
+            match token {
+                "[" => depth += 1,
+                "]" => {
+                    if depth == 0 {
+                        return Err("Unbalanced ']' in the usage".into());
+                    }
+                    depth -= 1;
+                }
+                "(" | ")" | "options" => {}
+                "--" => {
+                    let mut arg = Arg::named("").passalong();
+                    if let Some(param) = tokens.get(i).and_then(|token| positional_name(token)) {
+                        arg = arg.set_param(param);
+                        i += 1;
+                        if tokens.get(i) == Some(&"...") {
+                            i += 1;
+                        }
+                    }
+                    defs.push(arg.into());
+                }
+                _ if is_option(token) => {
+                    // Collect the alternative flags of the option (-o|--out)
+                    let mut flags = vec![split_param(token)];
+                    while tokens.get(i) == Some(&"|") &&
+                          tokens.get(i + 1).is_some_and(|token| is_option(token)) {
+                        flags.push(split_param(tokens[i + 1]));
+                        i += 2;
+                    }
+
+                    // Grouped short switches like -abc
+                    let (first, _) = flags[0];
+                    if flags.len() == 1 && !first.starts_with("--") && first.chars().count() > 2 {
+                        for (start, ch) in first.char_indices().skip(1) {
+                            let short = (&first[start..start + ch.len_utf8()], ch);
+                            if !descs.iter().any(|desc| desc.short == Some(short)) {
+                                let mut desc = OptionDesc::new();
+                                desc.short = Some(short);
+                                descs.push(desc);
+                            }
+                        }
+                        continue;
+                    }
+
+                    let known = descs.iter()
+                                     .position(|desc| flags.iter().any(|&(flag, _)| desc.has_flag(flag)));
+                    let index = known.unwrap_or_else(|| {
+                        descs.push(OptionDesc::new());
+                        descs.len() - 1
+                    });
+                    let desc = &mut descs[index];
+                    for (flag, param) in flags {
+                        if let Some(name) = flag.strip_prefix("--") {
+                            desc.long = Some(name);
+                        } else if desc.short.is_none() {
+                            desc.short = Some(short_flag(flag)?);
+                        }
+                        if param.is_some() {
+                            desc.param = param;
+                        }
+                    }
+                    // A described parameter can be given after a space
+                    if known.is_some() && desc.param.is_some() &&
+                       tokens.get(i).and_then(|token| positional_name(token)).is_some() {
+                        i += 1;
+                    }
+                    if tokens.get(i) == Some(&"...") {
+                        desc.multiple = true;
+                        i += 1;
+                    }
+                }
+                _ => {
+                    let name = match positional_name(token) {
+                        Some(name) => name,
+                        None => return Err(format!("Unsupported element '{}' in the usage", token)),
+                    };
+                    let arg: Arg = if tokens.get(i) == Some(&"...") {
+                        i += 1;
+                        if depth > 0 {
+                            Arg::optional_trail(name).into()
+                        } else {
+                            Arg::required_trail(name).into()
+                        }
+                    } else if depth > 0 {
+                        return Err(format!("The positional argument '{}' can only be optional as \
+                                            a trail ('{}...')",
+                                           name,
+                                           token));
+                    } else {
+                        Arg::positional(name).into()
+                    };
+                    defs.push(arg);
+                }
+            }
+        }
+        if depth != 0 {
+            return Err("Unbalanced '[' in the usage".into());
+        }
+
+        let mut parser = Parser::new();
+        for desc in &descs {
+            parser.define_single(desc.to_arg())?;
+        }
+        parser.define(&defs)?;
+        parser.set_usage(usage_text);
+        Ok(parser)
+    }
+}
@@ -1,269 +1,893 @@
 use std::borrow::Cow;
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
 
-use arg;
-use parser::{Parser, internal_get_definitions};
+use arg::{self, Arg};
+use color::{self, Style};
+use common::OptName;
+use parser::{Parser, ParseError};
+use strings;
 
-fn align_lines(lines: &mut Vec<Vec<String>>, padding: Option<char>) {
-    let mut widths = Vec::new();
-    // Calculate widths
-    for line in lines.iter() {
-        while line.len() > widths.len() {
-            widths.push(0);
+/// The order of the arguments within each section of the help message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HelpOrder {
+    /// The order the arguments were defined in.
+    #[default]
+    Declaration,
+    /// Sorted by name.
+    Alphabetical,
+}
+
+/// How the arguments of a section of the help message are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HelpLayout {
+    /// The flags in aligned columns, with the help texts in a column of their
+    /// own after them.
+    #[default]
+    Columns,
+    /// The help text right after the flags of each argument, on one line
+    /// unless it has to be wrapped.
+    Compact,
+    /// The flags of each argument on a line of their own, with the help text
+    /// as an indented paragraph below them (like man pages).
+    Paragraphs,
+    /// Each argument on a single line in the form `flags: help text`,
+    /// without indentation, alignment, separators or wrapping (for screen
+    /// readers and other tools).
+    Plain,
+}
+
+/// The layout of the help message.
+///
+/// Override the fields to match the look of other tools (eg. with
+/// `HelpStyle { separator: ",", ..HelpStyle::default() }`), and give the
+/// result to `Parser::set_help_style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelpStyle<'a> {
+    /// The text in front of each argument.
+    pub indent: &'a str,
+    /// The text between the flags of an argument and its help text.
+    pub gutter: &'a str,
+    /// The text between the long and the short flag of an argument.
+    pub separator: &'a str,
+    /// The character used to align the flags and the help texts.
+    pub padding: char,
+    /// How the flags and the help texts are arranged.
+    pub layout: HelpLayout,
+}
+
+impl<'a> Default for HelpStyle<'a> {
+    fn default() -> Self {
+        HelpStyle {
+            indent: "  ",
+            gutter: "   ",
+            separator: "|",
+            padding: ' ',
+            layout: HelpLayout::Columns,
         }
-        for (i, item) in line.iter().enumerate() {
-            if item.len() > widths[i] {
-                widths[i] = item.len();
-            }
+    }
+}
+
+/// Sorts a section of the help by the display order of the arguments, and
+/// then by the order of the parser.
+fn sort_section(section: &mut [usize], args: &[Arg], order: HelpOrder) {
+    // The sort is stable, so the declaration order is kept for equal keys
+    section.sort_by_key(|&i| {
+        let name = match order {
+            HelpOrder::Declaration => "",
+            HelpOrder::Alphabetical => args[i].name(),
+        };
+        (args[i].display_order().unwrap_or(u32::MAX), name)
+    });
+}
+
+/// Returns the name shown for the values of the argument: its parameter name
+/// if set, and otherwise its name (in uppercase for optional arguments).
+fn param_name(arg: Arg) -> Cow<str> {
+    match arg.param() {
+        // The brackets are added where needed
+        Some(param) => Cow::Borrowed(param.trim_start_matches('<').trim_end_matches('>')),
+        None if arg.option_name().is_none() => Cow::Borrowed(arg.name()),
+        // The pass-along after a bare '--'
+        None if arg.name().is_empty() => Cow::Borrowed("ARGS"),
+        None => Cow::Owned(arg.name().to_uppercase()),
+    }
+}
+
+/// Returns the long flag of the argument followed by its visible aliases (like
+/// `--color, --colour`), with the prefix after the dashes of each.
+fn long_flags(arg: Arg, prefix: &str, long: &str) -> String {
+    let mut flags = format!("--{}{}", prefix, long);
+    for alias in arg.aliases() {
+        flags.push_str(&format!(", --{}{}", prefix, alias));
+    }
+    flags
+}
+
+/// Returns the help text of the argument, followed by the notes on its
+/// values.
+fn help_text(arg: Arg, parser: &Parser) -> String {
+    let strings = parser.strings();
+    let mut notes = Vec::new();
+    if arg.is_required() {
+        notes.push(strings.required_note.to_owned());
+    }
+    if let Some(range) = arg.value_range() {
+        let range = arg::internal_describe_range(&range);
+        notes.push(strings::fill(strings.range_note, &[("range", &range)]));
+    }
+    if let Some(value) = arg.default() {
+        notes.push(strings::fill(strings.default_note, &[("value", value)]));
+    }
+    if let Some(var) = parser.env_var(arg) {
+        notes.push(strings::fill(strings.env_note, &[("var", &var)]));
+    }
+    if let Some(choices) = arg.choices() {
+        let choices = choices.iter().collect::<Vec<_>>().join(", ");
+        notes.push(strings::fill(strings.choices_note, &[("choices", &choices)]));
+    }
+
+    let mut text = arg.help().unwrap_or("").to_owned();
+    // The notes go on a line of their own after help texts of several lines
+    let separator = if text.contains('\n') { '\n' } else { ' ' };
+    for note in notes {
+        if !text.is_empty() {
+            text.push(separator);
         }
+        text.push_str(&note);
     }
+    text
+}
 
-    let padding = padding.unwrap_or(' ');
-    // Pad the lines
-    for line in lines.iter_mut() {
-        if line.is_empty() {
-            continue;
+/// The narrowest the help texts are wrapped to, however wide the flags are.
+const MIN_HELP_WIDTH: usize = 20;
+
+/// Returns the width to wrap the help message to: the one set on the parser,
+/// or the width of the terminal, or the `COLUMNS` environment variable.
+fn help_width(parser: &Parser) -> Option<usize> {
+    if let Some(width) = parser.help_width() {
+        return Some(width);
+    }
+    #[cfg(feature = "terminal_size")]
+    {
+        if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+            return Some(usize::from(width));
         }
-        for (i, item) in line.iter_mut().enumerate() {
-            let target_width = widths[i];
-            while item.len() < target_width {
-                item.push(padding);
-            }
+    }
+    env::var("COLUMNS").ok().and_then(|columns| columns.trim().parse().ok())
+}
+
+/// Splits the line into lines of at most the given width (in chars) at its
+/// spaces, keeping its indentation on each of them. Longer words are kept
+/// whole.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let text = line.trim_start();
+    let indent = &line[..line.len() - text.len()];
+    let mut lines = Vec::new();
+    let mut current = indent.to_owned();
+    for word in text.split_whitespace() {
+        let length = current.chars().count();
+        if length > indent.len() && length + 1 + word.chars().count() > width {
+            lines.push(current);
+            current = indent.to_owned();
+        }
+        if current.len() > indent.len() {
+            current.push(' ');
         }
+        current.push_str(word);
     }
+    lines.push(current);
+    lines
 }
 
-/// Generates a help message for the tool based on the given list of arguments,
-/// their parameter name (if relevant), and their help string.
-pub fn generate_help(parser: &Parser) -> String {
+/// The flags of an argument in the help message, in the cells that are
+/// aligned with the ones of the other arguments of its section: the long
+/// flags (or the values of a positional argument), and the short flag with
+/// its parameter.
+struct Flags {
+    long: String,
+    short: Option<String>,
+    param: Option<String>,
+}
+
+impl Flags {
+    /// Returns the cells of the flags, with the separator before the short
+    /// flag. The missing cells are at the end.
+    fn cells<'s>(&'s self, separator: &'s str) -> [Option<&'s str>; 4] {
+        [Some(&self.long),
+         self.short.as_ref().map(|_| separator),
+         self.short.as_deref(),
+         self.param.as_deref()]
+    }
+
+    /// Returns the cells separated by spaces, without alignment.
+    fn joined(&self, separator: &str) -> String {
+        self.cells(separator).iter().flatten().cloned().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Returns the flags of the argument as shown in the help message.
+fn help_flags(arg: Arg, parser: &Parser) -> Flags {
     use arg::ArgType::*;
     use common::OptName::*;
 
-    let args = internal_get_definitions(parser);
-    let mut help_message = String::new();
-
-    let mut required = Vec::new();
-    let mut interrupting = Vec::new();
-    let mut passing = Vec::new();
-    let mut optional = Vec::new();
-    for (i, &arg) in args.iter().enumerate() {
-        let argtype = arg::internal_get_raw(arg);
-        match argtype {
-            Single(_) | ZeroPlus(_) | OnePlus(_) => {
-                required.push((i, argtype));
-            }
-            Interrupt(_) => {
-                interrupting.push((i, argtype));
-            }
-            OptSingle(_) | OptZeroPlus(_) | OptOnePlus(_) | Switch(_) => {
-                optional.push((i, argtype));
-            }
-            PassAlong(_) => {
-                passing.push((i, argtype));
-            }
+    let param = param_name(arg);
+    // Required parameters are in angle brackets, and optional ones in square
+    // brackets
+    let (name, prefix, param) = match arg::internal_get_raw(arg) {
+        Single(_) | OnePlus(_) | ZeroPlus(_) => {
+            let long = match arg::internal_get_raw(arg) {
+                Single(_) => param.into_owned(),
+                OnePlus(_) => format!("{}...", param),
+                _ => format!("[{}...]", param),
+            };
+            return Flags { long, short: None, param: None };
+        }
+        Digits(name) => {
+            return Flags {
+                long: long_flags(arg, "", name.name()),
+                short: Some("-<digit>".to_owned()),
+                param: Some(format!("<{}>", param)),
+            };
+        }
+        OptSingle(name) => (name, "", Some(format!("<{}>", param))),
+        OptOnePlus(name) => (name, "", Some(format!("<{}>...", param))),
+        OptZeroPlus(name) | PassAlong(name) => (name, "", Some(format!("[<{}>...]", param))),
+        // Switches that can be negated are shown as `--[no-]name`
+        Switch(name) if parser.negates_switches() && !arg.is_counted() => (name, "[no-]", None),
+        Switch(name) | Interrupt(name) => (name, "", None),
+    };
+    match name {
+        Normal(long) => {
+            let long = match param {
+                Some(param) => format!("{} {}", long_flags(arg, prefix, long), param),
+                None => long_flags(arg, prefix, long),
+            };
+            Flags { long, short: None, param: None }
+        }
+        NormalAndShort(long, short) => {
+            Flags { long: long_flags(arg, prefix, long), short: Some(format!("-{}", short)), param }
         }
     }
+}
 
-    if !required.is_empty() {
-        help_message.push_str("Required arguments:\n");
+/// The widths (in chars) of the cells of the flags in a help section.
+#[derive(Debug, Default)]
+struct Widths {
+    cells: [usize; 4],
+    count: usize,
+}
 
-        let mut lines = Vec::new();
-        let mut help_texts = Vec::new();
-        for (i, argtype) in required {
-            match argtype {
-                Single(name) => {
-                    lines.push(vec![name.to_owned()]);
-                }
-                OnePlus(name) => {
-                    lines.push(vec![format!("{0} [{0}, ..]", name)]);
-                }
-                ZeroPlus(name) => {
-                    lines.push(vec![format!("[{}, ..]", name)]);
-                }
-                _ => unreachable!(),
-            }
-            help_texts.push(args[i].help().unwrap_or(""));
-        }
-        align_lines(&mut lines, None);
-        for (i, line) in lines.iter().enumerate() {
-            help_message.push_str("  ");
-            for part in line {
-                help_message.push_str(part);
-                help_message.push(' ');
-            }
-            help_message.push_str("   ");
-            help_message.push_str(help_texts[i]);
-            help_message.push_str("\n");
+impl Widths {
+    /// Widens the cells to fit the flags.
+    fn fit(&mut self, flags: &Flags, separator: &str) {
+        for (i, cell) in flags.cells(separator).iter().flatten().enumerate() {
+            self.cells[i] = self.cells[i].max(cell.chars().count());
+            self.count = self.count.max(i + 1);
         }
     }
 
-    if !interrupting.is_empty() {
-        if !help_message.is_empty() {
-            help_message.push_str("\n");
+    /// Returns the width of the widest flags, with a space after each cell.
+    fn total(&self) -> usize {
+        self.cells[..self.count].iter().map(|width| width + 1).sum()
+    }
+}
+
+/// Writes the row of an argument in the column layout: its flags in cells as
+/// wide as the ones of the section, followed by its help text.
+///
+/// The lines of a help text after the first are indented to the column of
+/// the help texts, so that paragraphs and lists keep their layout. With a
+/// width, the help texts are wrapped to fit in it.
+fn write_column_row<W: fmt::Write>(out: &mut HelpWriter<W>,
+                                   flags: &Flags,
+                                   help: &str,
+                                   style: &HelpStyle,
+                                   widths: &Widths,
+                                   wrap_width: Option<usize>)
+                                   -> fmt::Result {
+    let column = style.indent.chars().count() + widths.total() + style.gutter.chars().count();
+    let available = wrap_width.map(|total| total.saturating_sub(column).max(MIN_HELP_WIDTH));
+    let mut help_lines = help.lines().flat_map(|line| match available {
+        Some(available) => wrap(line, available),
+        None => vec![line.to_owned()],
+    });
+    let first = help_lines.next().filter(|line| !line.trim().is_empty());
+
+    out.start_line()?;
+    out.text(style.indent)?;
+    let cells = flags.cells(style.separator);
+    let count = cells.iter().flatten().count();
+    let mut written = 0;
+    for (i, cell) in cells.iter().flatten().enumerate() {
+        out.text(cell)?;
+        // Without a help text, nothing is padded after the last cell
+        if first.is_none() && i + 1 == count {
+            break;
         }
-        help_message.push_str("Interrupts:\n");
-        let mut lines = Vec::new();
-        let mut help_texts = Vec::new();
-        for (i, argtype) in interrupting {
-            match argtype {
-                Interrupt(Normal(long)) => {
-                    lines.push(vec![format!("--{}", long)]);
-                }
-                Interrupt(NormalAndShort(long, short)) => {
-                    lines.push(vec![format!("--{}", long), "|".to_owned(), format!("-{}", short)]);
-                }
-                _ => unreachable!(),
-            };
-            help_texts.push(args[i].help().unwrap_or(""));
+        out.pad(style.padding, widths.cells[i] - cell.chars().count())?;
+        out.text(" ")?;
+        written += widths.cells[i] + 1;
+    }
+    if let Some(line) = first {
+        out.pad(style.padding, widths.total() - written)?;
+        out.text(style.gutter)?;
+        out.text(line.trim_end())?;
+    }
+
+    let indent = " ".repeat(column);
+    for line in help_lines {
+        // Keep empty lines between paragraphs without trailing spaces
+        if line.trim().is_empty() {
+            out.line("")?;
+        } else {
+            out.line(&format!("{}{}", indent, line.trim_end()))?;
         }
+    }
+    Ok(())
+}
 
-        align_lines(&mut lines, None);
-        let mut combined = Vec::new();
-        for (i, line) in lines.iter().enumerate() {
-            let mut text = String::new();
-            for part in line {
-                text.push_str(part);
-                text.push(' ');
+/// Writes the row of an argument in the compact layout: its help text
+/// follows its flags on the same line, and the lines it is wrapped to are
+/// indented a bit deeper than the flags.
+fn write_compact_row<W: fmt::Write>(out: &mut HelpWriter<W>,
+                                    flags: &Flags,
+                                    help: &str,
+                                    style: &HelpStyle,
+                                    wrap_width: Option<usize>)
+                                    -> fmt::Result {
+    let indent = style.indent.repeat(2);
+    let mut current = format!("{}{}", style.indent, flags.joined(style.separator));
+    let mut first = true;
+    for paragraph in help.lines() {
+        if !first {
+            out.line(current.trim_end())?;
+            current = indent.clone();
+        }
+        let start = if first { style.gutter } else { "" };
+        first = false;
+        for (i, word) in paragraph.split_whitespace().enumerate() {
+            let separator = if i == 0 { start } else { " " };
+            let length = current.chars().count() + separator.chars().count();
+            let fits = wrap_width.is_none_or(|width| {
+                length + word.chars().count() <= width.max(indent.len() + MIN_HELP_WIDTH)
+            });
+            if !fits && current.len() > indent.len() {
+                out.line(&current)?;
+                current = indent.clone();
+            } else {
+                current.push_str(separator);
             }
-            combined.push(vec![text, help_texts[i].to_owned()]);
+            current.push_str(word);
         }
-        align_lines(&mut combined, None);
+    }
+    out.line(current.trim_end())
+}
 
-        for line in combined {
-            help_message.push_str("  ");
-            help_message.push_str(&line[0]);
-            help_message.push_str("   ");
-            help_message.push_str(&line[1]);
-            help_message.push_str("\n");
+/// Writes the row of an argument in the paragraph layout: its flags on a
+/// line of their own, followed by its help text indented below them.
+fn write_paragraph_row<W: fmt::Write>(out: &mut HelpWriter<W>,
+                                      flags: &Flags,
+                                      help: &str,
+                                      style: &HelpStyle,
+                                      wrap_width: Option<usize>)
+                                      -> fmt::Result {
+    let indent = format!("{}    ", style.indent);
+    out.line(&format!("{}{}", style.indent, flags.joined(style.separator)))?;
+    for line in help.lines() {
+        if line.trim().is_empty() {
+            out.line("")?;
+            continue;
+        }
+        let line = format!("{}{}", indent, line.trim_end());
+        let wrapped = match wrap_width {
+            Some(width) => wrap(&line, width.max(indent.len() + MIN_HELP_WIDTH)),
+            None => vec![line],
+        };
+        for line in wrapped {
+            out.line(&line)?;
         }
     }
+    Ok(())
+}
 
-    if !optional.is_empty() {
-        // Add a separating space
-        if !help_message.is_empty() {
-            help_message.push_str("\n");
+/// Writes the row of an argument in the plain layout: its flags separated by
+/// commas, then a colon and its help text, on one line.
+fn write_plain_row<W: fmt::Write>(out: &mut HelpWriter<W>,
+                                  flags: &Flags,
+                                  help: &str)
+                                  -> fmt::Result {
+    let (flags, params): (Vec<&str>, Vec<&str>) =
+        [Some(flags.long.as_str()), flags.short.as_deref(), flags.param.as_deref()]
+            .iter()
+            .filter_map(|&part| part)
+            .partition(|part| part.starts_with('-'));
+    let mut text = flags.join(", ");
+    for param in params {
+        if !text.is_empty() {
+            text.push(' ');
         }
-        help_message.push_str("Optional arguments:\n");
+        text.push_str(param);
+    }
+    let help = help.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !help.is_empty() {
+        text.push_str(": ");
+        text.push_str(&help);
+    }
+    out.line(&text)
+}
 
-        let mut lines = Vec::new();
-        let mut help_texts = Vec::new();
-        for (i, argtype) in optional {
-            let param = match args[i].param() {
-                Some(param) => Cow::Borrowed(param),
-                None => Cow::Owned(args[i].name().to_uppercase()),
-            };
-            match argtype {
-                OptSingle(Normal(long)) => {
-                    lines.push(vec![format!("--{}{}", long, param)]);
-                }
-                OptSingle(NormalAndShort(long, short)) => {
-                    lines.push(vec![format!("--{}", long),
-                                    "|".to_owned(),
-                                    format!("-{}", short),
-                                    param.into_owned()]);
-                }
-                OptZeroPlus(Normal(long)) => {
-                    lines.push(vec![format!("--{}[{}, ..]", long, param)]);
-                }
-                OptZeroPlus(NormalAndShort(long, short)) => {
-                    lines.push(vec![format!("--{}", long),
-                                    "|".to_owned(),
-                                    format!("-{}", short),
-                                    format!("[{}, ..]", param)]);
-                }
-                OptOnePlus(Normal(long)) => {
-                    lines.push(vec![
-                        format!("--{0} {1} [{1}, ..]", long, param),
-                    ]);
-                }
-                OptOnePlus(NormalAndShort(long, short)) => {
-                    lines.push(vec![format!("--{}", long),
-                                    "|".to_owned(),
-                                    format!("-{}", short),
-                                    format!("{0} [{0}, ..]", param)]);
-                }
-                Switch(Normal(long)) => {
-                    lines.push(vec![format!("--{}", long)]);
-                }
-                Switch(NormalAndShort(long, short)) => {
-                    lines.push(vec![format!("--{}", long), "|".to_owned(), format!("-{}", short)]);
-                }
-                _ => unreachable!(),
-            };
-            help_texts.push(args[i].help().unwrap_or(""));
-        }
+/// Writes the lines of a help message, with newlines between them.
+struct HelpWriter<'w, W: 'w> {
+    out: &'w mut W,
+    empty: bool,
+}
 
-        align_lines(&mut lines, None);
-        let mut combined = Vec::new();
-        for (i, line) in lines.iter().enumerate() {
-            let mut text = String::new();
-            for part in line {
-                text.push_str(part);
-                text.push(' ');
-            }
-            combined.push(vec![text, help_texts[i].to_owned()]);
+impl<'w, W: fmt::Write> HelpWriter<'w, W> {
+    fn line(&mut self, text: &str) -> fmt::Result {
+        self.start_line()?;
+        self.text(text)
+    }
+
+    /// Starts a line, whose text is then written in pieces with `text` and
+    /// `pad`.
+    fn start_line(&mut self) -> fmt::Result {
+        if !self.empty {
+            self.out.write_char('\n')?;
         }
-        align_lines(&mut combined, None);
+        self.empty = false;
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) -> fmt::Result {
+        self.out.write_str(text)
+    }
 
-        for line in combined {
-            help_message.push_str("  ");
-            help_message.push_str(&line[0]);
-            help_message.push_str("   ");
-            help_message.push_str(&line[1]);
-            help_message.push_str("\n");
+    /// Writes the padding character the given number of times.
+    fn pad(&mut self, padding: char, count: usize) -> fmt::Result {
+        for _ in 0..count {
+            self.out.write_char(padding)?;
         }
+        Ok(())
     }
 
-    if !passing.is_empty() {
-        // Add a separating space
-        if !help_message.is_empty() {
-            help_message.push_str("\n");
+    /// Writes the title of a section, after an empty line if needed.
+    fn title(&mut self, text: &str) -> fmt::Result {
+        if !self.empty {
+            self.line("")?;
         }
-        help_message.push_str("Pass-alongs:\n");
+        self.line(text)
+    }
+}
+
+/// Writes formatted text into an `io::Write`, keeping the first error.
+struct IoWriter<'w, W: 'w> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+impl<'w, W: io::Write> fmt::Write for IoWriter<'w, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.inner.write_all(text.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Generates a help message for the tool based on the given list of arguments,
+/// their parameter name (if relevant), and their help string.
+///
+/// The section titles are colored according to `Parser::set_color`, as if
+/// the help is printed to stdout.
+pub fn generate_help(parser: &Parser) -> String {
+    let mut help_message = String::new();
+    // Writing into a string cannot fail
+    let _ = write_help(parser, &mut help_message);
+    help_message
+}
+
+/// Writes the help message of `generate_help` into a text buffer, without a
+/// trailing newline.
+pub fn write_help<W: fmt::Write>(parser: &Parser, out: &mut W) -> fmt::Result {
+    write_help_of(parser, out, &|_| true)
+}
+
+/// Generates the help message of the arguments whose name or help text
+/// matches the pattern, for finding an argument among many (like
+/// `prog --help color`).
+///
+/// The pattern is matched anywhere in the text, ignoring case. It may use
+/// the wildcards `*` (any text) and `?` (any character), as in `no-*` or
+/// `c?lor`. Sections without a matching argument are left out, so the help
+/// is empty if nothing matches.
+pub fn generate_help_filtered(parser: &Parser, pattern: &str) -> String {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let matches = |arg: Arg| {
+        contains_pattern(arg.name(), &pattern) ||
+        arg.help().is_some_and(|help| contains_pattern(help, &pattern))
+    };
+    let mut help_message = String::new();
+    // Writing into a string cannot fail
+    let _ = write_help_of(parser, &mut help_message, &matches);
+    help_message
+}
 
-        let mut lines = Vec::new();
-        let mut help_texts = Vec::new();
-        for (i, argtype) in passing {
-            let param = match args[i].param() {
-                Some(param) => Cow::Borrowed(param),
-                None => Cow::Owned(args[i].name().to_uppercase()),
+/// Returns whether the lowercase pattern, with the wildcards `*` and `?`,
+/// matches a part of the text, ignoring case.
+fn contains_pattern(text: &str, pattern: &[char]) -> bool {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    // matched[j] tells whether the first j chars of the pattern match a part
+    // of the text that ends at the current char
+    let mut matched = vec![false; pattern.len() + 1];
+    matched[0] = true;
+    for j in 1..=pattern.len() {
+        matched[j] = matched[j - 1] && pattern[j - 1] == '*';
+    }
+    if matched[pattern.len()] {
+        return true;
+    }
+    for &ch in &text {
+        let mut next = vec![false; pattern.len() + 1];
+        // A match may start anywhere
+        next[0] = true;
+        for j in 1..=pattern.len() {
+            next[j] = match pattern[j - 1] {
+                '*' => next[j - 1] || matched[j],
+                '?' => matched[j - 1],
+                other => matched[j - 1] && other == ch,
             };
-            match argtype {
-                PassAlong(Normal(long)) => {
-                    lines.push(vec![format!("--{}{}...", long, param)]);
+        }
+        if next[pattern.len()] {
+            return true;
+        }
+        matched = next;
+    }
+    false
+}
+
+/// Writes the help message of the arguments that the filter keeps.
+fn write_help_of<W: fmt::Write>(parser: &Parser,
+                                out: &mut W,
+                                keep: &dyn Fn(Arg) -> bool)
+                                -> fmt::Result {
+    use arg::ArgType::*;
+
+    let args = parser.args().as_slice();
+    let strings = parser.strings();
+    let style = parser.help_style();
+    let width = help_width(parser);
+    let use_color = parser.color().for_stdout();
+    let mut out = HelpWriter { out, empty: true };
+
+    // The arguments of each section, and the widths of their flags
+    let mut sections = [(strings.required_arguments, Vec::new(), Widths::default()),
+                        (strings.interrupts, Vec::new(), Widths::default()),
+                        (strings.optional_arguments, Vec::new(), Widths::default()),
+                        (strings.pass_alongs, Vec::new(), Widths::default())];
+    for (i, &arg) in args.iter().enumerate().filter(|&(_, &arg)| keep(arg)) {
+        let section = match arg::internal_get_raw(arg) {
+            Single(_) | ZeroPlus(_) | OnePlus(_) => 0,
+            Interrupt(_) => 1,
+            OptSingle(_) | OptZeroPlus(_) | OptOnePlus(_) | Switch(_) | Digits(_) => 2,
+            PassAlong(_) => 3,
+        };
+        let (_, ref mut members, ref mut widths) = sections[section];
+        members.push(i);
+        widths.fit(&help_flags(arg, parser), style.separator);
+    }
+
+    for &mut (title, ref mut members, ref widths) in &mut sections {
+        if members.is_empty() {
+            continue;
+        }
+        sort_section(members, args, parser.help_order());
+        out.title(&color::paint(title, Style::Emphasis, use_color))?;
+        for (n, &i) in members.iter().enumerate() {
+            let flags = help_flags(args[i], parser);
+            let help = help_text(args[i], parser);
+            match style.layout {
+                HelpLayout::Columns => {
+                    write_column_row(&mut out, &flags, &help, style, widths, width)?;
                 }
-                PassAlong(NormalAndShort(long, short)) => {
-                    lines.push(vec![format!("--{}", long),
-                                    "|".to_owned(),
-                                    format!("-{}", short),
-                                    format!("{}...", param)]);
+                HelpLayout::Compact => write_compact_row(&mut out, &flags, &help, style, width)?,
+                HelpLayout::Paragraphs => {
+                    // An empty line between the arguments
+                    if n > 0 {
+                        out.line("")?;
+                    }
+                    write_paragraph_row(&mut out, &flags, &help, style, width)?;
                 }
-                _ => unreachable!(),
+                HelpLayout::Plain => write_plain_row(&mut out, &flags, &help)?,
             }
-            help_texts.push(args[i].help().unwrap_or(""));
         }
+    }
+    Ok(())
+}
 
-        align_lines(&mut lines, None);
-        let mut combined = Vec::new();
-        for (i, line) in lines.iter().enumerate() {
-            let mut text = String::new();
-            for part in line {
-                text.push_str(part);
-                text.push(' ');
+/// Generates a one-line synopsis of the arguments of the parser for the
+/// given program, like
+/// `Usage: prog [--help | --version] [--verbose] --out <FILE> <input>...`.
+///
+/// Optional parts are in square brackets, values in angle brackets, and
+/// what can be given more than once is followed by `...`. The interrupts
+/// come first as alternatives, then the optional arguments, the positional
+/// ones and the pass-alongs.
+pub fn generate_usage(parser: &Parser, program: &str) -> String {
+    use arg::ArgType::*;
+
+    let mut interrupts = Vec::new();
+    let mut parts = Vec::new();
+    let mut positionals = Vec::new();
+    let mut passalongs = Vec::new();
+    for &arg in parser.args() {
+        let param = param_name(arg);
+        let flag = format!("--{}", arg.name());
+        let option = match arg::internal_get_raw(arg) {
+            Interrupt(_) => {
+                interrupts.push(flag);
+                continue;
+            }
+            Single(_) => {
+                positionals.push(format!("<{}>", param));
+                continue;
+            }
+            OnePlus(_) => {
+                positionals.push(format!("<{}>...", param));
+                continue;
+            }
+            ZeroPlus(_) => {
+                positionals.push(format!("[<{}>...]", param));
+                continue;
             }
-            combined.push(vec![text, help_texts[i].to_owned()]);
+            PassAlong(_) => {
+                passalongs.push(format!("[{} [<{}>...]]", flag, param));
+                continue;
+            }
+            Switch(_) if parser.negates_switches() && !arg.is_counted() => {
+                format!("--[no-]{}", arg.name())
+            }
+            Switch(_) => flag,
+            OptSingle(_) => format!("{} <{}>", flag, param),
+            OptZeroPlus(_) => format!("{} [<{}>...]", flag, param),
+            OptOnePlus(_) => format!("{} <{}>...", flag, param),
+            Digits(_) => "-<digit>".to_owned(),
+        };
+        let option = if arg.is_required() {
+            option
+        } else {
+            format!("[{}]", option)
+        };
+        if arg.is_counted() || arg.allows_duplicates() {
+            parts.push(format!("{}...", option));
+        } else {
+            parts.push(option);
         }
-        align_lines(&mut combined, None);
+    }
 
-        for line in combined {
-            help_message.push_str("  ");
-            help_message.push_str(&line[0]);
-            help_message.push_str("   ");
-            help_message.push_str(&line[1]);
-            help_message.push_str("\n");
+    let mut usage = parser.strings().usage_label.to_owned();
+    if !program.is_empty() {
+        usage.push(' ');
+        usage.push_str(program);
+    }
+    if !interrupts.is_empty() {
+        usage.push_str(&format!(" [{}]", interrupts.join(" | ")));
+    }
+    for part in parts.iter().chain(&positionals).chain(&passalongs) {
+        usage.push(' ');
+        usage.push_str(part);
+    }
+    usage
+}
+
+/// Returns the help of a single argument, looked up by its name or one of
+/// its flags (`exclude`, `--exclude` or `-x`), or `None` if there is no such
+/// argument.
+///
+/// The help has the synopsis of the argument, with its flags and parameter,
+/// followed by its help text and the notes on its values (choices, default,
+/// environment variable), wrapped like the full help message:
+///
+/// ```text
+/// --exclude | -x <ITEM>...
+///     Skips the item. [default: tmp] [possible: tmp, log]
+/// ```
+///
+/// This answers `--help <flag>` or `--help=<flag>`, whose attached value is
+/// the first argument of the interrupt.
+pub fn generate_arg_help(parser: &Parser, query: &str) -> Option<String> {
+    use arg::ArgType::*;
+    use common::OptName::*;
+
+    let query = query.trim_start_matches('-');
+    let arg = *parser.args().find(|arg| {
+        arg.name() == query ||
+        arg.aliases().iter().chain(arg.hidden_aliases()).any(|&alias| alias == query) ||
+        matches!(arg.option_name(), Some(NormalAndShort(_, short))
+                                    if query.chars().eq(Some(short)))
+    })?;
+    let param = param_name(arg);
+    let flags = match arg.option_name() {
+        Some(Normal(long)) => long_flags(arg, "", long),
+        Some(NormalAndShort(long, short)) => {
+            format!("{} {} -{}", long_flags(arg, "", long), parser.help_style().separator, short)
         }
+        None => String::new(),
+    };
+    let value = match arg::internal_get_raw(arg) {
+        Single(_) => format!("<{}>", param),
+        OnePlus(_) | OptOnePlus(_) => format!("<{}>...", param),
+        ZeroPlus(_) | OptZeroPlus(_) | PassAlong(_) => format!("[<{}>...]", param),
+        OptSingle(_) => format!("<{}>", param),
+        Digits(_) => format!("-<digit> <{}>", param),
+        Switch(_) | Interrupt(_) => String::new(),
+    };
+    let mut help = [flags, value].iter()
+                                 .filter(|part| !part.is_empty())
+                                 .cloned()
+                                 .collect::<Vec<_>>()
+                                 .join(" ");
+
+    // The help text is indented below the synopsis
+    let indent = parser.help_style().indent.repeat(2);
+    for line in help_text(arg, parser).lines() {
+        let line = format!("{}{}", indent, line);
+        let lines = match help_width(parser) {
+            Some(width) => wrap(&line, width.max(indent.len() + MIN_HELP_WIDTH)),
+            None => vec![line],
+        };
+        for line in lines {
+            help.push('\n');
+            help.push_str(&line);
+        }
+    }
+    Some(help)
+}
+
+/// Returns the name the program was run with, without its directory.
+fn program_name() -> String {
+    let program = env::args().next().unwrap_or_default();
+    Path::new(&program).file_name()
+                       .and_then(|name| name.to_str())
+                       .unwrap_or("")
+                       .to_owned()
+}
+
+/// Writes the help message of `generate_help` into a stream (e.g. stdout or
+/// a pager), followed by a newline.
+pub fn write_help_io<W: io::Write>(parser: &Parser, out: &mut W) -> io::Result<()> {
+    let mut writer = IoWriter { inner: out, error: None };
+    let result = write_help(parser, &mut writer)
+        .and_then(|_| fmt::Write::write_char(&mut writer, '\n'));
+    match (result, writer.error) {
+        (Ok(()), _) => Ok(()),
+        (Err(_), Some(err)) => Err(err),
+        (Err(_), None) => Err(io::Error::other("formatting the help failed")),
+    }
+}
+
+/// Returns the first flag of an interrupt named `help`, if one is defined.
+fn help_flag(parser: &Parser) -> Option<String> {
+    parser.args()
+        .filter_map(|&arg| match arg::internal_get_raw(arg) {
+            arg::ArgType::Interrupt(name @ OptName::Normal("help")) |
+            arg::ArgType::Interrupt(name @ OptName::NormalAndShort("help", _)) => Some(name),
+            _ => None,
+        })
+        .next()
+        .map(|name| format!("--{}", name.name()))
+}
+
+/// Writes the error to stderr along with the usage line of the parser and a
+/// pointer to its `--help` interrupt (if defined).
+///
+/// The report is made by the formatter of `Parser::set_error_formatter`, or
+/// else by `format_error`.
+pub fn report_error(error: &ParseError, parser: &Parser) {
+    let mut report = match parser.error_formatter() {
+        Some(formatter) => formatter(error, parser),
+        None => format_error(error, parser),
+    };
+    if !report.ends_with('\n') {
+        report.push('\n');
     }
-    if help_message.ends_with('\n') {
-        help_message.pop();
+    // There is nowhere left to report a failing stderr.
+    let _ = io::stderr().write_all(report.as_bytes());
+}
+
+/// Returns the report of `report_error` for the error: the message with the
+/// usage line of the parser and a pointer to its `--help` interrupt (if
+/// defined).
+///
+/// The usage line is the one set with `Parser::set_usage`, or else the one
+/// of `generate_usage`.
+///
+/// The report is colored according to `Parser::set_color`.
+pub fn format_error(error: &ParseError, parser: &Parser) -> String {
+    let strings = parser.strings();
+    let use_color = parser.color().for_stderr();
+
+    let mut report = format!("{}: {}\n",
+                             color::paint(strings.error_label, Style::Error, use_color),
+                             error.message(strings));
+    let usage = match parser.usage() {
+        Some(usage) => Cow::Borrowed(usage),
+        None => Cow::Owned(generate_usage(parser, &program_name())),
+    };
+    report.push('\n');
+    report.push_str(&usage);
+    report.push('\n');
+    if let Some(flag) = help_flag(parser) {
+        let flag = color::paint(&flag, Style::Emphasis, use_color);
+        report.push('\n');
+        report.push_str(&strings::fill(strings.help_hint, &[("flag", &flag)]));
+        report.push('\n');
     }
-    help_message
+    report
+}
+
+/// Returns the short flag of the group (like `-vx`) that the parser does not
+/// know, or else its last one.
+fn unknown_short(arg: &str, parser: &Parser) -> char {
+    let flags = arg.trim_start_matches('-');
+    let flags = flags.split('=').next().unwrap_or(flags);
+    flags.chars()
+         .find(|&ch| !parser.args().any(|arg| arg.short() == Some(ch)))
+         .or_else(|| flags.chars().last())
+         .unwrap_or('-')
+}
+
+/// Formats the error like the messages of GNU getopt and coreutils, for
+/// drop-in replacements of tools whose output is read by scripts:
+///
+/// ```text
+/// prog: unrecognized option '--foo'
+/// prog: option requires an argument -- 'x'
+/// Try 'prog --help' for more information.
+/// ```
+///
+/// Give it to `Parser::set_error_formatter` to make `report_error` use it. The
+/// messages are the ones of GNU, so they are not colored and do not come
+/// from `Parser::set_strings`, except for errors that GNU getopt does not
+/// report.
+pub fn format_gnu_error(error: &ParseError, parser: &Parser) -> String {
+    use parser::ParseError::*;
+    let program = program_name();
+    let message = match *error {
+        UnknownOptionalArgument { arg, .. } if arg.starts_with("--") => {
+            format!("unrecognized option '{}'", arg)
+        }
+        UnknownOptionalArgument { arg, .. } => {
+            format!("invalid option -- '{}'", unknown_short(arg, parser))
+        }
+        MissingParameter { arg } if arg.starts_with("--") => {
+            format!("option '{}' requires an argument", arg)
+        }
+        MissingParameter { arg } => {
+            format!("option requires an argument -- '{}'", arg.chars().last().unwrap_or('-'))
+        }
+        UnexpectedArgument { arg, .. } if arg.starts_with('-') => {
+            let flag = arg.split('=').next().unwrap_or(arg);
+            format!("option '{}' doesn't allow an argument", flag)
+        }
+        UnexpectedArgument { arg, .. } => format!("extra operand '{}'", arg),
+        MissingPositionalArgument { .. } | MissingTrail { .. } => "missing operand".to_owned(),
+        InvalidChoice { arg, value, choices } => {
+            let mut message = format!("invalid argument '{}' for '{}'\nValid arguments are:",
+                                      value,
+                                      arg);
+            for choice in choices.iter() {
+                message.push_str(&format!("\n  - '{}'", choice));
+            }
+            message
+        }
+        _ => error.message(parser.strings()),
+    };
+    let mut report = format!("{}: {}\n", program, message);
+    if let Some(flag) = help_flag(parser) {
+        report.push_str(&format!("Try '{} {}' for more information.\n", program, flag));
+    }
+    report
 }
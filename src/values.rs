@@ -0,0 +1,264 @@
+//! Parsers for values written the way people write them, such as durations
+//! (`30s`, `1h30m`), sizes (`10MiB`), percentages (`50%`) and network
+//! addresses (`127.0.0.1:8080`, `example.com:443`).
+//!
+//! The types implement `FromValue`, so they can be used with the `set_type`
+//! of the argument builders (like `OptSingle::set_type`) and
+//! `ParsedArgs::single_as`:
+//!
+//! ```text
+//! let timeout = Arg::named("timeout").single().set_type::<Duration>();
+//! ...
+//! let timeout: Option<Duration> = args.single_as("timeout")?;
+//! ```
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use typed::FromValue;
+
+/// The duration units and their length in nanoseconds.
+const DURATION_UNITS: &[(&[&str], f64)] =
+    &[(&["ns", "nsec", "nanosecond", "nanoseconds"], 1.0),
+      (&["us", "µs", "usec", "microsecond", "microseconds"], 1e3),
+      (&["ms", "msec", "millisecond", "milliseconds"], 1e6),
+      (&["s", "sec", "secs", "second", "seconds"], 1e9),
+      (&["m", "min", "mins", "minute", "minutes"], 60e9),
+      (&["h", "hr", "hrs", "hour", "hours"], 3600e9),
+      (&["d", "day", "days"], 86400e9),
+      (&["w", "week", "weeks"], 604800e9)];
+
+/// The size units (in lowercase) and their size in bytes.
+const SIZE_UNITS: &[(&[&str], f64)] = &[(&["", "b"], 1.0),
+                                        (&["k", "kb"], 1e3),
+                                        (&["ki", "kib"], 1024.0),
+                                        (&["m", "mb"], 1e6),
+                                        (&["mi", "mib"], 1048576.0),
+                                        (&["g", "gb"], 1e9),
+                                        (&["gi", "gib"], 1073741824.0),
+                                        (&["t", "tb"], 1e12),
+                                        (&["ti", "tib"], 1099511627776.0),
+                                        (&["p", "pb"], 1e15),
+                                        (&["pi", "pib"], 1125899906842624.0)];
+
+/// Splits the text into a leading non-negative number and the rest.
+fn split_number(text: &str) -> Result<(f64, &str), String> {
+    let end = text.find(|ch: char| !ch.is_ascii_digit() && ch != '.').unwrap_or(text.len());
+    match text[..end].parse::<f64>() {
+        Ok(number) => Ok((number, &text[end..])),
+        Err(_) => Err(format!("expected a number at '{}'", text)),
+    }
+}
+
+/// Parses a duration like `30s`, `1.5h` or `1h30m`.
+///
+/// A number without a unit is a number of seconds. The units are `ns`, `us`,
+/// `ms`, `s`, `m`, `h`, `d` and `w`, or longer names like `min` or `hours`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("expected a duration like '30s' or '5m'".to_owned());
+    }
+    if let Ok(seconds) = text.parse::<f64>() {
+        return to_duration(seconds * 1e9);
+    }
+
+    let mut nanos = 0.0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest.trim_start())?;
+        let after = after.trim_start();
+        let end = after.find(|ch: char| !ch.is_alphabetic()).unwrap_or(after.len());
+        let unit = &after[..end];
+        let scale = match DURATION_UNITS.iter().find(|&&(names, _)| names.contains(&unit)) {
+            Some(&(_, scale)) => scale,
+            None if unit.is_empty() => return Err(format!("missing the unit after '{}'", number)),
+            None => return Err(format!("unknown unit '{}' (use eg. 'ms', 's', 'm' or 'h')", unit)),
+        };
+        nanos += number * scale;
+        rest = &after[end..];
+    }
+    to_duration(nanos)
+}
+
+fn to_duration(nanos: f64) -> Result<Duration, String> {
+    const NANOS_PER_SEC: f64 = 1e9;
+    if !nanos.is_finite() || nanos < 0.0 || nanos / NANOS_PER_SEC >= u64::MAX as f64 {
+        return Err("the duration is out of range".to_owned());
+    }
+    let nanos = nanos.round();
+    let secs = (nanos / NANOS_PER_SEC).floor();
+    Ok(Duration::new(secs as u64, (nanos - secs * NANOS_PER_SEC) as u32))
+}
+
+/// Parses a size in bytes like `512`, `10MB` or `1.5GiB`.
+///
+/// Units are case-insensitive: `k`, `m`, `g`, `t` and `p` (optionally with a
+/// `b`) are powers of 1000, and `ki`, `mi`, etc. (optionally with a `b`) are
+/// powers of 1024.
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let (number, unit) = split_number(text)?;
+    let unit = unit.trim().to_lowercase();
+    let scale = match SIZE_UNITS.iter().find(|&&(names, _)| names.contains(&unit.as_str())) {
+        Some(&(_, scale)) => scale,
+        None => return Err(format!("unknown unit '{}' (use eg. 'KB', 'MiB' or 'GB')", unit)),
+    };
+    let bytes = (number * scale).round();
+    if bytes >= u64::MAX as f64 {
+        return Err("the size is out of range".to_owned());
+    }
+    Ok(bytes as u64)
+}
+
+/// Parses a percentage like `50%` or `12.5`, and returns the fraction (`0.5`
+/// for `50%`).
+///
+/// The percent sign is optional. Percentages above 100 are accepted.
+pub fn parse_percentage(text: &str) -> Result<f64, String> {
+    let text = text.trim();
+    let number = text.strip_suffix('%').unwrap_or(text).trim_end();
+    match number.parse::<f64>() {
+        Ok(percent) if percent.is_finite() && percent >= 0.0 => Ok(percent / 100.0),
+        Ok(_) => Err("the percentage is out of range".to_owned()),
+        Err(_) => Err("expected a percentage like '50%'".to_owned()),
+    }
+}
+
+/// Parses an IPv4 or IPv6 address like `127.0.0.1` or `::1`.
+pub fn parse_ip_addr(text: &str) -> Result<IpAddr, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| "expected an IP address like 127.0.0.1 or ::1".to_owned())
+}
+
+/// Parses an IP address with a port like `127.0.0.1:8080` or `[::1]:8080`.
+pub fn parse_socket_addr(text: &str) -> Result<SocketAddr, String> {
+    let text = text.trim();
+    text.parse().map_err(|_| {
+        if text.parse::<IpAddr>().is_ok() {
+            format!("missing the port (eg. '{}:8080')", text)
+        } else {
+            "expected IP:PORT like 127.0.0.1:8080 or [::1]:8080".to_owned()
+        }
+    })
+}
+
+/// Parses a host name or IP address with a port like `example.com:443`,
+/// `127.0.0.1:8080` or `[::1]:8080`.
+///
+/// A leading scheme like `https://` and a trailing `/` are ignored. The host
+/// is not resolved.
+pub fn parse_host_port(text: &str) -> Result<HostPort, String> {
+    let text = text.trim();
+    let text = match text.find("://") {
+        Some(index) => &text[index + 3..],
+        None => text,
+    };
+    let text = text.strip_suffix('/').unwrap_or(text);
+
+    let (host, port) = match text.rfind(':') {
+        Some(index) => (&text[..index], &text[index + 1..]),
+        None => return Err("expected HOST:PORT".to_owned()),
+    };
+    let host = if let Some(inner) = host.strip_prefix('[') {
+        match inner.strip_suffix(']') {
+            Some(inner) if inner.parse::<Ipv6Addr>().is_ok() => inner,
+            _ => return Err(format!("invalid IPv6 address '{}'", host)),
+        }
+    } else if host.contains(':') {
+        return Err("expected HOST:PORT (write IPv6 addresses in brackets, like [::1]:8080)"
+                       .to_owned());
+    } else {
+        host
+    };
+    if host.is_empty() {
+        return Err("missing the host in HOST:PORT".to_owned());
+    }
+    if let Some(ch) = host.chars().find(|&ch| !ch.is_alphanumeric() && !".-_:".contains(ch)) {
+        return Err(format!("invalid character '{}' in the host '{}'", ch, host));
+    }
+    let port = port.parse()
+                   .map_err(|_| format!("invalid port '{}' (expected a number up to 65535)", port))?;
+    Ok(HostPort {
+        host: host.to_owned(),
+        port,
+    })
+}
+
+/// A host name or IP address with a port, parsed with `parse_host_port`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HostPort {
+    /// The host name or IP address (without brackets).
+    pub host: String,
+    /// The port.
+    pub port: u16,
+}
+
+impl fmt::Display for HostPort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl FromValue for HostPort {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_host_port(value)
+    }
+}
+
+impl FromValue for IpAddr {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_ip_addr(value)
+    }
+}
+
+impl FromValue for Ipv4Addr {
+    fn from_value(value: &str) -> Result<Self, String> {
+        value.trim().parse().map_err(|_| "expected an IPv4 address like 127.0.0.1".to_owned())
+    }
+}
+
+impl FromValue for Ipv6Addr {
+    fn from_value(value: &str) -> Result<Self, String> {
+        value.trim().parse().map_err(|_| "expected an IPv6 address like ::1".to_owned())
+    }
+}
+
+impl FromValue for SocketAddr {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_socket_addr(value)
+    }
+}
+
+impl FromValue for Duration {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_duration(value)
+    }
+}
+
+/// A size in bytes, parsed with `parse_size`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl FromValue for ByteSize {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_size(value).map(ByteSize)
+    }
+}
+
+/// A percentage as a fraction (`0.5` for `50%`), parsed with
+/// `parse_percentage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Percentage(pub f64);
+
+impl FromValue for Percentage {
+    fn from_value(value: &str) -> Result<Self, String> {
+        parse_percentage(value).map(Percentage)
+    }
+}
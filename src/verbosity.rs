@@ -0,0 +1,53 @@
+//! Standard verbosity semantics for tools that log with the `log` crate.
+//!
+//! Define a counted `-v` switch and a `-q` switch, and turn them into the
+//! level of the logger:
+//!
+//! ```text
+//! parser.define(&[Arg::named_and_short("verbose", 'v').count(),
+//!                 Arg::named_and_short("quiet", 'q').switch()])?;
+//! ...
+//! let args = ParsedArgs::from_parse(parse)?;
+//! argonaut::verbosity::init_logger(args.log_level())?;
+//! ```
+
+use log::LevelFilter;
+#[cfg(feature = "env_logger")]
+use log::SetLoggerError;
+
+use parsed::ParsedArgs;
+
+/// Returns the log level for the number of `-v` flags and whether `-q` was
+/// given.
+///
+/// Warnings and errors are shown by default, `-v` adds info, `-vv` debug and
+/// `-vvv` (or more) trace messages. `-q` leaves only errors, whatever the
+/// verbosity.
+pub fn log_level(verbose: usize, quiet: bool) -> LevelFilter {
+    if quiet {
+        return LevelFilter::Error;
+    }
+    match verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Initializes `env_logger` with the given level.
+///
+/// `RUST_LOG` still takes precedence, so users can pick the level of
+/// single modules. Fails if a logger was already set.
+#[cfg(feature = "env_logger")]
+pub fn init_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    ::env_logger::Builder::new().filter_level(level).parse_default_env().try_init()
+}
+
+impl ParsedArgs {
+    /// Returns the log level for the `verbose` and `quiet` switches, as
+    /// described by `verbosity::log_level`.
+    pub fn log_level(&self) -> LevelFilter {
+        log_level(self.count("verbose"), self.is_present("quiet"))
+    }
+}
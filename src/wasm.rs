@@ -0,0 +1,124 @@
+//! Bindings for running a parser in the browser with `wasm-bindgen`, e.g. for
+//! a playground of the command line of a tool in its documentation.
+//!
+//! The parser is described by a JSON spec (see `SpecDocument`), which is
+//! given to each function along with a command line to split like a shell
+//! would. A crate built with `wasm-pack` that depends on argonaut with the
+//! `wasm` feature exports them:
+//!
+//! ```text
+//! import init, { parse, help } from "./pkg/playground.js";
+//!
+//! await init();
+//! const result = JSON.parse(parse(spec, "-v --out 'my file.txt' input"));
+//! // { "args": { "verbose": true, "out": "my file.txt", "input": "input" },
+//! //   "unknown": [] }, or { "error": "..." } if the parse failed
+//! ```
+//!
+//! An invalid spec or command line throws an error with the reason.
+
+use serde_json::{self, Map, Value as Json};
+use wasm_bindgen::prelude::*;
+
+use parsed::{ParsedArgs, Value};
+use parser::Parser;
+use spec::SpecDocument;
+use utils::generate_help;
+
+/// Reads the spec of the parser from its JSON text.
+fn read_spec(spec: &str) -> Result<SpecDocument, String> {
+    serde_json::from_str(spec).map_err(|err| format!("invalid spec: {}", err))
+}
+
+/// Splits a command line into arguments at whitespace, keeping the text
+/// within single or double quotes together. A backslash escapes the next
+/// character, except within single quotes.
+fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // Whether there is an argument, even an empty one like ''
+    let mut started = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match (quote, ch) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => current.push(ch),
+            (_, '\\') => {
+                current.extend(chars.next());
+                started = true;
+            }
+            (Some(_), _) => current.push(ch),
+            (None, '\'') | (None, '"') => {
+                quote = Some(ch);
+                started = true;
+            }
+            (None, _) if ch.is_whitespace() => {
+                if started {
+                    args.push(current.split_off(0));
+                    started = false;
+                }
+            }
+            (None, _) => {
+                current.push(ch);
+                started = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote in the command line".to_owned());
+    }
+    if started {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Returns the collected arguments as a JSON object.
+fn to_json(parsed: &ParsedArgs) -> Json {
+    let mut args = Map::new();
+    for (name, value) in parsed {
+        let value = match *value {
+            Value::Switch => Json::Bool(true),
+            Value::Off => Json::Bool(false),
+            Value::Count(count) => Json::from(count),
+            Value::Single(ref value) => Json::from(value.as_str()),
+            Value::Multiple(ref values) => Json::from(values.clone()),
+        };
+        args.insert(name.to_owned(), value);
+    }
+    let mut result = Map::new();
+    result.insert("args".to_owned(), Json::Object(args));
+    result.insert("unknown".to_owned(), Json::from(parsed.unknown().to_vec()));
+    Json::Object(result)
+}
+
+/// Parses the command line with the parser of the spec, and returns the
+/// collected arguments as JSON (see the module documentation).
+#[wasm_bindgen]
+pub fn parse(spec: &str, command_line: &str) -> Result<String, JsError> {
+    let spec = read_spec(spec).map_err(|err| JsError::new(&err))?;
+    let parser = Parser::from_spec(&spec).map_err(|err| JsError::new(&err))?;
+    let args = split_command_line(command_line).map_err(|err| JsError::new(&err))?;
+    let result = match ParsedArgs::from_parse(parser.parse(&args)) {
+        Ok(parsed) => to_json(&parsed),
+        Err(err) => {
+            let mut error = Map::new();
+            error.insert("error".to_owned(), Json::from(err.message(parser.strings())));
+            Json::Object(error)
+        }
+    };
+    Ok(result.to_string())
+}
+
+/// Returns the help message of the parser of the spec, after its usage line
+/// if it has one.
+#[wasm_bindgen]
+pub fn help(spec: &str) -> Result<String, JsError> {
+    let spec = read_spec(spec).map_err(|err| JsError::new(&err))?;
+    let parser = Parser::from_spec(&spec).map_err(|err| JsError::new(&err))?;
+    Ok(match parser.usage() {
+        Some(usage) => format!("{}\n\n{}", usage, generate_help(&parser)),
+        None => generate_help(&parser),
+    })
+}
@@ -0,0 +1,144 @@
+//! The C interface, called like a C program would.
+
+#![cfg(feature = "capi")]
+
+extern crate argonaut;
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use argonaut::capi::*;
+
+const SPEC: &str = r#"{"args": [
+    {"name": "out", "short": "o", "kind": "single"},
+    {"name": "tag", "kind": "one_or_more"},
+    {"name": "verbose", "short": "v", "kind": "count"},
+    {"name": "dry-run", "kind": "switch"}]}"#;
+
+fn c_string(text: &str) -> CString {
+    CString::new(text).unwrap()
+}
+
+/// Returns the string behind the pointer, which must not be null.
+unsafe fn text<'a>(text: *const c_char) -> &'a str {
+    assert!(!text.is_null());
+    CStr::from_ptr(text).to_str().unwrap()
+}
+
+fn parser() -> *mut ArgonautParser {
+    let spec = c_string(SPEC);
+    let parser = unsafe { argonaut_parser_new(spec.as_ptr(), ptr::null_mut()) };
+    assert!(!parser.is_null());
+    parser
+}
+
+/// Parses the arguments like `main` would give them (the results do not borrow
+/// them).
+fn parse(parser: *const ArgonautParser, args: &[&str]) -> *mut ArgonautArgs {
+    let args: Vec<CString> = args.iter().map(|arg| c_string(arg)).collect();
+    let argv: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+    unsafe { argonaut_parse(parser, argv.len() as _, argv.as_ptr()) }
+}
+
+#[test]
+fn invalid_spec_sets_the_error() {
+    let spec = c_string(r#"{"args": [{"name": "x", "kind": "nope"}]}"#);
+    let mut error = ptr::null_mut();
+    unsafe {
+        assert!(argonaut_parser_new(spec.as_ptr(), &mut error).is_null());
+        assert!(text(error).starts_with("invalid spec: "));
+        argonaut_string_free(error);
+
+        // The arguments are checked as well, and the error may be left out
+        let spec = c_string(r#"{"args": [{"name": "x", "kind": "switch", "param": "X"}]}"#);
+        let mut error = ptr::null_mut();
+        assert!(argonaut_parser_new(spec.as_ptr(), &mut error).is_null());
+        assert_eq!(text(error), "Switches and interrupts take no parameter ('x')");
+        argonaut_string_free(error);
+        assert!(argonaut_parser_new(spec.as_ptr(), ptr::null_mut()).is_null());
+    }
+}
+
+#[test]
+fn values_are_read() {
+    let parser = parser();
+    let args = parse(parser, &["-o", "file", "--tag", "a", "b", "-vv", "--dry-run"]);
+    unsafe {
+        assert!(argonaut_args_error(args).is_null());
+        assert_eq!(text(argonaut_args_single(args, c_string("out").as_ptr())), "file");
+        let tag = c_string("tag");
+        assert_eq!(argonaut_args_multiple_len(args, tag.as_ptr()), 2);
+        assert_eq!(text(argonaut_args_multiple_at(args, tag.as_ptr(), 0)), "a");
+        assert_eq!(text(argonaut_args_multiple_at(args, tag.as_ptr(), 1)), "b");
+        assert!(argonaut_args_multiple_at(args, tag.as_ptr(), 2).is_null());
+        assert_eq!(argonaut_args_count(args, c_string("verbose").as_ptr()), 2);
+        assert_eq!(argonaut_args_is_present(args, c_string("dry-run").as_ptr()), 1);
+
+        // Missing arguments and names have no values
+        assert!(argonaut_args_single(args, tag.as_ptr()).is_null());
+        assert!(argonaut_args_single(args, c_string("nope").as_ptr()).is_null());
+        assert!(argonaut_args_single(args, ptr::null()).is_null());
+        assert_eq!(argonaut_args_multiple_len(args, c_string("out").as_ptr()), 1);
+        assert_eq!(argonaut_args_is_present(args, ptr::null()), 0);
+        argonaut_args_free(args);
+
+        let args = parse(parser, &[]);
+        assert!(argonaut_args_error(args).is_null());
+        assert_eq!(argonaut_args_count(args, c_string("verbose").as_ptr()), 0);
+        assert_eq!(argonaut_args_is_present(args, c_string("dry-run").as_ptr()), 0);
+        argonaut_args_free(args);
+        argonaut_parser_free(parser);
+    }
+}
+
+#[test]
+fn errors_are_in_the_results() {
+    let parser = parser();
+    unsafe {
+        let args = parse(parser, &["--nope"]);
+        assert_eq!(text(argonaut_args_error(args)), "unknown optional argument '--nope'");
+        assert!(argonaut_args_single(args, c_string("out").as_ptr()).is_null());
+        assert_eq!(argonaut_args_count(args, c_string("verbose").as_ptr()), 0);
+        argonaut_args_free(args);
+
+        let args = parse(ptr::null(), &["-v"]);
+        assert_eq!(text(argonaut_args_error(args)), "no parser was given");
+        argonaut_args_free(args);
+
+        let args = argonaut_parse(parser, 1, ptr::null());
+        assert_eq!(text(argonaut_args_error(args)), "no arguments were given");
+        argonaut_args_free(args);
+
+        let invalid = [b'-', b'o', 0xff, 0];
+        let flag = c_string("-v");
+        let argv = [flag.as_ptr(), invalid.as_ptr() as *const c_char];
+        let args = argonaut_parse(parser, 2, argv.as_ptr());
+        assert_eq!(text(argonaut_args_error(args)), "the argument 2 is not a UTF-8 string");
+        argonaut_args_free(args);
+        argonaut_parser_free(parser);
+    }
+}
+
+#[test]
+fn help_is_owned_by_the_caller() {
+    let parser = parser();
+    unsafe {
+        let help = argonaut_parser_help(parser);
+        assert!(text(help).contains("--dry-run"));
+        argonaut_string_free(help);
+        assert!(argonaut_parser_help(ptr::null()).is_null());
+        argonaut_parser_free(parser);
+    }
+}
+
+#[test]
+fn null_is_freed() {
+    unsafe {
+        argonaut_parser_free(ptr::null_mut());
+        argonaut_args_free(ptr::null_mut());
+        argonaut_string_free(ptr::null_mut());
+        assert!(argonaut_args_error(ptr::null()).is_null());
+        assert_eq!(argonaut_args_multiple_len(ptr::null(), c_string("tag").as_ptr()), 0);
+    }
+}
@@ -0,0 +1,41 @@
+//! Options whose values are split at a delimiter, given with and without `=`.
+
+extern crate argonaut;
+
+use argonaut::{Arg, ParsedArgs, Parser};
+
+fn parse(args: &[&str]) -> Vec<String> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::named_and_short("add", 'a').one_or_more().set_delimiter(',').into(),
+                    Arg::optional_trail("files").into()])
+          .unwrap();
+    let parsed = ParsedArgs::from_parse(parser.parse(args)).unwrap();
+    parsed.multiple("add").unwrap_or(&[]).to_vec()
+}
+
+#[test]
+fn attached_values_are_split() {
+    assert_eq!(parse(&["--add=1,2,3"]), ["1", "2", "3"]);
+    assert_eq!(parse(&["-a=1,2"]), ["1", "2"]);
+}
+
+#[test]
+fn separate_values_are_split() {
+    assert_eq!(parse(&["--add", "1,2", "3"]), ["1", "2", "3"]);
+    assert_eq!(parse(&["-a", "1,2"]), ["1", "2"]);
+}
+
+#[test]
+fn empty_segments_are_kept() {
+    assert_eq!(parse(&["--add=1,,3"]), ["1", "", "3"]);
+    assert_eq!(parse(&["--add=,"]), ["", ""]);
+    assert_eq!(parse(&["--add="]), [""]);
+}
+
+#[test]
+fn quotes_do_not_protect_the_delimiter() {
+    // The shell gives `--add="a,b"` without the quotes
+    assert_eq!(parse(&["--add=a,b"]), ["a", "b"]);
+    // Quotes that reach the program are part of the values
+    assert_eq!(parse(&["--add=\"a,b\""]), ["\"a", "b\""]);
+}
@@ -0,0 +1,131 @@
+//! The derive macros, from the parse of the arguments to the conversion of
+//! their values.
+
+#![cfg(feature = "derive")]
+
+extern crate argonaut;
+extern crate trybuild;
+
+use argonaut::{Choices, FromArgs, ParsedArgs, Subcommand};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Choices)]
+enum Mode {
+    FastMode,
+    #[choice(rename = "slow")]
+    SlowAndSteady,
+}
+
+#[derive(Debug, PartialEq, FromArgs)]
+struct Logging {
+    /// Prints more details
+    #[arg(short = 'v', count)]
+    verbose: u8,
+}
+
+#[derive(Debug, PartialEq, FromArgs)]
+struct AddArgs {
+    /// The files to add
+    #[arg(positional)]
+    files: Vec<String>,
+    dry_run: bool,
+    #[arg(long = "how", choices, default = "fast-mode")]
+    mode: Mode,
+    #[arg(short = 'n', param = "N")]
+    max_count: Option<u32>,
+    #[arg(zero_or_more)]
+    tags: Vec<String>,
+    #[arg(flatten)]
+    logging: Logging,
+}
+
+#[derive(Debug, PartialEq, FromArgs)]
+struct Clashing {
+    verbose: bool,
+    #[arg(flatten)]
+    logging: Logging,
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+enum Command {
+    /// Adds files to the index
+    Add(AddArgs),
+    /// Shows the state
+    /// of the index
+    Status,
+    #[command(rename = "rm")]
+    Remove,
+}
+
+fn from_argv<T: FromArgs>(argv: &[&str]) -> Result<T, String> {
+    let parser = T::parser()?;
+    let args = ParsedArgs::from_parse(parser.parse(argv)).map_err(|err| err.to_string())?;
+    T::from_args(&args)
+}
+
+#[test]
+fn choices_are_kebab_case_or_renamed() {
+    assert_eq!(Mode::variants(), ["fast-mode", "slow"]);
+    assert_eq!(Mode::from_str("slow"), Some(Mode::SlowAndSteady));
+    assert_eq!(Mode::from_str("slow-and-steady"), None);
+}
+
+#[test]
+fn fields_are_converted() {
+    let args = from_argv::<AddArgs>(&["a", "--dry-run", "-vv", "--how", "slow", "-n", "3", "b"]);
+    assert_eq!(args,
+               Ok(AddArgs {
+                   files: vec!["a".to_owned(), "b".to_owned()],
+                   dry_run: true,
+                   mode: Mode::SlowAndSteady,
+                   max_count: Some(3),
+                   tags: Vec::new(),
+                   logging: Logging { verbose: 2 },
+               }));
+
+    let args = from_argv::<AddArgs>(&["--tags"]).unwrap();
+    assert_eq!((args.files.len(), args.mode, args.max_count), (0, Mode::FastMode, None));
+    assert!(from_argv::<AddArgs>(&["--how", "quick"]).unwrap_err().contains("quick"));
+    assert!(from_argv::<AddArgs>(&["-n", "many"]).is_err());
+}
+
+#[test]
+fn counts_do_not_overflow() {
+    let argv = vec!["-v"; 256];
+    assert_eq!(from_argv::<Logging>(&argv),
+               Err("the argument 'verbose' was given too many times".to_owned()));
+}
+
+#[test]
+fn flattened_names_must_differ() {
+    assert_eq!(Clashing::parser().err(),
+               Some("The flag '--verbose' is already defined".to_owned()));
+}
+
+#[test]
+fn commands_are_parsed() {
+    assert_eq!(Command::from_argv(&["status"]), Ok(Command::Status));
+    assert_eq!(Command::from_argv(&["rm"]), Ok(Command::Remove));
+    match Command::from_argv(&["add", "file", "-v"]) {
+        Ok(Command::Add(args)) => {
+            assert_eq!((args.files, args.logging.verbose), (vec!["file".to_owned()], 1))
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(Command::from_argv(&["remove"]),
+               Err("unknown command 'remove' (choose from: add, status, rm)".to_owned()));
+    assert_eq!(Command::from_argv::<&str>(&[]),
+               Err("a command is required (choose from: add, status, rm)".to_owned()));
+}
+
+#[test]
+fn commands_are_listed_in_the_help() {
+    assert_eq!(Command::help(),
+               "Commands:\n  add       Adds files to the index\n  status    Shows the state of the \
+                index\n  rm\n");
+}
+
+#[test]
+fn invalid_definitions_do_not_compile() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/derive/*.rs");
+}
@@ -0,0 +1,52 @@
+#[macro_use]
+extern crate argonaut;
+
+#[derive(FromArgs)]
+struct SwitchParam {
+    #[arg(param = "N")]
+    verbose: bool,
+}
+
+#[derive(FromArgs)]
+struct CountDefault {
+    #[arg(count, default = "1")]
+    verbose: u8,
+}
+
+#[derive(FromArgs)]
+struct PositionalFlag {
+    #[arg(positional, short = 'i')]
+    input: String,
+}
+
+#[derive(FromArgs)]
+struct PositionalDefault {
+    #[arg(positional, env = "INPUT")]
+    input: String,
+}
+
+#[derive(FromArgs)]
+struct CountNotInteger {
+    #[arg(count)]
+    verbose: Option<u8>,
+}
+
+#[derive(FromArgs)]
+struct RequiredNotVec {
+    #[arg(required)]
+    output: String,
+}
+
+#[derive(FromArgs)]
+struct FlattenSettings {
+    #[arg(flatten, short = 'x')]
+    other: SwitchParam,
+}
+
+#[derive(FromArgs)]
+struct UnknownSetting {
+    #[arg(hidden)]
+    output: String,
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#[macro_use]
+extern crate argonaut;
+
+#[derive(Choices)]
+enum Mode {
+    Fast,
+    #[choice(rename = "fast")]
+    Quick,
+}
+
+#[derive(Choices)]
+enum Level {
+    Low(u8),
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Add,
+    #[command(rename = "add")]
+    Append,
+}
+
+#[derive(Subcommand)]
+enum Pair {
+    Both(u8, u8),
+}
+
+fn main() {}
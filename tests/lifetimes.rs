@@ -0,0 +1,42 @@
+//! Parses of arguments that live shorter than the definitions.
+
+extern crate argonaut;
+
+use argonaut::{Arg, ParseError, Parser, StructuredArgument};
+
+fn parser() -> Parser<'static> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::positional("input").into(), Arg::named("verbose").switch().into()])
+          .unwrap();
+    parser
+}
+
+/// Returns the names of the arguments in the command line, which outlive it.
+fn given_names(parser: &Parser<'static>, line: &str) -> Vec<&'static str> {
+    let args: Vec<String> = line.split_whitespace().map(|arg| arg.to_owned()).collect();
+    parser.parse(&args)
+          .filter_map(|item| match item {
+              Ok(StructuredArgument::Positional { name, .. }) |
+              Ok(StructuredArgument::Switch { name, .. }) => Some(name),
+              _ => None,
+          })
+          .collect()
+}
+
+/// Returns the name of the missing argument, which outlives the command line.
+fn missing(parser: &Parser<'static>, line: &str) -> Option<&'static str> {
+    let args: Vec<String> = line.split_whitespace().map(|arg| arg.to_owned()).collect();
+    let mut parse = parser.parse(&args);
+    parse.find_map(|item| match item {
+        Err(ParseError::MissingPositionalArgument { arg }) => Some(arg),
+        _ => None,
+    })
+}
+
+#[test]
+fn names_outlive_the_arguments() {
+    let parser = parser();
+    assert_eq!(given_names(&parser, "file --verbose"), ["input", "verbose"]);
+    assert_eq!(missing(&parser, "--verbose"), Some("input"));
+    assert_eq!(missing(&parser, "file"), None);
+}
@@ -0,0 +1,108 @@
+//! Parses of random command lines, which must end in arguments or errors but
+//! never in a panic.
+
+extern crate argonaut;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use argonaut::{Arg, ParsedArgs, Parser};
+
+/// Arguments that are tricky to lex, or that use the definitions below.
+const TOKENS: &[&str] = &["", "-", "--", "-=", "--=", "---", "=", "-==", "--==", "-é", "-éx",
+                          "-xé", "-ö=v", "-ö", "é", "日本", "-日", "--日=本", "a=b", "-h",
+                          "--help", "--help=topic", "-v", "-vvv", "-v=", "--verbose",
+                          "--verbose=2", "--dry-run", "--dry-run=1", "--no-dry-run", "-o",
+                          "-ox", "-o=", "--output", "--output=", "--output=5", "-x",
+                          "--exclude", "--exclude=a,,b", "--exclude=,", "--extra", "--level",
+                          "-7", "-42", "--cmd", "--", "\u{0}", "x", "input", "1,2", "-5",
+                          "--colour", "--color=always"];
+
+/// The characters of the random arguments.
+const CHARS: &[char] = &['-', '-', '=', ',', 'a', 'v', 'o', 'x', 'h', '1', 'é', '日', ' '];
+
+/// A small xorshift generator, so that the runs can be repeated.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    fn argument(&mut self) -> String {
+        if self.below(3) > 0 {
+            return TOKENS[self.below(TOKENS.len())].to_owned();
+        }
+        (0..self.below(6)).map(|_| CHARS[self.below(CHARS.len())]).collect()
+    }
+}
+
+/// A parser with an argument of every kind, and most of their settings.
+fn every_kind() -> Parser<'static> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::positional("input").set_choices(&["input", "x", "é"]).into(),
+                    Arg::optional_trail("files").into(),
+                    Arg::named_and_short("help", 'h').interrupt().into(),
+                    Arg::named_and_short("verbose", 'v').count().into(),
+                    Arg::named("dry-run").switch().into(),
+                    Arg::named_and_short("output", 'o').single().range(0..=10).into(),
+                    Arg::named_and_short("exclude", 'x').one_or_more()
+                                                        .set_delimiter(',')
+                                                        .lazy()
+                                                        .into(),
+                    Arg::named("extra").zero_or_more().allow_duplicates().into(),
+                    Arg::named_and_short("ölvl", 'ö').single().set_default("1").into(),
+                    Arg::named("color").single()
+                                       .set_aliases(&["colour"])
+                                       .set_choices(&["always", "never"])
+                                       .into(),
+                    Arg::named("level").digits().into(),
+                    Arg::named("cmd").passalong().stop_at_flags().into(),
+                    Arg::named("").passalong().into()])
+          .unwrap();
+    parser.set_negate_switches(true);
+    parser
+}
+
+/// A parser with a required trail, a number option and lenient settings.
+fn lenient() -> Parser<'static> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::required_trail("files").into(),
+                    Arg::named_and_short("verbose", 'v').switch().into(),
+                    Arg::named_and_short("output", 'o').single().set_number_flag().into(),
+                    Arg::named("exclude").zero_or_more().set_required_if("verbose").into()])
+          .unwrap();
+    parser.set_collect_unknown(true);
+    parser.set_continue_after_unknown(true);
+    parser.set_normalize_long_names(true);
+    parser.set_positionals_after_passalong(true);
+    parser
+}
+
+fn check(parser: &Parser, seed: u64) {
+    let mut random = Random(seed);
+    for _ in 0..20_000 {
+        let args: Vec<String> = (0..random.below(8)).map(|_| random.argument()).collect();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            for _ in parser.parse(&args) {}
+            if let Ok(parsed) = ParsedArgs::from_parse(parser.parse(&args)) {
+                parsed.to_args(parser);
+            }
+        }));
+        if result.is_err() {
+            panic!("the parse of {:?} panicked", args);
+        }
+    }
+}
+
+#[test]
+fn every_kind_never_panics() {
+    check(&every_kind(), 0x9e37_79b9_7f4a_7c15);
+}
+
+#[test]
+fn lenient_never_panics() {
+    check(&lenient(), 0x2545_f491_4f6c_dd1d);
+}
@@ -0,0 +1,60 @@
+//! Short flags that are not ASCII, which take more than one byte.
+
+extern crate argonaut;
+
+use argonaut::{Arg, ParsedArgs, Parser};
+
+fn parser() -> Parser<'static> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::named_and_short("echo", 'é').switch().into(),
+                    Arg::named_and_short("extra", 'x').switch().into(),
+                    Arg::named_and_short("output", 'ö').single().into(),
+                    Arg::optional_trail("files").into()])
+          .unwrap();
+    parser
+}
+
+fn parse(args: &[&str]) -> Result<ParsedArgs, String> {
+    let parser = parser();
+    ParsedArgs::from_parse(parser.parse(args)).map_err(|err| err.to_string())
+}
+
+#[test]
+fn multi_byte_short_switch() {
+    let args = parse(&["-é"]).unwrap();
+    assert!(args.is_present("echo"));
+    assert!(!args.is_present("extra"));
+}
+
+#[test]
+fn multi_byte_short_with_attached_value() {
+    let args = parse(&["-ö=file"]).unwrap();
+    assert_eq!(args.single("output"), Some("file"));
+
+    // The value of a multi-byte short switch is not a value it takes
+    assert!(parse(&["-é=value"]).is_err());
+}
+
+#[test]
+fn grouped_multi_byte_shorts() {
+    let args = parse(&["-éx"]).unwrap();
+    assert!(args.is_present("echo"));
+    assert!(args.is_present("extra"));
+
+    let args = parse(&["-xé"]).unwrap();
+    assert!(args.is_present("echo"));
+    assert!(args.is_present("extra"));
+}
+
+#[test]
+fn multi_byte_short_with_parameter() {
+    let args = parse(&["-ö", "file", "rest"]).unwrap();
+    assert_eq!(args.single("output"), Some("file"));
+    assert_eq!(args.multiple("files"), Some(&["rest".to_owned()][..]));
+}
+
+#[test]
+fn unknown_multi_byte_short() {
+    let err = parse(&["-ü"]).unwrap_err();
+    assert!(err.contains("-ü"), "{}", err);
+}
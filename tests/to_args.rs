@@ -0,0 +1,69 @@
+//! Collected arguments turned back into the arguments that give them.
+
+extern crate argonaut;
+
+use argonaut::{Arg, ParsedArgs, Parser};
+
+fn parser() -> Parser<'static> {
+    let mut parser = Parser::new();
+    // The trail is defined first, but still goes after the positional
+    parser.define(&[Arg::optional_trail("files").into(),
+                    Arg::positional("input").into(),
+                    Arg::named_and_short("verbose", 'v').count().into(),
+                    Arg::named("dry-run").switch().into(),
+                    Arg::named("offset").single().into(),
+                    Arg::named("add").one_or_more().set_delimiter(',').into(),
+                    Arg::named("tag").one_or_more().into(),
+                    Arg::named("extra").zero_or_more().into(),
+                    Arg::named("level").digits().into()])
+          .unwrap();
+    parser.set_negate_switches(true);
+    parser
+}
+
+/// Checks that the arguments give the same values after a round trip, and
+/// returns the arguments of the round trip.
+fn round_trip(args: &[&str]) -> Vec<String> {
+    let parser = parser();
+    let parsed = ParsedArgs::from_parse(parser.parse(args)).unwrap();
+    let again = parsed.to_args(&parser);
+    let reparsed = ParsedArgs::from_parse(parser.parse(&again)).unwrap();
+    assert_eq!(parsed, reparsed, "{:?} became {:?}", args, again);
+    again
+}
+
+#[test]
+fn option_values_are_attached() {
+    assert_eq!(round_trip(&["in", "--offset=-5"]), ["in", "--offset=-5"]);
+    assert_eq!(round_trip(&["in", "--offset", "5"]), ["in", "--offset=5"]);
+    assert_eq!(round_trip(&["in", "--offset="]), ["in", "--offset="]);
+}
+
+#[test]
+fn positionals_that_look_like_flags_follow_a_separator() {
+    assert_eq!(round_trip(&["--", "-x"]), ["--", "-x"]);
+    assert_eq!(round_trip(&["in", "-v", "--", "-a", "b"]), ["in", "--verbose", "--", "-a", "b"]);
+    assert_eq!(round_trip(&["-", "-v"]), ["-", "--verbose"]);
+}
+
+#[test]
+fn trail_follows_the_positional() {
+    assert_eq!(round_trip(&["in", "a", "b", "--dry-run"]), ["in", "a", "b", "--dry-run"]);
+}
+
+#[test]
+fn switches_and_counts() {
+    assert_eq!(round_trip(&["in", "-vvv", "--no-dry-run"]),
+               ["in", "--verbose", "--verbose", "--verbose", "--no-dry-run"]);
+}
+
+#[test]
+fn several_values() {
+    assert_eq!(round_trip(&["in", "--add", "1", "2"]), ["in", "--add=1,2"]);
+    assert_eq!(round_trip(&["in", "--add", "1,2", "3"]), ["in", "--add=1,2,3"]);
+    assert_eq!(round_trip(&["in", "--tag", "a", "b"]), ["in", "--tag", "a", "b"]);
+    assert_eq!(round_trip(&["in", "--tag=-a"]), ["in", "--tag=-a"]);
+    assert_eq!(round_trip(&["in", "--extra"]), ["in", "--extra"]);
+    assert_eq!(round_trip(&["in", "--extra", "x", "--level", "7"]),
+               ["in", "--extra=x", "-7"]);
+}
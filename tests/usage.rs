@@ -0,0 +1,103 @@
+//! Parsers created from usage texts, compared by their definitions written
+//! back in the notation of the usage.
+
+extern crate argonaut;
+
+use argonaut::{Arg, Parser};
+
+/// Writes the definition like the usage element that creates it.
+fn describe(arg: &Arg) -> String {
+    if arg.is_positional() {
+        let name = format!("<{}>", arg.name());
+        return match arg.arity() {
+            (1, Some(1)) => name,
+            (1, None) => format!("{}...", name),
+            _ => format!("[{}...]", name),
+        };
+    }
+    if arg.is_passalong() {
+        return match arg.param() {
+            Some(param) => format!("-- <{}>...", param),
+            None => "--".to_owned(),
+        };
+    }
+    let mut text = match arg.short() {
+        Some(short) => format!("-{}|", short),
+        None => String::new(),
+    };
+    text.push_str("--");
+    text.push_str(arg.name());
+    if let Some(param) = arg.param() {
+        text.push_str(&format!("=<{}>", param));
+    }
+    if arg.arity().1.is_none() || arg.is_counted() {
+        text.push_str("...");
+    }
+    text
+}
+
+fn definitions(usage: &'static str) -> Vec<String> {
+    match Parser::from_usage(usage) {
+        Ok(parser) => parser.args().map(describe).collect(),
+        Err(err) => panic!("{:?} failed: {}", usage, err),
+    }
+}
+
+#[test]
+fn elements_are_defined() {
+    let cases: &[(&str, &[&str])] =
+        &[("Usage: prog", &[]),
+          ("Usage: prog -abc", &["-a|--a", "-b|--b", "-c|--c"]),
+          ("Usage: prog [-v...] [--dry-run]", &["-v|--v...", "--dry-run"]),
+          ("Usage: prog -o|--out=<file>", &["-o|--out=<file>"]),
+          ("Usage: prog --name=<p>...", &["--name=<p>..."]),
+          ("Usage: prog <x>...", &["<x>..."]),
+          ("Usage: prog [<x>...]", &["[<x>...]"]),
+          ("Usage: prog NAME [FILE...]", &["<NAME>", "[<FILE>...]"]),
+          ("Usage: prog <cmd> -- <args>...", &["<cmd>", "-- <args>..."]),
+          ("Usage: prog --", &["--"]),
+          ("Usage: prog [options] (<a> <b>)", &["<a>", "<b>"])];
+    for &(usage, expected) in cases {
+        assert_eq!(definitions(usage), expected, "{:?}", usage);
+    }
+}
+
+#[test]
+fn described_parameter_follows_a_space() {
+    let described = "Usage: prog --out <file> <input>\n\
+                     \n\
+                     Options:\n  \
+                       -o FILE, --out=FILE  Where to write.\n  \
+                       -q                   Prints nothing.";
+    let cases: &[(&str, &[&str])] =
+        &[(described, &["-o|--out=<FILE>", "-q|--q", "<input>"]),
+          ("Usage: prog --out <file> <input>", &["--out", "<file>", "<input>"]),
+          ("Usage: prog -n <count>...\n\nOptions:\n  -n <count>  Repeats.",
+           &["-n|--n=<count>..."])];
+    for &(usage, expected) in cases {
+        assert_eq!(definitions(usage), expected, "{:?}", usage);
+    }
+
+    let parser = Parser::from_usage(described).unwrap();
+    let help: Vec<_> = parser.args().map(|arg| arg.help()).collect();
+    assert_eq!(help, [Some("Where to write."), Some("Prints nothing."), None]);
+    assert_eq!(parser.usage(), Some("Usage: prog --out <file> <input>"));
+}
+
+#[test]
+fn invalid_usage_is_an_error() {
+    let cases = [("Usage: prog [<x>...", "Unbalanced '[' in the usage"),
+                 ("Usage: prog <x>]", "Unbalanced ']' in the usage"),
+                 ("Usage: prog <x>\n       prog -v", "Only a single usage pattern is supported"),
+                 ("Usage: prog [<x>]",
+                  "The positional argument 'x' can only be optional as a trail ('<x>...')"),
+                 ("Usage: prog file", "Unsupported element 'file' in the usage"),
+                 ("Usage: prog\n\nOptions:\n  -ab  Both.", "Invalid short flag '-ab'"),
+                 ("Usage: prog\n\nOptions:\n  --out file  Where.",
+                  "Invalid option description '--out file  Where.'"),
+                 ("Usage: prog <x> <x>",
+                  "A positional argument with the name 'x' has already been added")];
+    for &(usage, expected) in &cases {
+        assert_eq!(Parser::from_usage(usage).err(), Some(expected.to_owned()), "{:?}", usage);
+    }
+}
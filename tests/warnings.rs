@@ -0,0 +1,59 @@
+//! The warnings of a parse, for arguments that are accepted but probably not
+//! what was meant.
+
+extern crate argonaut;
+
+use argonaut::{Arg, ParseWarning, Parser};
+
+fn parser() -> Parser<'static> {
+    let mut parser = Parser::new();
+    parser.define(&[Arg::named("out").single().allow_duplicates().into(),
+                    Arg::named_and_short("verbose", 'v').switch().overrides("quiet").into(),
+                    Arg::named_and_short("quiet", 'q').switch().into(),
+                    Arg::named("tag").one_or_more().allow_duplicates().into(),
+                    Arg::named_and_short("count", 'c').count().into()])
+          .unwrap();
+    parser
+}
+
+/// Returns the messages of the warnings of the parse.
+fn warnings(args: &[&str]) -> Vec<String> {
+    let parser = parser();
+    let mut parse = parser.parse(args);
+    for item in &mut parse {
+        item.unwrap();
+    }
+    parse.warnings().iter().map(|warning| warning.to_string()).collect()
+}
+
+#[test]
+fn repeated_value_is_overridden() {
+    let parser = parser();
+    let args = ["--out", "a", "--out=b"];
+    let mut parse = parser.parse(&args);
+    for item in &mut parse {
+        item.unwrap();
+    }
+    match *parse.warnings() {
+        [ParseWarning::OverriddenValue { arg: "out", previous: "a" }] => {}
+        ref warnings => panic!("unexpected warnings {:?}", warnings),
+    }
+    assert_eq!(warnings(&["--out", "a", "--out", "b", "--out", "c"]),
+               ["'a' is ignored, as '--out' is overridden by a later argument",
+                "'b' is ignored, as '--out' is overridden by a later argument"]);
+}
+
+#[test]
+fn overriding_flag_drops_the_earlier_one() {
+    assert_eq!(warnings(&["-q", "--verbose"]),
+               ["'-q' is ignored, as '--quiet' is overridden by a later argument"]);
+    assert_eq!(warnings(&["-cv", "-q"]),
+               ["'-cv' is ignored, as '--verbose' is overridden by a later argument"]);
+}
+
+#[test]
+fn kept_values_are_not_overridden() {
+    assert!(warnings(&["--out", "a"]).is_empty());
+    assert!(warnings(&["--tag", "a", "--tag", "b"]).is_empty());
+    assert!(warnings(&["-ccc", "-c"]).is_empty());
+}